@@ -47,6 +47,159 @@ pub enum VerityError {
     #[msg("Vault is currently locked by an active listing")]
     VaultLocked,
     
-    #[msg("NFT not in user vault")]
+    #[msg("NFT is no longer in the user vault - use reconcile_vault to recover")]
     NftNotInVault,
+    
+    #[msg("Metadata account is invalid or collection is not verified")]
+    InvalidMetadata,
+    
+    #[msg("Floor price must be greater than zero")]
+    InvalidFloor,
+    
+    #[msg("Only the config authority can perform this action")]
+    UnauthorizedAuthority,
+    
+    #[msg("Invalid fee recipients (1-4 recipients, shares must sum to 10000 bps)")]
+    InvalidFeeRecipients,
+    
+    #[msg("Vault still holds the NFT")]
+    VaultNotEmpty,
+    
+    #[msg("An oracle account is required for this listing")]
+    MissingOracle,
+    
+    #[msg("Oracle price is invalid, stale, or too uncertain")]
+    InvalidOracle,
+    
+    #[msg("Memo exceeds maximum length")]
+    MemoTooLong,
+    
+    #[msg("Oracle account does not match the listing's oracle feed")]
+    InvalidOracleAccount,
+    
+    #[msg("Creator accounts do not match the NFT's metadata creators")]
+    InvalidCreatorAccounts,
+    
+    #[msg("Account version is not supported - migrate the account first")]
+    UnsupportedAccountVersion,
+    
+    #[msg("Accounts can only grow, up to the current layout size")]
+    InvalidAccountSize,
+    
+    #[msg("Buyer does not have enough lamports to cover the price")]
+    InsufficientFunds,
+    
+    #[msg("Token account has an active delegate - revoke it before depositing")]
+    DelegatedTokenAccount,
+    
+    #[msg("Marketplace is in emergency mode - try again once it expires")]
+    EmergencyActive,
+    
+    #[msg("Emergency window exceeds MAX_EMERGENCY_DURATION")]
+    EmergencyTooLong,
+    
+    #[msg("Token account has the wrong mint or owner")]
+    InvalidTokenAccount,
+    
+    #[msg("Only a verified creator of the NFT can override its royalty recipient")]
+    UnauthorizedRoyaltyOverride,
+    
+    #[msg("Royalty recipient does not match the listing's royalty override")]
+    InvalidRoyaltyRecipient,
+    
+    #[msg("Batch size out of range (MAX_BATCH_CANCEL listings, MAX_BATCH_VAULTS vaults)")]
+    InvalidBatchSize,
+    
+    #[msg("Sample count must be between 1 and MAX_PREVIEW_SAMPLES")]
+    InvalidSampleCount,
+    
+    #[msg("Royalties are enforced and listings may not opt out")]
+    RoyaltyOptOutDisabled,
+    
+    #[msg("Fee override must be between the minimum listing fee and the marketplace fee")]
+    InvalidFeeOverride,
+    
+    #[msg("Listing pays into escrow - pass the seller's Proceeds account")]
+    InvalidProceedsAccount,
+    
+    #[msg("Agreed price is below the oracle floor")]
+    PriceBelowOracleFloor,
+    
+    #[msg("Creator royalty payments do not add up to the royalty")]
+    RoyaltyUnderpaid,
+    
+    #[msg("Vault still has a listing - its owner must use reconcile_vault")]
+    VaultHasListing,
+    
+    #[msg("Community recipient is missing or does not match the config")]
+    InvalidCommunityRecipient,
+    
+    #[msg("Listing cannot be expired until valid_until plus the expiry grace has passed")]
+    ListingNotExpired,
+    
+    #[msg("Quantity must be at least 1 and no more than the units available")]
+    InvalidQuantity,
+    
+    #[msg("This NFT was listed too recently to be listed again")]
+    RelistCooldown,
+    
+    #[msg("Seller already has the maximum number of active listings")]
+    TooManyActiveListings,
+    
+    #[msg("Seller state account required to release this listing's slot")]
+    MissingSellerState,
+    
+    #[msg("Emergency mode must be on for this instruction")]
+    EmergencyNotActive,
+    
+    #[msg("Listings are still active")]
+    ActiveListingsRemain,
+    
+    #[msg("Fee recipient account required to pay for a boost")]
+    MissingFeeRecipient,
+    
+    #[msg("Token accounts for the split payment's token leg are missing or invalid")]
+    InvalidPaymentAccounts,
+    
+    #[msg("Royalty policy account for the listing's collection is missing or invalid")]
+    MissingRoyaltyPolicy,
+    
+    #[msg("Only the config authority or a verified creator of the collection can set its royalty policy")]
+    UnauthorizedRoyaltyPolicy,
+    
+    #[msg("Collection TWAP account for the listing's collection is missing or invalid")]
+    MissingCollectionTwap,
+    
+    #[msg("Fee discount token account must be the buyer's, for the configured discount mint")]
+    InvalidDiscountAccount,
+    
+    #[msg("A collection TWAP floor needs the listing's verified collection")]
+    FloorNeedsCollection,
+    
+    #[msg("The NFT's metadata is required because this sale pays royalties")]
+    MissingMetadata,
+    
+    #[msg("The fee discount threshold must be positive while the discount is enabled")]
+    InvalidDiscountThreshold,
+    
+    #[msg("Buyer and seller must be different wallets")]
+    SelfPurchase,
+    
+    #[msg("Marketplace fee exceeds MAX_FEE_BPS")]
+    InvalidFeeBps,
+    
+    #[msg("A min_floor needs a floor_source to check it against, and a floor_source needs a min_floor")]
+    MissingFloorSource,
+    
+    #[msg("The leaf is already listed")]
+    ListingAlreadyActive,
+    
+    #[msg("Payment mint is not on the marketplace's allowlist")]
+    UnsupportedPaymentMint,
+    
+    #[msg("The payment mint allowlist is full (max MAX_PAYMENT_MINTS)")]
+    TooManyPaymentMints,
+    
+    #[msg("payment_decimals doesn't match the payment mint")]
+    InvalidPaymentDecimals,
 }
\ No newline at end of file
@@ -0,0 +1,177 @@
+use anchor_lang::prelude::*;
+use crate::state::PriceType;
+
+/// Emitted when a listing is created
+#[event]
+pub struct ListingCreated {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub price_type: PriceType,
+    pub start_price: u64,
+    pub min_price: u64,
+    pub collection: Option<Pubkey>,
+    pub memo: String,
+    /// Lamports paid for placement (0 when not boosted)
+    pub boost_score: u64,
+}
+
+/// Emitted once, when initialize_config creates the marketplace
+#[event]
+pub struct ConfigInitialized {
+    pub authority: Pubkey,
+    pub fee_bps: u16,
+    pub fee_recipient: Pubkey,
+    /// Program release (PROGRAM_VERSION) and account layout (ACCOUNT_VERSION)
+    pub program_version: String,
+    pub account_version: u8,
+    pub timestamp: i64,
+}
+
+/// Why a listing left the market
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CancelReason {
+    /// The seller cancelled it
+    Seller,
+    /// Its time window ended without a sale
+    Expired,
+    /// Its vault no longer held the listed NFT
+    Invalidated,
+}
+
+/// Emitted when a listing is cancelled
+#[event]
+pub struct ListingCancelled {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub reason: CancelReason,
+}
+
+/// Emitted when a listing is bought
+#[event]
+pub struct Sale {
+    pub listing: Pubkey,
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub marketplace_fee: u64,
+    pub royalty: u64,
+    pub fee_exempt: bool,
+    /// Fee rate actually applied (after any listing override or exemption)
+    pub fee_bps: u16,
+    /// Taken from the seller's proceeds for the community treasury
+    pub community_amount: u64,
+    /// Units bought; `price` is the total paid for them
+    pub quantity: u64,
+    /// Wallet the NFT was delivered to (the buyer unless they named a recipient)
+    pub recipient: Pubkey,
+    /// First sale of the NFT per its metadata (charged no royalty)
+    pub primary_sale: bool,
+    /// Token leg of a split-payment listing (zeros without one)
+    pub payment_mint: Option<Pubkey>,
+    pub token_price: u64,
+    pub token_marketplace_fee: u64,
+    pub token_royalty: u64,
+    /// Buyer held enough of Config.discount_mint for the fee discount
+    pub fee_discounted: bool,
+}
+
+/// Emitted when settle_otc completes a privately agreed sale
+#[event]
+pub struct OtcSettled {
+    pub mint: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub marketplace_fee: u64,
+    pub royalty: u64,
+    pub community_amount: u64,
+}
+
+/// One creator's cut of a sale's royalty
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RoyaltyPayment {
+    pub creator: Pubkey,
+    pub amount: u64,
+}
+
+/// Emitted when a sale credits a creator's RoyaltyEscrow instead of their wallet
+#[event]
+pub struct RoyaltyAccrued {
+    pub creator: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Emitted when a creator withdraws their RoyaltyEscrow balance
+#[event]
+pub struct RoyaltyClaimed {
+    pub creator: Pubkey,
+    pub amount: u64,
+}
+
+/// Emitted when a collection's royalty policy is created or changed
+#[event]
+pub struct RoyaltyPolicySet {
+    pub collection: Pubkey,
+    pub enforce: bool,
+    pub authority: Pubkey,
+}
+
+/// Emitted when a sale pays royalties to the NFT's metadata creators
+/// (or to the listing's royalty override)
+#[event]
+pub struct RoyaltyPaid {
+    pub mint: Pubkey,
+    pub payments: Vec<RoyaltyPayment>,
+    /// The sale's whole royalty - always equal to the sum of `payments`
+    pub total: u64,
+    /// seller_fee_basis_points from the metadata (None when not supplied)
+    pub metadata_bps: Option<u16>,
+    /// Rate actually charged, after Config.min_royalty_bps / max_royalty_bps
+    pub enforced_bps: u16,
+    /// Set when the listing redirected the whole royalty to one account
+    pub royalty_override: Option<Pubkey>,
+}
+
+/// One point on a listing's price curve
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PricePoint {
+    pub timestamp: i64,
+    pub price: u64,
+}
+
+/// Emitted by preview_schedule
+#[event]
+pub struct PriceSchedule {
+    pub listing: Pubkey,
+    pub points: Vec<PricePoint>,
+}
+
+/// Emitted by dry_run_buy
+/// `total` is what the buyer pays (fees and royalties come out of the price);
+/// when `buyable` is false the amounts are zero
+#[event]
+pub struct BuyPreview {
+    pub listing: Pubkey,
+    pub quantity: u64,
+    pub price: u64,
+    pub marketplace_fee: u64,
+    pub royalty: u64,
+    pub community_amount: u64,
+    pub seller_amount: u64,
+    pub total: u64,
+    pub buyable: bool,
+}
+
+/// Emitted by check_vault_status
+#[event]
+pub struct VaultStatus {
+    pub user_vault: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub is_locked: bool,
+    pub active_listing: Option<Pubkey>,
+}
\ No newline at end of file
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use crate::state::{Config, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Allow split payments priced in an SPL mint (authority only)
+#[derive(Accounts)]
+pub struct AddPaymentMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        has_one = authority @ VerityError::UnauthorizedAuthority,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Mint being allowed (must be an SPL token mint)
+    pub payment_mint: Account<'info, Mint>,
+    
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AddPaymentMint>) -> Result<()> {
+    let mint = ctx.accounts.payment_mint.key();
+    let config = &mut ctx.accounts.config;
+    config.allow_payment_mint(mint)?;
+    
+    msg!(
+        "Payment mint allowed: {} ({} allowed)",
+        mint,
+        config.payment_mints.len()
+    );
+    
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,402 @@
+use anchor_lang::prelude::*;
+use mpl_bubblegum::instructions::{TransferCpi, TransferCpiAccounts, TransferInstructionArgs};
+use mpl_bubblegum::programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID};
+use anchor_spl::token::TokenAccount;
+use crate::state::{
+    CompressedLeafArgs, CompressedListing, CompressedMetadataArgs, Config, FeeExemption,
+    ListingState, SellerState, Stats, ACCOUNT_VERSION
+};
+use crate::error::VerityError;
+use crate::events::{RoyaltyPaid, Sale};
+use crate::utils::{
+    pay_community_cut, pay_creator_royalties, pay_marketplace_fee, quote_sale,
+    record_collection_sale, transfer_lamports, verify_compressed_metadata, SaleQuote
+};
+
+/// Purchase a compressed NFT
+/// Priced and paid exactly like buy_now (see utils::quote_sale), with the
+/// leaf's metadata standing in for the Token Metadata account
+/// Remaining accounts: fee split recipients (one per Config.fee_shares
+/// entry, in order), then - when `metadata` is supplied - one account per
+/// creator in it (their RoyaltyEscrow PDAs while Config.escrow_royalties is
+/// set), followed by the proof nodes for the leaf
+#[derive(Accounts)]
+pub struct BuyCompressed<'info> {
+    /// Compressed listing being purchased
+    #[account(
+        mut,
+        close = seller,
+        seeds = [
+            b"compressed_listing",
+            compressed_listing.seller.as_ref(),
+            compressed_listing.merkle_tree.as_ref(),
+            &compressed_listing.leaf_nonce.to_le_bytes()
+        ],
+        bump = compressed_listing.bump,
+        constraint = compressed_listing.state == ListingState::Active @ VerityError::ListingNotActive
+    )]
+    pub compressed_listing: Account<'info, CompressedListing>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    /// Seller receives payment (current leaf owner)
+    /// CHECK: Validated via compressed_listing.seller
+    #[account(
+        mut,
+        constraint = seller.key() == compressed_listing.seller @ VerityError::UnauthorizedSeller
+    )]
+    pub seller: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Seller's listing counter (list_compressed took a slot in it)
+    #[account(
+        mut,
+        seeds = [b"seller_state", compressed_listing.seller.as_ref()],
+        bump = seller_state.bump
+    )]
+    pub seller_state: Account<'info, SellerState>,
+    
+    /// Fee recipient
+    /// CHECK: Validated via config.accepts_fee_recipient
+    #[account(
+        mut,
+        constraint = config.accepts_fee_recipient(&fee_recipient.key(), Clock::get()?.unix_timestamp)
+    )]
+    pub fee_recipient: UncheckedAccount<'info>,
+    
+    /// Community treasury (required while Config.community_bps is set)
+    /// CHECK: Validated via config.community_recipient
+    #[account(
+        mut,
+        constraint = community_recipient.key() == config.community_recipient @ VerityError::InvalidCommunityRecipient
+    )]
+    pub community_recipient: Option<UncheckedAccount<'info>>,
+    
+    /// Seller's fee exemption, if one was granted - waives the marketplace fee
+    #[account(
+        constraint = fee_exemption.seller == compressed_listing.seller @ VerityError::UnauthorizedSeller
+    )]
+    pub fee_exemption: Option<Box<Account<'info, FeeExemption>>>,
+    
+    /// RoyaltyPolicy PDA of the leaf's verified collection - required
+    /// (initialized or not) whenever the metadata names one
+    /// CHECK: Address and contents validated by read_royalty_policy
+    pub royalty_policy: Option<UncheckedAccount<'info>>,
+    
+    /// CollectionTwap PDA of the leaf's verified collection (as for buy_now)
+    /// CHECK: Address and contents validated by read_collection_twap
+    #[account(mut)]
+    pub collection_twap: Option<UncheckedAccount<'info>>,
+    
+    /// Buyer's token account for Config.discount_mint (as for buy_now)
+    #[account(
+        constraint = discount_token_account.mint == config.discount_mint @ VerityError::InvalidDiscountAccount,
+        constraint = discount_token_account.owner == buyer.key() @ VerityError::InvalidDiscountAccount
+    )]
+    pub discount_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    /// Bubblegum tree config PDA
+    /// CHECK: Derived from merkle_tree under the Bubblegum program
+    #[account(
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key()
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Must be the tree recorded on the listing; verified by Bubblegum
+    #[account(
+        mut,
+        constraint = merkle_tree.key() == compressed_listing.merkle_tree @ VerityError::VaultMismatch
+    )]
+    pub merkle_tree: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, BuyCompressed<'info>>,
+    leaf: CompressedLeafArgs,
+    metadata: Option<CompressedMetadataArgs>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    
+    require!(
+        leaf.nonce == ctx.accounts.compressed_listing.leaf_nonce,
+        VerityError::VaultMismatch
+    );
+    
+    // The leaf's metadata, checked against its hashes, plays the part of
+    // buy_now's Token Metadata account
+    let seller_key = ctx.accounts.seller.key();
+    let metadata = metadata
+        .map(|metadata| verify_compressed_metadata(&metadata, &leaf, &seller_key))
+        .transpose()?;
+    let metadata_bps = metadata
+        .as_ref()
+        .map(|metadata| metadata.seller_fee_basis_points);
+    
+    // Price the purchase (conditions, fees, royalty policy, primary sale)
+    let fee_exempt = ctx.accounts.fee_exemption.is_some();
+    let fee_discount = ctx
+        .accounts
+        .discount_token_account
+        .as_ref()
+        .is_some_and(|account| ctx.accounts.config.fee_discount_applies(account.amount));
+    let SaleQuote {
+        unit_price,
+        price,
+        marketplace_fee,
+        royalty,
+        community_amount,
+        seller_amount,
+        fee_bps,
+        enforced_bps,
+        creators,
+        primary_sale,
+        collection,
+        fee_discounted,
+    } = quote_sale(
+        &ctx.accounts.config,
+        &ctx.accounts.compressed_listing.sale_terms(),
+        1,
+        clock.unix_timestamp,
+        None,
+        None,
+        fee_exempt,
+        fee_discount,
+        metadata.as_ref(),
+        ctx.accounts.royalty_policy.as_ref().map(|policy| policy.as_ref()),
+        ctx.accounts.collection_twap.as_ref().map(|twap| twap.as_ref()),
+    )?;
+    
+    // Mark the listing sold and give back the seller's slot, persisted
+    // before any CPI (as in buy_now)
+    let listing = &mut ctx.accounts.compressed_listing;
+    listing.state = ListingState::Sold;
+    ctx.accounts.seller_state.release_slot();
+    listing.exit(&crate::ID)?;
+    
+    msg!("Purchase price: {} lamports at timestamp {}", price, clock.unix_timestamp);
+    
+    // Fail early and clearly if the buyer can't cover the whole price
+    require!(
+        ctx.accounts.buyer.lamports() >= price,
+        VerityError::InsufficientFunds
+    );
+    
+    msg!(
+        "Payment breakdown: price={}, fee={}, royalty={}, community={}, seller={}",
+        price, marketplace_fee, royalty, community_amount, seller_amount
+    );
+    
+    let buyer_info = ctx.accounts.buyer.to_account_info();
+    let system_program_info = ctx.accounts.system_program.to_account_info();
+    
+    // Transfer marketplace fee (split across fee recipients if configured)
+    let seller_info = ctx.accounts.seller.to_account_info();
+    let creator_count = creators.as_ref().map_or(0, |creators| creators.len());
+    let (fee_accounts, other_accounts) = ctx
+        .remaining_accounts
+        .split_at(ctx.accounts.config.fee_shares.len().min(ctx.remaining_accounts.len()));
+    require!(
+        other_accounts.len() >= creator_count,
+        VerityError::InvalidCreatorAccounts
+    );
+    let (creator_accounts, proof_accounts) = other_accounts.split_at(creator_count);
+    let fee_owed_to_seller = pay_marketplace_fee(
+        &ctx.accounts.config,
+        marketplace_fee,
+        &buyer_info,
+        &ctx.accounts.fee_recipient.to_account_info(),
+        fee_accounts,
+        &system_program_info,
+        &seller_key,
+    )?;
+    pay_community_cut(
+        community_amount,
+        &buyer_info,
+        ctx.accounts
+            .community_recipient
+            .as_ref()
+            .map(|recipient| recipient.as_ref()),
+        &system_program_info,
+    )?;
+    
+    // Transfer royalties to the creators
+    let asset_id = ctx.accounts.compressed_listing.asset_id;
+    let royalty_owed_to_seller = match &creators {
+        Some(creators) => {
+            let payments = pay_creator_royalties(
+                &asset_id,
+                creators,
+                royalty,
+                ctx.accounts.config.redistribute_unverified_royalty,
+                ctx.accounts.config.escrow_royalties,
+                &buyer_info,
+                creator_accounts,
+                &system_program_info,
+                &seller_key,
+            )?;
+            let owed_to_seller = payments
+                .iter()
+                .filter(|payment| payment.creator == seller_key)
+                .map(|payment| payment.amount)
+                .sum();
+            if royalty > 0 {
+                emit!(RoyaltyPaid {
+                    mint: asset_id,
+                    payments,
+                    total: royalty,
+                    metadata_bps,
+                    enforced_bps,
+                    royalty_override: None,
+                });
+            }
+            owed_to_seller
+        }
+        // No creators (or royalties not enforced): nothing was charged
+        None => 0,
+    };
+    
+    // Transfer SOL to seller in one go (proceeds, any royalty share of their
+    // own, plus any fee share when the seller is also a fee recipient)
+    transfer_lamports(
+        &buyer_info,
+        &seller_info,
+        &system_program_info,
+        seller_amount
+            .checked_add(royalty_owed_to_seller)
+            .and_then(|total| total.checked_add(fee_owed_to_seller))
+            .ok_or(VerityError::ArithmeticOverflow)?,
+    )?;
+    
+    // Transfer the leaf to the buyer (signed by the listing PDA as leaf delegate)
+    let listing = &ctx.accounts.compressed_listing;
+    let nonce_bytes = listing.leaf_nonce.to_le_bytes();
+    let seeds = &[
+        b"compressed_listing",
+        listing.seller.as_ref(),
+        listing.merkle_tree.as_ref(),
+        nonce_bytes.as_ref(),
+        &[listing.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let proof: Vec<(&AccountInfo<'info>, bool, bool)> = proof_accounts
+        .iter()
+        .map(|account| (account, false, false))
+        .collect();
+    
+    let listing_info = listing.to_account_info();
+    TransferCpi::new(
+        &ctx.accounts.bubblegum_program.to_account_info(),
+        TransferCpiAccounts {
+            tree_config: &ctx.accounts.tree_config.to_account_info(),
+            leaf_owner: (&seller_info, false),
+            leaf_delegate: (&listing_info, true),
+            new_leaf_owner: &buyer_info,
+            merkle_tree: &ctx.accounts.merkle_tree.to_account_info(),
+            log_wrapper: &ctx.accounts.log_wrapper.to_account_info(),
+            compression_program: &ctx.accounts.compression_program.to_account_info(),
+            system_program: &system_program_info,
+        },
+        TransferInstructionArgs {
+            root: leaf.root,
+            data_hash: leaf.data_hash,
+            creator_hash: leaf.creator_hash,
+            nonce: leaf.nonce,
+            index: leaf.index,
+        },
+    )
+    .invoke_signed_with_remaining_accounts(signer, &proof)?;
+    
+    // Update global marketplace statistics
+    let stats = &mut ctx.accounts.stats;
+    stats.total_sales = stats
+        .total_sales
+        .checked_add(1)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    stats.total_volume = stats
+        .total_volume
+        .checked_add(price)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    stats.active_listings = stats.active_listings.saturating_sub(1);
+    
+    // Fold the sale into the collection's average price
+    if let Some(collection) = collection {
+        let twap = ctx
+            .accounts
+            .collection_twap
+            .as_ref()
+            .ok_or(VerityError::MissingCollectionTwap)?;
+        let ema_price = record_collection_sale(
+            twap.as_ref(),
+            &collection,
+            unit_price,
+            clock.unix_timestamp,
+            &buyer_info,
+            &system_program_info,
+        )?;
+        msg!("Collection {} average price: {} lamports", collection, ema_price);
+    }
+    
+    msg!(
+        "Compressed purchase completed: buyer={}, seller={}, asset_id={}, price={}",
+        ctx.accounts.buyer.key(),
+        seller_key,
+        asset_id,
+        price
+    );
+    
+    emit!(Sale {
+        listing: ctx.accounts.compressed_listing.key(),
+        mint: asset_id,
+        seller: seller_key,
+        buyer: ctx.accounts.buyer.key(),
+        price,
+        marketplace_fee,
+        royalty,
+        fee_exempt,
+        fee_bps,
+        community_amount,
+        quantity: 1,
+        recipient: ctx.accounts.buyer.key(),
+        primary_sale,
+        payment_mint: None,
+        token_price: 0,
+        token_marketplace_fee: 0,
+        token_royalty: 0,
+        fee_discounted,
+    });
+    
+    // Listing account closes automatically (close = seller)
+    Ok(())
+}
@@ -1,24 +1,30 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::{program::invoke, system_instruction};
 use anchor_spl::{
-    associated_token::AssociatedToken,
+    associated_token::{self, get_associated_token_address, AssociatedToken},
+    metadata::{self, Metadata, MetadataAccount, UpdatePrimarySaleHappenedViaToken},
     token::{self, Mint, Token, TokenAccount, Transfer},
 };
 use crate::state::{
-    Config, Listing, UserVault, STATE_ACTIVE, STATE_SOLD,
-    calculate_price, validate_conditions
+    Config, FeeExemption, Listing, ListingState, Proceeds, SellerState, Stats, UserStats, UserVault,
+    ACCOUNT_VERSION
 };
 use crate::error::VerityError;
+use crate::events::{RoyaltyPaid, RoyaltyPayment, Sale};
+use crate::utils::{
+    pay_community_cut, pay_creator_royalties, pay_marketplace_fee, pay_token_leg, quote_sale,
+    record_collection_sale, release_seller_slot, require_token_account, transfer_lamports, SaleMetadata,
+    SaleQuote, TokenLeg, TokenLegAccounts
+};
 
 #[derive(Accounts)]
 pub struct BuyNow<'info> {
-    /// Listing being purchased
+    /// Listing being purchased (closed to the seller once sold out)
     #[account(
         mut,
-        close = seller,
         seeds = [b"listing", listing.seller.as_ref(), listing.mint.as_ref()],
         bump = listing.bump,
-        constraint = listing.state == STATE_ACTIVE @ VerityError::ListingNotActive
+        constraint = listing.state == ListingState::Active @ VerityError::ListingNotActive,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
     )]
     pub listing: Account<'info, Listing>,
     
@@ -26,7 +32,8 @@ pub struct BuyNow<'info> {
     #[account(
         seeds = [b"user_vault", listing.seller.as_ref(), listing.mint.as_ref()],
         bump = user_vault.bump,
-        constraint = user_vault.key() == listing.user_vault @ VerityError::VaultMismatch
+        constraint = user_vault.key() == listing.user_vault @ VerityError::VaultMismatch,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
     )]
     pub user_vault: Account<'info, UserVault>,
     
@@ -38,27 +45,30 @@ pub struct BuyNow<'info> {
     )]
     pub vault_pda: UncheckedAccount<'info>,
     
-    /// Vault ATA holding the NFT
+    /// Vault ATA holding the NFT (or the listed units)
     #[account(
         mut,
-        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch,
-        constraint = vault_ata.amount == 1 @ VerityError::InvalidTokenAmount
+        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch
     )]
     pub vault_ata: Account<'info, TokenAccount>,
     
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-    
-    /// Buyer's ATA to receive NFT
+    /// Must not be the seller: their UserStats PDAs would be the same account
     #[account(
-        init,
-        payer = buyer,
-        associated_token::mint = mint,
-        associated_token::authority = buyer
+        mut,
+        constraint = buyer.key() != listing.seller @ VerityError::SelfPurchase
     )]
-    pub buyer_ata: Account<'info, TokenAccount>,
+    pub buyer: Signer<'info>,
+    
+    /// ATA that receives the NFT: the recipient's when one is supplied,
+    /// otherwise the buyer's (created at the buyer's expense if needed)
+    /// CHECK: Address derived, created and validated in the handler
+    #[account(mut)]
+    pub buyer_ata: UncheckedAccount<'info>,
     
     /// Seller receives payment
+    /// May be a closed (zero-lamport) system account when this purchase sells
+    /// out the listing: the proceeds and the listing's rent refund recreate
+    /// it, and together always cover its rent
     /// CHECK: Validated via listing.seller
     #[account(
         mut,
@@ -70,110 +80,436 @@ pub struct BuyNow<'info> {
     
     #[account(
         seeds = [b"config"],
-        bump
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
     )]
     pub config: Account<'info, Config>,
     
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Seller's listing counter (required when this purchase sells out a
+    /// listing that holds a slot)
+    #[account(
+        mut,
+        seeds = [b"seller_state", listing.seller.as_ref()],
+        bump = seller_state.bump
+    )]
+    pub seller_state: Option<Box<Account<'info, SellerState>>>,
+    
+    /// Buyer's trading stats (created on first trade)
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserStats::LEN,
+        seeds = [b"user_stats", buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_stats: Account<'info, UserStats>,
+    
+    /// Seller's trading stats (created on first trade)
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserStats::LEN,
+        seeds = [b"user_stats", listing.seller.as_ref()],
+        bump
+    )]
+    pub seller_stats: Account<'info, UserStats>,
+    
     /// Fee recipient
-    /// CHECK: Validated via config.fee_recipient
+    /// CHECK: Validated via config.accepts_fee_recipient
     #[account(
         mut,
-        constraint = fee_recipient.key() == config.fee_recipient
+        constraint = config.accepts_fee_recipient(&fee_recipient.key(), Clock::get()?.unix_timestamp)
     )]
     pub fee_recipient: UncheckedAccount<'info>,
     
+    /// Community treasury (required while Config.community_bps is set)
+    /// CHECK: Validated via config.community_recipient
+    #[account(
+        mut,
+        constraint = community_recipient.key() == config.community_recipient @ VerityError::InvalidCommunityRecipient
+    )]
+    pub community_recipient: Option<UncheckedAccount<'info>>,
+    
+    /// Oracle quoting the collection floor in SOL
+    /// Required when the listing's decay floor or min_floor tracks the
+    /// oracle, and must be the listing's oracle_feed
+    /// CHECK: Owner and layout validated by read_oracle_price_lamports
+    pub oracle: Option<UncheckedAccount<'info>>,
+    
+    /// SOL/USD oracle (Config.sol_usd_feed) - required when the listing
+    /// sets min_usd_value
+    /// CHECK: Owner and layout validated by read_sol_usd_price_micros
+    pub sol_usd_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// Seller's fee exemption, if one was granted - waives the marketplace fee
+    #[account(
+        constraint = fee_exemption.seller == listing.seller @ VerityError::UnauthorizedSeller
+    )]
+    pub fee_exemption: Option<Box<Account<'info, FeeExemption>>>,
+    
+    /// Token Metadata account for the listed mint - when supplied, the
+    /// royalty follows seller_fee_basis_points and is paid to its creators
+    /// (none on a primary sale, whose flag token_metadata_program flips)
+    /// Required whenever the sale pays royalties, e.g. because the listing's
+    /// collection has an enforcing RoyaltyPolicy
+    #[account(
+        mut,
+        constraint = metadata.mint == listing.mint @ VerityError::InvalidMetadata
+    )]
+    pub metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    
+    /// RoyaltyPolicy PDA of the listing's verified collection - required
+    /// (initialized or not) whenever the listing has one
+    /// CHECK: Address and contents validated by read_royalty_policy
+    pub royalty_policy: Option<UncheckedAccount<'info>>,
+    
+    /// CollectionTwap PDA of the listing's verified collection - required
+    /// (initialized or not) whenever the listing has one; the sale is folded
+    /// into it, creating it at the buyer's expense on the collection's first sale
+    /// CHECK: Address and contents validated by read_collection_twap
+    #[account(mut)]
+    pub collection_twap: Option<UncheckedAccount<'info>>,
+    
+    /// Buyer's token account for Config.discount_mint - holding at least
+    /// discount_threshold takes discount_bps off the fee rate
+    #[account(
+        constraint = discount_token_account.mint == config.discount_mint @ VerityError::InvalidDiscountAccount,
+        constraint = discount_token_account.owner == buyer.key() @ VerityError::InvalidDiscountAccount
+    )]
+    pub discount_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    /// Receives the whole royalty when the listing has a royalty override
+    /// CHECK: Must equal listing.royalty_override
+    #[account(
+        mut,
+        constraint = listing.royalty_override == Some(royalty_recipient.key())
+            @ VerityError::InvalidRoyaltyRecipient
+    )]
+    pub royalty_recipient: Option<UncheckedAccount<'info>>,
+    
+    /// Wallet that receives the NFT instead of the buyer (gifting, custodial
+    /// purchases) - the buyer still signs and pays
+    /// CHECK: Any wallet can receive; only used as the ATA authority
+    pub recipient: Option<UncheckedAccount<'info>>,
+    
+    /// Seller's Proceeds PDA - required when the listing escrows proceeds
+    #[account(
+        mut,
+        constraint = proceeds.seller == listing.seller @ VerityError::InvalidProceedsAccount
+    )]
+    pub proceeds: Option<Box<Account<'info, Proceeds>>>,
+    
+    /// The listing's payment_mint and token accounts for it - all required
+    /// when the listing has a split payment: the buyer's (paying), the
+    /// seller's and fee_recipient's
+    pub payment_mint: Option<Box<Account<'info, Mint>>>,
+    
+    #[account(mut)]
+    pub buyer_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    #[account(mut)]
+    pub seller_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    #[account(mut)]
+    pub fee_recipient_payment_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    /// Token Metadata program - when supplied on a primary sale, the vault
+    /// (as token owner) marks the metadata's primary_sale_happened
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+    
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
-pub fn handler(ctx: Context<BuyNow>) -> Result<()> {
+/// Remaining accounts: fee split recipients (when Config.fee_shares is set)
+/// in the same order as Config.fee_shares, followed by the metadata creators
+/// in metadata order (when metadata is supplied) - their RoyaltyEscrow PDAs
+/// instead while Config.escrow_royalties is set - followed, for split-payment
+/// listings, by the payment_mint token accounts of the royalty override or
+/// of each metadata creator in metadata order (see pay_token_leg)
+/// `quantity` is the number of units to buy (always 1 for an NFT)
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, BuyNow<'info>>, quantity: u64) -> Result<()> {
     let listing = &mut ctx.accounts.listing;
     let clock = Clock::get()?;
     
-    // Validate listing conditions (time window, floor price)
-    validate_conditions(&listing.conditions, clock.unix_timestamp, None)?;
-    
-    // Calculate current price
-    let price = calculate_price(&listing.price_config, clock.unix_timestamp);
-    
-    msg!("Purchase price: {} lamports at timestamp {}", price, clock.unix_timestamp);
-    
-    // Calculate fees
-    let marketplace_fee = (price as u128)
-        .checked_mul(ctx.accounts.config.fee_bps as u128)
-        .ok_or(VerityError::ArithmeticOverflow)?
-        .checked_div(10000)
-        .ok_or(VerityError::ArithmeticOverflow)? as u64;
-    
-    // Simplified royalty (5% - in production, parse metadata)
-    let royalty_bps = 500u64;
-    let royalty = (price as u128)
-        .checked_mul(royalty_bps as u128)
-        .ok_or(VerityError::ArithmeticOverflow)?
-        .checked_div(10000)
-        .ok_or(VerityError::ArithmeticOverflow)? as u64;
-    
-    let seller_amount = price
-        .checked_sub(marketplace_fee)
-        .ok_or(VerityError::ArithmeticOverflow)?
-        .checked_sub(royalty)
-        .ok_or(VerityError::ArithmeticOverflow)?;
+    // Price the purchase (conditions, oracle floor, fees, royalties)
+    let fee_exempt = ctx.accounts.fee_exemption.is_some();
+    let fee_discount = ctx
+        .accounts
+        .discount_token_account
+        .as_ref()
+        .is_some_and(|account| ctx.accounts.config.fee_discount_applies(account.amount));
+    let metadata = ctx
+        .accounts
+        .metadata
+        .as_deref()
+        .map(|metadata| SaleMetadata::from(&**metadata));
+    let SaleQuote {
+        unit_price,
+        price,
+        marketplace_fee,
+        royalty,
+        community_amount,
+        seller_amount,
+        fee_bps,
+        enforced_bps,
+        creators,
+        primary_sale,
+        collection,
+        fee_discounted,
+    } = quote_sale(
+        &ctx.accounts.config,
+        &listing.sale_terms(),
+        quantity,
+        clock.unix_timestamp,
+        ctx.accounts.oracle.as_ref().map(|oracle| oracle.as_ref()),
+        ctx.accounts.sol_usd_oracle.as_ref().map(|oracle| oracle.as_ref()),
+        fee_exempt,
+        fee_discount,
+        metadata.as_ref(),
+        ctx.accounts.royalty_policy.as_ref().map(|policy| policy.as_ref()),
+        ctx.accounts.collection_twap.as_ref().map(|twap| twap.as_ref()),
+    )?;
+    let metadata_bps = metadata.map(|metadata| metadata.seller_fee_basis_points);
     
-    msg!(
-        "Payment breakdown: price={}, fee={}, royalty={}, seller={}",
-        price, marketplace_fee, royalty, seller_amount
+    // The vault must be able to deliver before anything is paid (the NFT
+    // could have left through a delegate or a Token-2022 edge case)
+    require!(
+        ctx.accounts.vault_ata.amount >= quantity,
+        VerityError::NftNotInVault
     );
     
-    // Transfer SOL to seller
-    if seller_amount > 0 {
-        invoke(
-            &system_instruction::transfer(
-                ctx.accounts.buyer.key,
-                ctx.accounts.seller.key,
-                seller_amount,
-            ),
-            &[
-                ctx.accounts.buyer.to_account_info(),
-                ctx.accounts.seller.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
+    // Take the units off the listing (marking it sold once every unit is
+    // gone) and persist that before any CPI, so no program called below can
+    // observe or re-enter a listing that still looks buyable
+    let sold_out = listing.take_units(quantity)?;
+    if sold_out {
+        release_seller_slot(listing, ctx.accounts.seller_state.as_deref_mut().map(|state| &mut **state))?;
     }
+    listing.exit(&crate::ID)?;
     
-    // Transfer marketplace fee
-    if marketplace_fee > 0 {
-        invoke(
-            &system_instruction::transfer(
-                ctx.accounts.buyer.key,
-                ctx.accounts.fee_recipient.key,
-                marketplace_fee,
-            ),
-            &[
-                ctx.accounts.buyer.to_account_info(),
-                ctx.accounts.fee_recipient.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
+    // Deliver to the recipient if one was named, otherwise to the buyer
+    let nft_owner = match &ctx.accounts.recipient {
+        Some(recipient) => recipient.to_account_info(),
+        None => ctx.accounts.buyer.to_account_info(),
+    };
+    require_keys_eq!(
+        ctx.accounts.buyer_ata.key(),
+        get_associated_token_address(nft_owner.key, &ctx.accounts.mint.key()),
+        VerityError::InvalidTokenAccount
+    );
+    associated_token::create_idempotent(CpiContext::new(
+        ctx.accounts.associated_token_program.to_account_info(),
+        associated_token::Create {
+            payer: ctx.accounts.buyer.to_account_info(),
+            associated_token: ctx.accounts.buyer_ata.to_account_info(),
+            authority: nft_owner.clone(),
+            mint: ctx.accounts.mint.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        },
+    ))?;
+    {
+        let data = ctx.accounts.buyer_ata.try_borrow_data()?;
+        let buyer_ata = TokenAccount::try_deserialize(&mut &data[..])?;
+        require_token_account(&buyer_ata, &ctx.accounts.mint.key(), nft_owner.key)?;
     }
     
-    // Transfer royalties (simplified - send to seller)
-    if royalty > 0 {
-        invoke(
-            &system_instruction::transfer(
-                ctx.accounts.buyer.key,
-                ctx.accounts.seller.key,
+    msg!(
+        "Purchase price: {} lamports ({} x {}) at timestamp {}",
+        price, quantity, unit_price, clock.unix_timestamp
+    );
+    
+    // Fail early and clearly if the buyer can't cover the whole price.
+    // Rent for any buyer_ata / stats accounts Anchor had to create was
+    // already taken, so the remaining balance only has to cover the price
+    require!(
+        ctx.accounts.buyer.lamports() >= price,
+        VerityError::InsufficientFunds
+    );
+    
+    msg!(
+        "Payment breakdown: price={}, fee={}, royalty={}, community={}, seller={}",
+        price, marketplace_fee, royalty, community_amount, seller_amount
+    );
+    
+    let buyer_info = ctx.accounts.buyer.to_account_info();
+    let system_program_info = ctx.accounts.system_program.to_account_info();
+    
+    // Everything owed to the seller (proceeds, plus any fee or royalty the
+    // seller is also entitled to) goes out in a single transfer at the end
+    let seller_info = ctx.accounts.seller.to_account_info();
+    let seller_key = seller_info.key();
+    let mut seller_total = seller_amount;
+    
+    // Transfer marketplace fee (split across fee recipients if configured)
+    let fee_owed_to_seller = pay_marketplace_fee(
+        &ctx.accounts.config,
+        marketplace_fee,
+        &buyer_info,
+        &ctx.accounts.fee_recipient.to_account_info(),
+        ctx.remaining_accounts,
+        &system_program_info,
+        &seller_key,
+    )?;
+    seller_total = seller_total
+        .checked_add(fee_owed_to_seller)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    
+    pay_community_cut(
+        community_amount,
+        &buyer_info,
+        ctx.accounts
+            .community_recipient
+            .as_ref()
+            .map(|recipient| recipient.as_ref()),
+        &system_program_info,
+    )?;
+    
+    // Transfer royalties
+    let royalty_owed_to_seller = if let Some(recipient) = listing.royalty_override {
+        // A verified creator routed the whole royalty to one account
+        let royalty_recipient = ctx
+            .accounts
+            .royalty_recipient
+            .as_ref()
+            .ok_or(VerityError::InvalidRoyaltyRecipient)?;
+        if royalty > 0 {
+            emit!(RoyaltyPaid {
+                mint: listing.mint,
+                payments: vec![RoyaltyPayment {
+                    creator: recipient,
+                    amount: royalty,
+                }],
+                total: royalty,
+                metadata_bps,
+                enforced_bps,
+                royalty_override: Some(recipient),
+            });
+        }
+        if recipient == seller_key {
+            royalty
+        } else {
+            transfer_lamports(
+                &buyer_info,
+                &royalty_recipient.to_account_info(),
+                &system_program_info,
                 royalty,
-            ),
-            &[
-                ctx.accounts.buyer.to_account_info(),
-                ctx.accounts.seller.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
-    }
+            )?;
+            0
+        }
+    } else {
+        match &creators {
+            Some(creators) => {
+                let creator_accounts = ctx
+                    .remaining_accounts
+                    .get(ctx.accounts.config.fee_shares.len()..)
+                    .unwrap_or(&[]);
+                let payments = pay_creator_royalties(
+                    &listing.mint,
+                    creators,
+                    royalty,
+                    ctx.accounts.config.redistribute_unverified_royalty,
+                    ctx.accounts.config.escrow_royalties,
+                    &buyer_info,
+                    creator_accounts,
+                    &system_program_info,
+                    &seller_key,
+                )?;
+                let owed_to_seller = payments
+                    .iter()
+                    .filter(|payment| payment.creator == seller_key)
+                    .map(|payment| payment.amount)
+                    .sum();
+                if royalty > 0 {
+                    emit!(RoyaltyPaid {
+                        mint: listing.mint,
+                        payments,
+                        total: royalty,
+                        metadata_bps,
+                        enforced_bps,
+                        royalty_override: None,
+                    });
+                }
+                owed_to_seller
+            }
+            // No creators (or royalties not enforced): nothing was charged
+            None => 0,
+        }
+    };
+    seller_total = seller_total
+        .checked_add(royalty_owed_to_seller)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    
+    // Transfer SOL to seller (or into their Proceeds PDA to claim later)
+    let seller_destination = if listing.escrow_proceeds {
+        ctx.accounts
+            .proceeds
+            .as_ref()
+            .ok_or(VerityError::InvalidProceedsAccount)?
+            .to_account_info()
+    } else {
+        seller_info
+    };
+    transfer_lamports(&buyer_info, &seller_destination, &system_program_info, seller_total)?;
+    
+    // Token leg of a split-payment listing, at the same fee / royalty rates
+    let token_leg = match listing.split_payment {
+        Some(split) => {
+            let (Some(payment_mint), Some(buyer_tokens), Some(seller_tokens), Some(fee_tokens)) = (
+                ctx.accounts.payment_mint.as_deref(),
+                ctx.accounts.buyer_payment_account.as_deref(),
+                ctx.accounts.seller_payment_account.as_deref(),
+                ctx.accounts.fee_recipient_payment_account.as_deref(),
+            ) else {
+                return err!(VerityError::InvalidPaymentAccounts);
+            };
+            let sol_royalty_accounts = match (listing.royalty_override, &creators) {
+                (None, Some(creators)) => creators.len(),
+                _ => 0,
+            };
+            let royalty_accounts = ctx
+                .remaining_accounts
+                .get(ctx.accounts.config.fee_shares.len() + sol_royalty_accounts..)
+                .unwrap_or(&[]);
+            pay_token_leg(
+                &split,
+                quantity,
+                fee_bps,
+                enforced_bps,
+                listing.royalty_override,
+                creators.as_deref(),
+                ctx.accounts.config.redistribute_unverified_royalty,
+                &seller_key,
+                &ctx.accounts.fee_recipient.key(),
+                TokenLegAccounts {
+                    buyer: &buyer_info,
+                    payment_mint,
+                    buyer_tokens,
+                    seller_tokens,
+                    fee_tokens,
+                    royalty_accounts,
+                    token_program: &ctx.accounts.token_program.to_account_info(),
+                },
+            )?
+        }
+        None => TokenLeg {
+            price: 0,
+            marketplace_fee: 0,
+            royalty: 0,
+        },
+    };
     
-    // Transfer NFT from vault to buyer (signed by vault PDA)
+    // Transfer NFT (or the bought units) from vault to buyer (signed by vault PDA)
     let user_vault = &ctx.accounts.user_vault;
     let seeds = &[
         b"user_vault",
@@ -183,6 +519,24 @@ pub fn handler(ctx: Context<BuyNow>) -> Result<()> {
     ];
     let signer = &[&seeds[..]];
     
+    // Record the primary sale while the vault still owns the token, so the
+    // next sale pays royalties
+    if let (true, Some(metadata_program), Some(metadata_account)) = (
+        primary_sale,
+        &ctx.accounts.token_metadata_program,
+        &ctx.accounts.metadata,
+    ) {
+        metadata::update_primary_sale_happened_via_token(CpiContext::new_with_signer(
+            metadata_program.to_account_info(),
+            UpdatePrimarySaleHappenedViaToken {
+                metadata: metadata_account.to_account_info(),
+                owner: ctx.accounts.vault_pda.to_account_info(),
+                token: ctx.accounts.vault_ata.to_account_info(),
+            },
+            signer,
+        ))?;
+    }
+    
     let cpi_accounts = Transfer {
         from: ctx.accounts.vault_ata.to_account_info(),
         to: ctx.accounts.buyer_ata.to_account_info(),
@@ -193,10 +547,50 @@ pub fn handler(ctx: Context<BuyNow>) -> Result<()> {
         cpi_accounts,
         signer,
     );
-    token::transfer(cpi_ctx, 1)?;
+    token::transfer(cpi_ctx, quantity)?;
+    
+    // Update global marketplace statistics
+    let stats = &mut ctx.accounts.stats;
+    stats.total_sales = stats
+        .total_sales
+        .checked_add(1)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    stats.total_volume = stats
+        .total_volume
+        .checked_add(price)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    if sold_out {
+        stats.active_listings = stats.active_listings.saturating_sub(1);
+    }
     
-    // Mark listing as sold
-    listing.state = STATE_SOLD;
+    // Update per-user trading stats for both sides of the trade
+    let buyer_stats = &mut ctx.accounts.buyer_stats;
+    buyer_stats.user = ctx.accounts.buyer.key();
+    buyer_stats.bump = ctx.bumps.buyer_stats;
+    buyer_stats.record_purchase(price)?;
+    
+    let seller_stats = &mut ctx.accounts.seller_stats;
+    seller_stats.user = ctx.accounts.seller.key();
+    seller_stats.bump = ctx.bumps.seller_stats;
+    seller_stats.record_sale(price)?;
+    
+    // Fold the sale into the collection's average price
+    if let Some(collection) = collection {
+        let twap = ctx
+            .accounts
+            .collection_twap
+            .as_ref()
+            .ok_or(VerityError::MissingCollectionTwap)?;
+        let ema_price = record_collection_sale(
+            twap.as_ref(),
+            &collection,
+            unit_price,
+            clock.unix_timestamp,
+            &ctx.accounts.buyer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        msg!("Collection {} average price: {} lamports", collection, ema_price);
+    }
     
     msg!(
         "Purchase completed: buyer={}, seller={}, price={}",
@@ -205,6 +599,32 @@ pub fn handler(ctx: Context<BuyNow>) -> Result<()> {
         price
     );
     
-    // Listing account closes automatically (close = seller)
+    emit!(Sale {
+        listing: listing.key(),
+        mint: listing.mint,
+        seller: listing.seller,
+        buyer: ctx.accounts.buyer.key(),
+        price,
+        marketplace_fee,
+        royalty,
+        fee_exempt,
+        fee_bps,
+        community_amount,
+        quantity,
+        recipient: nft_owner.key(),
+        primary_sale,
+        payment_mint: listing.split_payment.map(|split| split.payment_mint),
+        token_price: token_leg.price,
+        token_marketplace_fee: token_leg.marketplace_fee,
+        token_royalty: token_leg.royalty,
+        fee_discounted,
+    });
+    
+    // Partially filled listings stay up for the remaining units
+    if sold_out {
+        ctx.accounts
+            .listing
+            .close(ctx.accounts.seller.to_account_info())?;
+    }
     Ok(())
 }
\ No newline at end of file
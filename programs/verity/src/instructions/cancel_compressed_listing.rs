@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use crate::state::{
+    CompressedListing, Config, ListingState, SellerState, Stats, ACCOUNT_VERSION,
+    relist_cooldown_passed
+};
+use crate::error::VerityError;
+
+/// Cancel a compressed listing
+/// The account closes (refunding rent and deposit) once Config.relist_cooldown
+/// has passed since the leaf was listed. Until then it is kept as Cancelled,
+/// so list_compressed can still see when the leaf was last listed; calling
+/// this again after the cooldown closes it
+#[derive(Accounts)]
+pub struct CancelCompressedListing<'info> {
+    /// Compressed listing to cancel (or a kept cancelled one to close)
+    #[account(
+        mut,
+        seeds = [
+            b"compressed_listing",
+            compressed_listing.seller.as_ref(),
+            compressed_listing.merkle_tree.as_ref(),
+            &compressed_listing.leaf_nonce.to_le_bytes()
+        ],
+        bump = compressed_listing.bump,
+        constraint = matches!(compressed_listing.state, ListingState::Active | ListingState::Cancelled)
+            @ VerityError::ListingNotActive
+    )]
+    pub compressed_listing: Account<'info, CompressedListing>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Seller's listing counter (list_compressed took a slot in it)
+    #[account(
+        mut,
+        seeds = [b"seller_state", compressed_listing.seller.as_ref()],
+        bump = seller_state.bump
+    )]
+    pub seller_state: Account<'info, SellerState>,
+    
+    #[account(
+        mut,
+        constraint = seller.key() == compressed_listing.seller @ VerityError::UnauthorizedSeller
+    )]
+    pub seller: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<CancelCompressedListing>) -> Result<()> {
+    let listing = &mut ctx.accounts.compressed_listing;
+    
+    // Mark listing as cancelled and give back the seller's slot
+    if listing.state == ListingState::Active {
+        listing.state = ListingState::Cancelled;
+        ctx.accounts.seller_state.release_slot();
+        
+        let stats = &mut ctx.accounts.stats;
+        stats.active_listings = stats.active_listings.saturating_sub(1);
+        
+        msg!(
+            "Compressed listing cancelled: seller={}, asset_id={}",
+            ctx.accounts.seller.key(),
+            listing.asset_id
+        );
+    }
+    
+    // The listing PDA stays recorded as leaf delegate, but it can only sign
+    // through buy_compressed, which requires an active listing account
+    // Closing drops listed_ts, so keep the account until the cooldown is over
+    let now = Clock::get()?.unix_timestamp;
+    if relist_cooldown_passed(listing.listed_ts, ctx.accounts.config.relist_cooldown, now) {
+        ctx.accounts
+            .compressed_listing
+            .close(ctx.accounts.seller.to_account_info())?;
+    } else {
+        msg!("Kept until the relist cooldown has passed");
+    }
+    Ok(())
+}
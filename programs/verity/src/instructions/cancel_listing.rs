@@ -1,16 +1,22 @@
 use anchor_lang::prelude::*;
-use crate::state::{Listing, UserVault, STATE_ACTIVE, STATE_CANCELLED};
+use crate::state::{Config, Listing, ListingState, SellerState, Stats, UserVault, ACCOUNT_VERSION};
 use crate::error::VerityError;
+use crate::events::{CancelReason, ListingCancelled};
+use crate::utils::release_seller_slot;
 
 #[derive(Accounts)]
 pub struct CancelListing<'info> {
-    /// Listing to cancel
+    /// Listing to cancel (or a deactivated listing to close)
     #[account(
         mut,
         close = seller,
         seeds = [b"listing", listing.seller.as_ref(), listing.mint.as_ref()],
         bump = listing.bump,
-        constraint = listing.state == STATE_ACTIVE @ VerityError::ListingNotActive
+        constraint = matches!(
+            listing.state,
+            ListingState::Active | ListingState::Cancelled
+        ) @ VerityError::ListingNotActive,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
     )]
     pub listing: Account<'info, Listing>,
     
@@ -18,31 +24,73 @@ pub struct CancelListing<'info> {
     #[account(
         seeds = [b"user_vault", seller.key().as_ref(), listing.mint.as_ref()],
         bump = user_vault.bump,
-        constraint = user_vault.key() == listing.user_vault @ VerityError::VaultMismatch
+        constraint = user_vault.key() == listing.user_vault @ VerityError::VaultMismatch,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
     )]
     pub user_vault: Account<'info, UserVault>,
     
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Seller's listing counter (required when the listing holds a slot)
+    #[account(
+        mut,
+        seeds = [b"seller_state", listing.seller.as_ref()],
+        bump = seller_state.bump
+    )]
+    pub seller_state: Option<Account<'info, SellerState>>,
+    
     #[account(
         mut,
         constraint = seller.key() == listing.seller @ VerityError::UnauthorizedSeller
     )]
     pub seller: Signer<'info>,
     
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
     pub system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<CancelListing>) -> Result<()> {
     let listing = &mut ctx.accounts.listing;
     
+    // A deactivated listing was already taken out of the active count
+    if listing.state == ListingState::Active {
+        let stats = &mut ctx.accounts.stats;
+        stats.active_listings = stats.active_listings.saturating_sub(1);
+    }
+    
+    release_seller_slot(listing, ctx.accounts.seller_state.as_deref_mut())?;
+    
     // Mark listing as cancelled
-    listing.state = STATE_CANCELLED;
+    listing.state = ListingState::Cancelled;
     
+    let reason = CancelReason::Seller;
     msg!(
-        "Listing cancelled: seller={}, mint={} (NFT remains in user vault)",
+        "Listing cancelled: seller={}, mint={}, reason={:?} (NFT remains in user vault)",
         ctx.accounts.seller.key(),
-        listing.mint
+        listing.mint,
+        reason
     );
     
+    emit!(ListingCancelled {
+        listing: listing.key(),
+        seller: listing.seller,
+        mint: listing.mint,
+        reason,
+    });
+    
     // NFT stays in user vault - seller retains control
     // Listing account closes automatically (close = seller)
     Ok(())
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, Listing, ListingState, SellerState, Stats, ACCOUNT_VERSION, MAX_BATCH_CANCEL};
+use crate::error::VerityError;
+use crate::events::{CancelReason, ListingCancelled};
+use crate::utils::release_seller_slot;
+
+/// Cancel several of the seller's listings in one transaction
+/// NFTs stay in their vaults; every listing account closes to the seller
+#[derive(Accounts)]
+pub struct CancelListingsBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Seller's listing counter (required when the listing holds a slot)
+    #[account(
+        mut,
+        seeds = [b"seller_state", seller.key().as_ref()],
+        bump = seller_state.bump
+    )]
+    pub seller_state: Option<Account<'info, SellerState>>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Remaining accounts: the listings to cancel (writable, 1..=MAX_BATCH_CANCEL),
+/// in any order. The whole batch fails if any listing isn't the signer's
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CancelListingsBatch<'info>>,
+) -> Result<()> {
+    let listing_infos = ctx.remaining_accounts;
+    require!(
+        !listing_infos.is_empty() && listing_infos.len() <= MAX_BATCH_CANCEL,
+        VerityError::InvalidBatchSize
+    );
+    
+    let seller = ctx.accounts.seller.key();
+    let mut active_cancelled: u64 = 0;
+    
+    for listing_info in listing_infos {
+        // Owner and discriminator are checked on load
+        let mut listing = Account::<Listing>::try_from(listing_info)?;
+        require_keys_eq!(listing.seller, seller, VerityError::UnauthorizedSeller);
+        require!(
+            listing.version == ACCOUNT_VERSION,
+            VerityError::UnsupportedAccountVersion
+        );
+        require!(
+            matches!(listing.state, ListingState::Active | ListingState::Cancelled),
+            VerityError::ListingNotActive
+        );
+        
+        // Same rule as cancel_listing: deactivated listings are already out
+        // of the active count
+        if listing.state == ListingState::Active {
+            active_cancelled += 1;
+        }
+        release_seller_slot(&mut listing, ctx.accounts.seller_state.as_deref_mut())?;
+        listing.state = ListingState::Cancelled;
+        
+        emit!(ListingCancelled {
+            listing: listing.key(),
+            seller,
+            mint: listing.mint,
+            reason: CancelReason::Seller,
+        });
+        
+        listing.close(ctx.accounts.seller.to_account_info())?;
+    }
+    
+    let stats = &mut ctx.accounts.stats;
+    stats.active_listings = stats.active_listings.saturating_sub(active_cancelled);
+    
+    msg!(
+        "Listings cancelled: seller={}, count={} (NFTs remain in user vaults)",
+        seller,
+        listing_infos.len()
+    );
+    
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::{UserVault, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::events::VaultStatus;
+use crate::utils::is_listing_active;
+
+/// Report whether a vault is locked by an active listing
+/// Read-only: emits a VaultStatus event so frontends know whether
+/// withdraw_from_vault will succeed without attempting it
+#[derive(Accounts)]
+pub struct CheckVaultStatus<'info> {
+    #[account(
+        seeds = [b"user_vault", user_vault.owner.as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    
+    /// Listing PDA for this vault (may not exist)
+    /// CHECK: Only inspected for an active listing
+    #[account(
+        seeds = [b"listing", user_vault.owner.as_ref(), user_vault.mint.as_ref()],
+        bump
+    )]
+    pub listing: UncheckedAccount<'info>,
+}
+
+pub fn handler(ctx: Context<CheckVaultStatus>) -> Result<()> {
+    let user_vault = &ctx.accounts.user_vault;
+    let is_locked = is_listing_active(&ctx.accounts.listing.to_account_info())?;
+    
+    emit!(VaultStatus {
+        user_vault: user_vault.key(),
+        owner: user_vault.owner,
+        mint: user_vault.mint,
+        is_locked,
+        active_listing: is_locked.then(|| ctx.accounts.listing.key()),
+    });
+    
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, Proceeds, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Pay out everything held in the seller's Proceeds PDA above its rent
+/// The account stays open for future escrowed sales
+#[derive(Accounts)]
+pub struct ClaimProceeds<'info> {
+    #[account(
+        mut,
+        seeds = [b"proceeds", seller.key().as_ref()],
+        bump = proceeds.bump,
+        has_one = seller @ VerityError::UnauthorizedSeller
+    )]
+    pub proceeds: Account<'info, Proceeds>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+}
+
+pub fn handler(ctx: Context<ClaimProceeds>) -> Result<()> {
+    let proceeds_info = ctx.accounts.proceeds.to_account_info();
+    let rent = Rent::get()?.minimum_balance(proceeds_info.data_len());
+    let amount = proceeds_info.lamports().saturating_sub(rent);
+    
+    // The program owns the PDA, so lamports move directly (no CPI)
+    **proceeds_info.try_borrow_mut_lamports()? -= amount;
+    **ctx.accounts.seller.to_account_info().try_borrow_mut_lamports()? += amount;
+    
+    msg!(
+        "Proceeds claimed: seller={}, amount={} lamports",
+        ctx.accounts.seller.key(),
+        amount
+    );
+    Ok(())
+}
\ No newline at end of file
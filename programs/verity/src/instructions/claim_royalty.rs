@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, RoyaltyEscrow, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::events::RoyaltyClaimed;
+
+/// Pay out everything held in the creator's RoyaltyEscrow above its rent
+/// The account stays open for future escrowed royalties
+#[derive(Accounts)]
+pub struct ClaimRoyalty<'info> {
+    #[account(
+        mut,
+        seeds = [b"royalty_escrow", creator.key().as_ref()],
+        bump = royalty_escrow.bump,
+        has_one = creator @ VerityError::InvalidRoyaltyRecipient
+    )]
+    pub royalty_escrow: Account<'info, RoyaltyEscrow>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+}
+
+pub fn handler(ctx: Context<ClaimRoyalty>) -> Result<()> {
+    let escrow_info = ctx.accounts.royalty_escrow.to_account_info();
+    let rent = Rent::get()?.minimum_balance(escrow_info.data_len());
+    let amount = escrow_info.lamports().saturating_sub(rent);
+    
+    // The program owns the PDA, so lamports move directly (no CPI)
+    **escrow_info.try_borrow_mut_lamports()? -= amount;
+    **ctx.accounts.creator.to_account_info().try_borrow_mut_lamports()? += amount;
+    
+    emit!(RoyaltyClaimed {
+        creator: ctx.accounts.creator.key(),
+        amount,
+    });
+    
+    msg!(
+        "Royalty claimed: creator={}, amount={} lamports",
+        ctx.accounts.creator.key(),
+        amount
+    );
+    Ok(())
+}
\ No newline at end of file
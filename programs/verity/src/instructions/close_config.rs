@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, Stats};
+use crate::error::VerityError;
+
+/// Close the config and stats singletons so initialize_config can run again
+/// (authority only). Meant for devnet resets and redeployments, so it is
+/// guarded: emergency mode must be on (user instructions are frozen) and no
+/// listing may still be active. Rent goes back to the authority
+#[derive(Accounts)]
+pub struct CloseConfig<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"config"],
+        bump,
+        has_one = authority @ VerityError::UnauthorizedAuthority,
+        constraint = config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyNotActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"stats"],
+        bump = stats.bump,
+        constraint = stats.active_listings == 0 @ VerityError::ActiveListingsRemain
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<CloseConfig>) -> Result<()> {
+    msg!(
+        "WARNING: marketplace config closed by {} - every instruction fails until initialize_config runs again",
+        ctx.accounts.authority.key()
+    );
+    msg!(
+        "Final stats: total_sales={}, total_volume={}",
+        ctx.accounts.stats.total_sales,
+        ctx.accounts.stats.total_volume
+    );
+    
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount};
+use crate::state::{Config, UserVault, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::utils::is_listing_active;
+
+/// Close an empty user vault and its ATA, returning rent to the owner
+/// Only allowed when the NFT is no longer in the vault and no active
+/// listing references it
+#[derive(Accounts)]
+pub struct CloseUserVault<'info> {
+    /// User vault
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.owner == owner.key() @ VerityError::UnauthorizedVaultOwner,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    
+    /// Vault PDA authority
+    /// CHECK: PDA signer
+    #[account(
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump
+    )]
+    pub vault_pda: UncheckedAccount<'info>,
+    
+    /// Vault ATA - must be empty
+    #[account(
+        mut,
+        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch
+    )]
+    pub vault_ata: Account<'info, TokenAccount>,
+    
+    /// Listing PDA for this vault (may not exist)
+    /// CHECK: Only inspected to make sure no active listing references the vault
+    #[account(
+        seeds = [b"listing", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump
+    )]
+    pub listing: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<CloseUserVault>) -> Result<()> {
+    // Never close a vault that still holds the NFT
+    require!(ctx.accounts.vault_ata.amount == 0, VerityError::VaultNotEmpty);
+    require!(
+        !is_listing_active(&ctx.accounts.listing.to_account_info())?,
+        VerityError::VaultLocked
+    );
+    
+    let user_vault = &ctx.accounts.user_vault;
+    let reclaimed = ctx
+        .accounts
+        .vault_ata
+        .to_account_info()
+        .lamports()
+        .checked_add(user_vault.to_account_info().lamports())
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    
+    // Close vault ATA (signed by vault PDA)
+    let seeds = &[
+        b"user_vault",
+        user_vault.owner.as_ref(),
+        user_vault.mint.as_ref(),
+        &[user_vault.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let cpi_close = CloseAccount {
+        account: ctx.accounts.vault_ata.to_account_info(),
+        destination: ctx.accounts.owner.to_account_info(),
+        authority: ctx.accounts.vault_pda.to_account_info(),
+    };
+    let cpi_ctx_close = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_close,
+        signer,
+    );
+    token::close_account(cpi_ctx_close)?;
+    
+    msg!(
+        "Empty vault closed: owner={}, mint={}, reclaimed={} lamports",
+        ctx.accounts.owner.key(),
+        user_vault.mint,
+        reclaimed
+    );
+    
+    // User vault closes automatically (close = owner)
+    Ok(())
+}
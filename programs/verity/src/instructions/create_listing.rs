@@ -1,9 +1,13 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::TokenAccount;
+use anchor_spl::metadata::MetadataAccount;
+use anchor_spl::token::{Mint, TokenAccount};
 use crate::state::{
-    Listing, UserVault, PriceConfig, PriceType, ListingConditions, STATE_ACTIVE
+    Config, Listing, ListingState, SellerState, UserVault, PriceConfig, PriceType, ListingConditions, Stats,
+    RoyaltyDefault, SplitPayment, FloorSource, MAX_MEMO_LEN, ACCOUNT_VERSION
 };
 use crate::error::VerityError;
+use crate::events::ListingCreated;
+use crate::utils::{open_listing, transfer_lamports};
 
 #[derive(Accounts)]
 pub struct CreateListing<'info> {
@@ -17,29 +21,80 @@ pub struct CreateListing<'info> {
     pub listing: Account<'info, Listing>,
     
     /// User vault must already exist and be owned by seller
+    /// Records when it was listed, for Config.relist_cooldown
     #[account(
+        mut,
         seeds = [b"user_vault", seller.key().as_ref(), mint.key().as_ref()],
         bump = user_vault.bump,
         constraint = user_vault.owner == seller.key() @ VerityError::UnauthorizedVaultOwner,
-        constraint = user_vault.mint == mint.key() @ VerityError::VaultMismatch
+        constraint = user_vault.mint == mint.key() @ VerityError::VaultMismatch,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
     )]
     pub user_vault: Account<'info, UserVault>,
     
-    /// Vault ATA must contain the NFT
+    /// Vault ATA must contain the NFT (or the units being listed)
     #[account(
         constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch,
-        constraint = vault_ata.amount == 1 @ VerityError::NftNotInVault
+        constraint = vault_ata.amount > 0 @ VerityError::NftNotInVault
     )]
     pub vault_ata: Account<'info, TokenAccount>,
     
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Seller's listing counter (created on their first listing)
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = SellerState::LEN,
+        seeds = [b"seller_state", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_state: Account<'info, SellerState>,
+    
     #[account(mut)]
     pub seller: Signer<'info>,
     
-    pub mint: UncheckedAccount<'info>,
+    /// Listed mint (its freeze authority is checked and recorded)
+    pub mint: Account<'info, Mint>,
+    
+    /// Optional Token Metadata account - when supplied, its collection (if
+    /// any) must be verified and is recorded on the listing
+    /// Required to set a royalty override (seller must be a verified creator)
+    /// or a min_floor checked against the collection TWAP
+    #[account(
+        constraint = metadata.mint == mint.key() @ VerityError::InvalidMetadata
+    )]
+    pub metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    
+    /// Fee recipient - required when boost_lamports is set
+    /// CHECK: Validated via config.accepts_fee_recipient
+    #[account(
+        mut,
+        constraint = config.accepts_fee_recipient(&fee_recipient.key(), Clock::get()?.unix_timestamp)
+    )]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    
+    /// Mint of split_payment.payment_mint - required with a split payment,
+    /// to check the payment_decimals recorded on the listing
+    pub payment_mint: Option<Box<Account<'info, Mint>>>,
     
     pub system_program: Program<'info, System>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<CreateListing>,
     price_type: PriceType,
@@ -50,20 +105,184 @@ pub fn handler(
     min_floor: Option<u64>,
     valid_from: Option<i64>,
     valid_until: Option<i64>,
+    min_price_is_floor_pct: Option<u16>,
+    memo: Option<String>,
+    oracle_feed: Option<Pubkey>,
+    royalty_override: Option<Pubkey>,
+    enforce_royalties: Option<bool>,
+    override_fee_bps: Option<u16>,
+    escrow_proceeds: bool,
+    quantity: Option<u64>,
+    min_usd_value: Option<u64>,
+    boost_lamports: Option<u64>,
+    split_payment: Option<SplitPayment>,
+    floor_source: Option<FloorSource>,
 ) -> Result<()> {
+    // Units for sale (prices are per unit); NFTs always list their single unit
+    let quantity = quantity.unwrap_or(1);
+    require!(
+        quantity > 0 && quantity <= ctx.accounts.vault_ata.amount,
+        VerityError::InvalidQuantity
+    );
+    
+    // A live freeze authority could trap the NFT in the vault: refused when
+    // the marketplace says so, otherwise flagged on the listing for buyers
+    let freezable = ctx.accounts.mint.freeze_authority.is_some();
+    if ctx.accounts.config.reject_freezable_mints {
+        require!(!freezable, VerityError::UnsupportedMint);
+    }
+    
     // Validate price configuration
-    require!(start_price > 0, VerityError::InvalidPrice);
-    require!(min_price > 0, VerityError::InvalidPrice);
-    require!(start_price >= min_price, VerityError::InvalidPrice);
+    let price_config = PriceConfig {
+        price_type,
+        start_price,
+        min_price,
+        start_ts,
+        duration,
+        min_price_is_floor_pct,
+    };
+    price_config.validate()?;
+    ctx.accounts.config.require_min_listing_price(&price_config)?;
+    
+    // Validity window and decay bounds (shared with every other listing path)
+    let clock = Clock::get()?;
+    let conditions = ListingConditions {
+        min_floor,
+        valid_from,
+        valid_until,
+    };
+    ctx.accounts
+        .config
+        .validate_window(&price_config, &conditions, clock.unix_timestamp)?;
+    
+    // Bind an oracle-tracked floor to a specific feed so buyers can't
+    // substitute an oracle for a different collection
+    if min_price_is_floor_pct.is_some() {
+        require!(oracle_feed.is_some(), VerityError::MissingOracle);
+    }
+    
+    let memo = memo.unwrap_or_default();
+    require!(memo.len() <= MAX_MEMO_LEN, VerityError::MemoTooLong);
+    
+    // A zero floor can never be breached, so it is meaningless
+    if let Some(floor) = min_floor {
+        require!(floor > 0, VerityError::InvalidFloor);
+    }
+    
+    // The seller picks what the floor is checked against; an oracle floor
+    // reads the listing's own feed (a TWAP floor's collection is checked
+    // once the metadata has been read)
+    require!(
+        min_floor.is_some() == floor_source.is_some(),
+        VerityError::MissingFloorSource
+    );
+    if floor_source == Some(FloorSource::Oracle) {
+        require!(oracle_feed.is_some(), VerityError::MissingOracle);
+    }
     
-    // Validate duration for decay pricing
-    if price_type != PriceType::Fixed {
-        require!(duration > 0, VerityError::InvalidDuration);
+    // Same for a USD floor, which also needs the marketplace's SOL/USD feed
+    if let Some(min_usd) = min_usd_value {
+        require!(min_usd > 0, VerityError::InvalidFloor);
+        require!(
+            ctx.accounts.config.sol_usd_feed != Pubkey::default(),
+            VerityError::MissingOracle
+        );
     }
     
-    // Validate time window
-    if let (Some(from), Some(until)) = (valid_from, valid_until) {
-        require!(from < until, VerityError::InvalidTimeWindow);
+    // Split payments: only in a mint the authority allows, at that mint's
+    // decimals, the token leg must be worth something, and the Proceeds PDA
+    // only holds lamports
+    if let Some(split) = split_payment {
+        require!(
+            ctx.accounts.config.accepts_payment_mint(&split.payment_mint),
+            VerityError::UnsupportedPaymentMint
+        );
+        let payment_mint = ctx
+            .accounts
+            .payment_mint
+            .as_ref()
+            .filter(|mint| mint.key() == split.payment_mint)
+            .ok_or(VerityError::InvalidPaymentAccounts)?;
+        require!(
+            payment_mint.decimals == split.payment_decimals,
+            VerityError::InvalidPaymentDecimals
+        );
+        require!(split.token_amount > 0, VerityError::InvalidPrice);
+        require!(!escrow_proceeds, VerityError::InvalidPrice);
+    }
+    
+    // Redirecting royalties is reserved for the NFT's verified creators,
+    // otherwise any seller could simply pay the royalty to themselves
+    if royalty_override.is_some() {
+        let metadata = ctx
+            .accounts
+            .metadata
+            .as_ref()
+            .ok_or(VerityError::UnauthorizedRoyaltyOverride)?;
+        let seller = ctx.accounts.seller.key();
+        let is_verified_creator = metadata.creators.as_ref().is_some_and(|creators| {
+            creators
+                .iter()
+                .any(|creator| creator.verified && creator.address == seller)
+        });
+        require!(is_verified_creator, VerityError::UnauthorizedRoyaltyOverride);
+    }
+    
+    // Opting out of an enforced royalty default needs the authority's say-so
+    if enforce_royalties == Some(false) {
+        let config = &ctx.accounts.config;
+        require!(
+            config.royalty_default == RoyaltyDefault::Skip || config.allow_royalty_opt_out,
+            VerityError::RoyaltyOptOutDisabled
+        );
+    }
+    
+    // A fee override may discount the marketplace fee, but never below
+    // what the authority allows (and never above the global fee)
+    if let Some(bps) = override_fee_bps {
+        let config = &ctx.accounts.config;
+        require!(
+            bps >= config.min_listing_fee_bps() && bps <= config.fee_bps,
+            VerityError::InvalidFeeOverride
+        );
+    }
+    
+    // Throttle relisting the same vault (cancelling stays free)
+    ctx.accounts
+        .user_vault
+        .require_relist_cooldown_passed(ctx.accounts.config.relist_cooldown, clock.unix_timestamp)?;
+    ctx.accounts.user_vault.last_listed_ts = clock.unix_timestamp;
+    
+    // Per-seller cap on active listings (Config.max_active_listings) and the
+    // anti-spam deposit, held in the listing's lamports and returned with its
+    // rent when the listing is sold or cancelled, forfeited if it expires
+    let seller_state = &mut ctx.accounts.seller_state;
+    seller_state.seller = ctx.accounts.seller.key();
+    seller_state.bump = ctx.bumps.seller_state;
+    let deposit = open_listing(
+        &ctx.accounts.config,
+        seller_state,
+        &ctx.accounts.seller.to_account_info(),
+        &ctx.accounts.listing.to_account_info(),
+        0,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    // Optional placement boost: paid to the marketplace up front and never
+    // refunded, recorded for off-chain ranking
+    let boost = boost_lamports.unwrap_or(0);
+    if boost > 0 {
+        let fee_recipient = ctx
+            .accounts
+            .fee_recipient
+            .as_ref()
+            .ok_or(VerityError::MissingFeeRecipient)?;
+        transfer_lamports(
+            &ctx.accounts.seller.to_account_info(),
+            &fee_recipient.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            boost,
+        )?;
     }
     
     let listing = &mut ctx.accounts.listing;
@@ -72,23 +291,57 @@ pub fn handler(
     listing.user_vault = ctx.accounts.user_vault.key();
     
     // Price configuration
-    listing.price_config = PriceConfig {
-        price_type,
-        start_price,
-        min_price,
-        start_ts,
-        duration,
-    };
+    listing.price_config = price_config;
     
     // Conditional listing features
-    listing.conditions = ListingConditions {
-        min_floor,
-        valid_from,
-        valid_until,
+    listing.conditions = conditions;
+    
+    // Collection gate (only when metadata is supplied): a collection claim
+    // must be verified
+    listing.collection = match ctx
+        .accounts
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.collection.as_ref())
+    {
+        Some(collection) => {
+            require!(collection.verified, VerityError::InvalidMetadata);
+            Some(collection.key)
+        }
+        None => None,
     };
     
-    listing.state = STATE_ACTIVE;
+    // A TWAP floor is checked against the collection's average sale price,
+    // so it needs a verified collection
+    if floor_source == Some(FloorSource::CollectionTwap) {
+        require!(listing.collection.is_some(), VerityError::FloorNeedsCollection);
+    }
+    
+    listing.state = ListingState::Active;
     listing.bump = ctx.bumps.listing;
+    listing.created_ts = clock.unix_timestamp;
+    listing.updated_ts = clock.unix_timestamp;
+    listing.memo = memo;
+    listing.oracle_feed = oracle_feed;
+    listing.version = ACCOUNT_VERSION;
+    listing.royalty_override = royalty_override;
+    listing.enforce_royalties = enforce_royalties;
+    listing.override_fee_bps = override_fee_bps;
+    listing.escrow_proceeds = escrow_proceeds;
+    listing.quantity = quantity;
+    listing.deposit = deposit;
+    listing.seller_counted = true;
+    listing.min_usd_value = min_usd_value;
+    listing.boost_score = boost;
+    listing.freezable = freezable;
+    listing.split_payment = split_payment;
+    listing.floor_source = floor_source;
+    
+    let stats = &mut ctx.accounts.stats;
+    stats.active_listings = stats
+        .active_listings
+        .checked_add(1)
+        .ok_or(VerityError::ArithmeticOverflow)?;
     
     msg!(
         "Listing created: seller={}, mint={}, type={:?}, start_price={}, min_price={}",
@@ -99,9 +352,54 @@ pub fn handler(
         min_price
     );
     
-    if let Some(floor) = min_floor {
-        msg!("Floor protection: min_floor={}", floor);
+    if let Some(collection) = listing.collection {
+        msg!("Verified collection: {}", collection);
+    }
+    
+    if let (Some(floor), Some(source)) = (min_floor, floor_source) {
+        msg!("Floor protection: min_floor={} from {:?}", floor, source);
+    }
+    
+    if let Some(min_usd) = min_usd_value {
+        msg!("USD floor: min_usd_value={} micro-USD", min_usd);
+    }
+    
+    if let Some(pct) = min_price_is_floor_pct {
+        msg!("Decay floor tracks oracle: {}bps of collection floor", pct);
+    }
+    
+    if let Some(recipient) = royalty_override {
+        msg!("Royalty override: {}", recipient);
     }
     
+    if boost > 0 {
+        msg!("Boosted: {} lamports", boost);
+    }
+    
+    if freezable {
+        msg!("Warning: mint has a freeze authority");
+    }
+    
+    if let Some(split) = split_payment {
+        msg!(
+            "Split payment: +{} of {} per unit ({} decimals)",
+            split.token_amount,
+            split.payment_mint,
+            split.payment_decimals
+        );
+    }
+    
+    emit!(ListingCreated {
+        listing: listing.key(),
+        seller: listing.seller,
+        mint: listing.mint,
+        price_type,
+        start_price,
+        min_price,
+        collection: listing.collection,
+        memo: listing.memo.clone(),
+        boost_score: boost,
+    });
+    
     Ok(())
 }
\ No newline at end of file
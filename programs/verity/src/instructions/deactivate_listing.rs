@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, Listing, ListingState, SellerState, Stats, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::utils::release_seller_slot;
+
+/// Take a listing off the market without closing it
+/// The account stays open so reactivate_listing can relist without paying
+/// rent again; cancel_listing still closes it for good
+#[derive(Accounts)]
+pub struct DeactivateListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", listing.seller.as_ref(), listing.mint.as_ref()],
+        bump = listing.bump,
+        has_one = seller @ VerityError::UnauthorizedSeller,
+        constraint = listing.state == ListingState::Active @ VerityError::ListingNotActive,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Seller's listing counter (required when the listing holds a slot)
+    #[account(
+        mut,
+        seeds = [b"seller_state", listing.seller.as_ref()],
+        bump = seller_state.bump
+    )]
+    pub seller_state: Option<Account<'info, SellerState>>,
+    
+    pub seller: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+}
+
+pub fn handler(ctx: Context<DeactivateListing>) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    listing.state = ListingState::Cancelled;
+    listing.updated_ts = Clock::get()?.unix_timestamp;
+    
+    let stats = &mut ctx.accounts.stats;
+    stats.active_listings = stats.active_listings.saturating_sub(1);
+    release_seller_slot(listing, ctx.accounts.seller_state.as_deref_mut())?;
+    
+    msg!(
+        "Listing deactivated: seller={}, mint={} (account kept for relisting)",
+        listing.seller,
+        listing.mint
+    );
+    
+    Ok(())
+}
\ No newline at end of file
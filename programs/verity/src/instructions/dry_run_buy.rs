@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{metadata::MetadataAccount, token::TokenAccount};
+use crate::state::{Config, FeeExemption, Listing, ListingState, UserVault, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::events::BuyPreview;
+use crate::utils::{quote_sale, SaleMetadata};
+
+/// Quote a purchase without making it
+/// Read-only: runs the same checks and fee / royalty math as buy_now and
+/// emits a BuyPreview event. Nothing is transferred and no buyer is needed;
+/// when buy_now would fail the event reports buyable = false and the
+/// reason is logged
+#[derive(Accounts)]
+pub struct DryRunBuy<'info> {
+    #[account(
+        seeds = [b"listing", listing.seller.as_ref(), listing.mint.as_ref()],
+        bump = listing.bump,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    /// User vault referenced by listing
+    #[account(
+        constraint = user_vault.key() == listing.user_vault @ VerityError::VaultMismatch
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    
+    /// Vault ATA holding the NFT (or the listed units)
+    #[account(
+        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch
+    )]
+    pub vault_ata: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Oracle quoting the collection floor in SOL (as for buy_now)
+    /// CHECK: Owner and layout validated by read_oracle_price_lamports
+    pub oracle: Option<UncheckedAccount<'info>>,
+    
+    /// SOL/USD oracle (Config.sol_usd_feed) - required when the listing
+    /// sets min_usd_value
+    /// CHECK: Owner and layout validated by read_sol_usd_price_micros
+    pub sol_usd_oracle: Option<UncheckedAccount<'info>>,
+    
+    /// Seller's fee exemption, if one was granted
+    #[account(
+        constraint = fee_exemption.seller == listing.seller @ VerityError::UnauthorizedSeller
+    )]
+    pub fee_exemption: Option<Box<Account<'info, FeeExemption>>>,
+    
+    /// Token Metadata account for the listed mint (as for buy_now)
+    #[account(
+        constraint = metadata.mint == listing.mint @ VerityError::InvalidMetadata
+    )]
+    pub metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    
+    /// RoyaltyPolicy PDA of the listing's verified collection (as for buy_now)
+    /// CHECK: Address and contents validated by read_royalty_policy
+    pub royalty_policy: Option<UncheckedAccount<'info>>,
+    
+    /// CollectionTwap PDA of the listing's verified collection (as for buy_now)
+    /// CHECK: Address and contents validated by read_collection_twap
+    pub collection_twap: Option<UncheckedAccount<'info>>,
+    
+    /// Prospective buyer's token account for Config.discount_mint (as for
+    /// buy_now, whose owner check needs the buyer)
+    #[account(
+        constraint = discount_token_account.mint == config.discount_mint @ VerityError::InvalidDiscountAccount
+    )]
+    pub discount_token_account: Option<Box<Account<'info, TokenAccount>>>,
+}
+
+pub fn handler(ctx: Context<DryRunBuy>, quantity: u64) -> Result<()> {
+    let listing = &ctx.accounts.listing;
+    let config = &ctx.accounts.config;
+    let now = Clock::get()?.unix_timestamp;
+    
+    let quote = (|| {
+        require!(!config.in_emergency(now), VerityError::EmergencyActive);
+        require!(
+            listing.state == ListingState::Active,
+            VerityError::ListingNotActive
+        );
+        let quote = quote_sale(
+            config,
+            &listing.sale_terms(),
+            quantity,
+            now,
+            ctx.accounts.oracle.as_ref().map(|oracle| oracle.as_ref()),
+            ctx.accounts.sol_usd_oracle.as_ref().map(|oracle| oracle.as_ref()),
+            ctx.accounts.fee_exemption.is_some(),
+            ctx.accounts
+                .discount_token_account
+                .as_ref()
+                .is_some_and(|account| config.fee_discount_applies(account.amount)),
+            ctx.accounts
+                .metadata
+                .as_deref()
+                .map(|metadata| SaleMetadata::from(&**metadata))
+                .as_ref(),
+            ctx.accounts.royalty_policy.as_ref().map(|policy| policy.as_ref()),
+            ctx.accounts.collection_twap.as_ref().map(|twap| twap.as_ref()),
+        )?;
+        require!(
+            ctx.accounts.vault_ata.amount >= quantity,
+            VerityError::NftNotInVault
+        );
+        Ok(quote)
+    })();
+    
+    let preview = match quote {
+        Ok(quote) => BuyPreview {
+            listing: listing.key(),
+            quantity,
+            price: quote.price,
+            marketplace_fee: quote.marketplace_fee,
+            royalty: quote.royalty,
+            community_amount: quote.community_amount,
+            seller_amount: quote.seller_amount,
+            total: quote.price,
+            buyable: true,
+        },
+        Err(err) => {
+            msg!("Not buyable: {}", err);
+            BuyPreview {
+                listing: listing.key(),
+                quantity,
+                price: 0,
+                marketplace_fee: 0,
+                royalty: 0,
+                community_amount: 0,
+                seller_amount: 0,
+                total: 0,
+                buyable: false,
+            }
+        }
+    };
+    
+    emit!(preview);
+    
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer},
+};
+use crate::state::{Config, UserVault, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::utils::{is_listing_active, require_token_account};
+
+/// Rescue an NFT from a vault whose regular withdraw keeps failing
+/// Same guarantees as withdraw_from_vault (owner only, no active listing),
+/// but the NFT goes to the owner's ATA, created here if needed, instead of a
+/// pre-existing token account the client has to get right
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    /// User vault
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.owner == owner.key() @ VerityError::UnauthorizedVaultOwner,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    
+    /// Vault PDA authority
+    /// CHECK: PDA signer
+    #[account(
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump
+    )]
+    pub vault_pda: UncheckedAccount<'info>,
+    
+    /// Vault ATA holding the NFT (or semi-fungible units)
+    #[account(
+        mut,
+        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch,
+        constraint = vault_ata.amount > 0 @ VerityError::NftNotInVault
+    )]
+    pub vault_ata: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Owner's ATA for the vaulted mint (created if it doesn't exist)
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = owner
+    )]
+    pub destination: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = mint.key() == user_vault.mint @ VerityError::VaultMismatch
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// Listing PDA for this vault (may not exist)
+    /// CHECK: Only inspected to make sure no active listing references the vault
+    #[account(
+        seeds = [b"listing", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump
+    )]
+    pub listing: UncheckedAccount<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn handler(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+    // Never bypass a live listing - a buyer could be filling it
+    require!(
+        !is_listing_active(&ctx.accounts.listing.to_account_info())?,
+        VerityError::VaultLocked
+    );
+    
+    // An ATA adopted by init_if_needed is used as is; re-check it
+    require_token_account(
+        &ctx.accounts.destination,
+        &ctx.accounts.user_vault.mint,
+        &ctx.accounts.owner.key(),
+    )?;
+    
+    let user_vault = &ctx.accounts.user_vault;
+    let amount = ctx.accounts.vault_ata.amount;
+    
+    // Transfer everything in the vault to the owner's ATA
+    let seeds = &[
+        b"user_vault",
+        user_vault.owner.as_ref(),
+        user_vault.mint.as_ref(),
+        &[user_vault.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.vault_ata.to_account_info(),
+        to: ctx.accounts.destination.to_account_info(),
+        authority: ctx.accounts.vault_pda.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer,
+    );
+    token::transfer(cpi_ctx, amount)?;
+    
+    // Close vault ATA
+    let cpi_close = CloseAccount {
+        account: ctx.accounts.vault_ata.to_account_info(),
+        destination: ctx.accounts.owner.to_account_info(),
+        authority: ctx.accounts.vault_pda.to_account_info(),
+    };
+    let cpi_ctx_close = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_close,
+        signer,
+    );
+    token::close_account(cpi_ctx_close)?;
+    
+    msg!(
+        "Vault rescued: owner={}, mint={}, amount={}, destination={}",
+        ctx.accounts.owner.key(),
+        user_vault.mint,
+        amount,
+        ctx.accounts.destination.key()
+    );
+    
+    // User vault closes automatically (close = owner)
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, Listing, ListingState, SellerState, Stats, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::events::{CancelReason, ListingCancelled};
+use crate::utils::release_seller_slot;
+
+/// Close a listing whose time window has ended (permissionless crank)
+/// Only allowed once valid_until plus Config.expiry_grace has passed, so a
+/// buy_now sent just before the deadline still has time to land. Rent goes
+/// back to the seller and the NFT stays in their vault; the listing deposit
+/// is forfeited to the fee recipient
+#[derive(Accounts)]
+pub struct ExpireListing<'info> {
+    /// Listing to expire
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"listing", listing.seller.as_ref(), listing.mint.as_ref()],
+        bump = listing.bump,
+        constraint = listing.state == ListingState::Active @ VerityError::ListingNotActive,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    /// Seller (receives the listing rent)
+    /// CHECK: Must equal listing.seller
+    #[account(
+        mut,
+        constraint = seller.key() == listing.seller @ VerityError::UnauthorizedSeller
+    )]
+    pub seller: UncheckedAccount<'info>,
+    
+    /// Seller's listing counter (required when the listing holds a slot)
+    #[account(
+        mut,
+        seeds = [b"seller_state", listing.seller.as_ref()],
+        bump = seller_state.bump
+    )]
+    pub seller_state: Option<Account<'info, SellerState>>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Fee recipient (receives the forfeited listing deposit)
+    /// CHECK: Validated via config.accepts_fee_recipient
+    #[account(
+        mut,
+        constraint = config.accepts_fee_recipient(&fee_recipient.key(), Clock::get()?.unix_timestamp)
+    )]
+    pub fee_recipient: UncheckedAccount<'info>,
+    
+    /// Anyone can crank an expired listing
+    pub cranker: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<ExpireListing>) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    
+    // Listings without valid_until never expire
+    let valid_until = listing.conditions.valid_until.ok_or(VerityError::ListingNotExpired)?;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now >= valid_until.saturating_add(ctx.accounts.config.expiry_grace),
+        VerityError::ListingNotExpired
+    );
+    
+    let stats = &mut ctx.accounts.stats;
+    stats.active_listings = stats.active_listings.saturating_sub(1);
+    release_seller_slot(listing, ctx.accounts.seller_state.as_deref_mut())?;
+    
+    listing.state = ListingState::Expired;
+    
+    // Forfeit the deposit; the remaining lamports (rent) go to the seller
+    let listing_info = listing.to_account_info();
+    let forfeited = listing.deposit.min(listing_info.lamports());
+    **listing_info.try_borrow_mut_lamports()? -= forfeited;
+    **ctx.accounts.fee_recipient.to_account_info().try_borrow_mut_lamports()? += forfeited;
+    
+    let reason = CancelReason::Expired;
+    msg!(
+        "Listing expired: seller={}, mint={}, reason={:?} (NFT remains in user vault)",
+        listing.seller,
+        listing.mint,
+        reason
+    );
+    
+    emit!(ListingCancelled {
+        listing: listing.key(),
+        seller: listing.seller,
+        mint: listing.mint,
+        reason,
+    });
+    
+    // Listing account closes automatically (close = seller)
+    Ok(())
+}
\ No newline at end of file
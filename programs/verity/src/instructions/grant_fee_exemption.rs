@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, FeeExemption, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Waive the marketplace fee for a partner seller (authority only)
+#[derive(Accounts)]
+pub struct GrantFeeExemption<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = authority @ VerityError::UnauthorizedAuthority,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = FeeExemption::LEN,
+        seeds = [b"fee_exempt", seller.key().as_ref()],
+        bump
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
+    
+    /// Seller being exempted
+    /// CHECK: Any wallet can be exempted; only its key is stored
+    pub seller: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<GrantFeeExemption>) -> Result<()> {
+    let exemption = &mut ctx.accounts.fee_exemption;
+    exemption.seller = ctx.accounts.seller.key();
+    exemption.bump = ctx.bumps.fee_exemption;
+    
+    msg!("Fee exemption granted: seller={}", exemption.seller);
+    Ok(())
+}
\ No newline at end of file
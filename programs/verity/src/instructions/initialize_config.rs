@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
-use crate::state::Config;
-use crate::error::VerityError;
+use crate::state::{
+    Config, DustFeePolicy, RoyaltyDefault, Stats, ACCOUNT_VERSION, DEFAULT_MAX_DURATION, DEFAULT_MAX_ROYALTY_BPS,
+    DEFAULT_EXPIRY_GRACE, DEFAULT_MIN_DURATION, DEFAULT_START_TS_GRACE, PROGRAM_VERSION
+};
+use crate::events::ConfigInitialized;
 
 #[derive(Accounts)]
 pub struct InitializeConfig<'info> {
@@ -13,6 +16,16 @@ pub struct InitializeConfig<'info> {
     )]
     pub config: Account<'info, Config>,
     
+    /// Global marketplace statistics, created alongside the config
+    #[account(
+        init,
+        payer = authority,
+        space = Stats::LEN,
+        seeds = [b"stats"],
+        bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
@@ -24,13 +37,60 @@ pub fn handler(
     fee_bps: u16,
     fee_recipient: Pubkey,
 ) -> Result<()> {
-    require!(fee_bps <= 1000, VerityError::InvalidPrice); // Max 10% fee
+    Config::validate_fee_bps(fee_bps)?;
+    let now = Clock::get()?.unix_timestamp;
     
     let config = &mut ctx.accounts.config;
     config.authority = ctx.accounts.authority.key();
     config.fee_bps = fee_bps;
     config.fee_recipient = fee_recipient;
+    config.fee_shares = Vec::new();
+    config.max_royalty_bps = DEFAULT_MAX_ROYALTY_BPS;
+    config.start_ts_grace = DEFAULT_START_TS_GRACE;
+    config.min_duration = DEFAULT_MIN_DURATION;
+    config.max_duration = DEFAULT_MAX_DURATION;
+    config.dust_fee_policy = DustFeePolicy::Proportional;
+    config.min_fee_lamports = 0;
+    config.version = ACCOUNT_VERSION;
+    config.emergency_until = 0;
+    config.royalty_default = RoyaltyDefault::Enforce;
+    config.allow_royalty_opt_out = false;
+    config.max_fee_discount_bps = 0;
+    config.community_bps = 0;
+    config.community_recipient = Pubkey::default();
+    config.expiry_grace = DEFAULT_EXPIRY_GRACE;
+    config.min_royalty_bps = 0;
+    config.listing_deposit = 0;
+    config.relist_cooldown = 0;
+    config.previous_fee_recipient = Pubkey::default();
+    config.fee_recipient_changed_ts = 0;
+    config.max_active_listings = 0;
+    config.sol_usd_feed = Pubkey::default();
+    config.redistribute_unverified_royalty = false;
+    config.escrow_royalties = false;
+    config.reject_freezable_mints = false;
+    config.init_ts = now;
+    config.min_listing_price = 0;
+    config.discount_mint = Pubkey::default();
+    config.discount_threshold = 0;
+    config.discount_bps = 0;
+    config.payment_mints = Vec::new();
+    
+    let stats = &mut ctx.accounts.stats;
+    stats.total_volume = 0;
+    stats.total_sales = 0;
+    stats.active_listings = 0;
+    stats.bump = ctx.bumps.stats;
     
     msg!("Verity marketplace initialized: fee={}bps", fee_bps);
+    
+    emit!(ConfigInitialized {
+        authority: config.authority,
+        fee_bps,
+        fee_recipient,
+        program_version: PROGRAM_VERSION.to_string(),
+        account_version: ACCOUNT_VERSION,
+        timestamp: now,
+    });
     Ok(())
 }
\ No newline at end of file
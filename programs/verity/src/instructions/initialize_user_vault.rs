@@ -3,11 +3,13 @@ use anchor_spl::{
     associated_token::AssociatedToken,
     token::{self, Mint, Token, TokenAccount, Transfer},
 };
-use crate::state::UserVault;
+use crate::state::{Config, UserVault, ACCOUNT_VERSION};
 use crate::error::VerityError;
+use crate::utils::{require_is_nft, require_is_semi_fungible};
 
 /// Initialize a user-owned vault for NFT storage
 /// This vault belongs to the USER, not the marketplace
+/// Semi-fungible mints deposit the source account's whole balance
 #[derive(Accounts)]
 pub struct InitializeUserVault<'info> {
     /// User vault PDA - owned by the USER via seeds
@@ -31,12 +33,15 @@ pub struct InitializeUserVault<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
     
-    /// User's token account holding the NFT
+    /// User's token account holding the NFT (or the semi-fungible units)
     #[account(
         mut,
         constraint = owner_token_account.owner == owner.key() @ VerityError::UnauthorizedVaultOwner,
         constraint = owner_token_account.mint == mint.key() @ VerityError::UnsupportedMint,
-        constraint = owner_token_account.amount == 1 @ VerityError::InvalidTokenAmount
+        constraint = owner_token_account.amount > 0 @ VerityError::InvalidTokenAmount,
+        // A delegate (e.g. a staking program) could still move the token
+        // while the deposit is in flight
+        constraint = owner_token_account.delegate.is_none() @ VerityError::DelegatedTokenAccount
     )]
     pub owner_token_account: Account<'info, TokenAccount>,
     
@@ -51,15 +56,38 @@ pub struct InitializeUserVault<'info> {
     
     pub mint: Account<'info, Mint>,
     
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 pub fn handler(ctx: Context<InitializeUserVault>) -> Result<()> {
-    // Verify NFT standard (decimals = 0, supply = 1)
-    require!(ctx.accounts.mint.decimals == 0, VerityError::UnsupportedMint);
-    require!(ctx.accounts.mint.supply == 1, VerityError::UnsupportedMint);
+    // Verify NFT standard (decimals = 0, supply = 1, no mint authority), or a
+    // fixed-supply semi-fungible when more than one unit exists
+    if ctx.accounts.mint.supply == 1 {
+        require_is_nft(&ctx.accounts.mint)?;
+    } else {
+        require_is_semi_fungible(&ctx.accounts.mint)?;
+    }
+    
+    // Whoever holds a live freeze authority could freeze the vault ATA and
+    // trap the NFT, so the marketplace may refuse such mints outright
+    if ctx.accounts.config.reject_freezable_mints {
+        require!(
+            ctx.accounts.mint.freeze_authority.is_none(),
+            VerityError::UnsupportedMint
+        );
+    }
+    let amount = ctx.accounts.owner_token_account.amount;
     
     // Initialize user vault
     let vault = &mut ctx.accounts.user_vault;
@@ -67,21 +95,24 @@ pub fn handler(ctx: Context<InitializeUserVault>) -> Result<()> {
     vault.mint = ctx.accounts.mint.key();
     vault.vault_ata = ctx.accounts.vault_ata.key();
     vault.bump = ctx.bumps.user_vault;
+    vault.version = ACCOUNT_VERSION;
+    vault.last_listed_ts = 0;
     
-    // Transfer NFT from owner to vault
+    // Transfer NFT (or all units) from owner to vault
     let cpi_accounts = Transfer {
         from: ctx.accounts.owner_token_account.to_account_info(),
         to: ctx.accounts.vault_ata.to_account_info(),
         authority: ctx.accounts.owner.to_account_info(),
     };
     let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-    token::transfer(cpi_ctx, 1)?;
+    token::transfer(cpi_ctx, amount)?;
     
     msg!(
-        "User vault created: owner={}, mint={}, vault_ata={}",
+        "User vault created: owner={}, mint={}, vault_ata={}, amount={}",
         ctx.accounts.owner.key(),
         ctx.accounts.mint.key(),
-        ctx.accounts.vault_ata.key()
+        ctx.accounts.vault_ata.key(),
+        amount
     );
     
     Ok(())
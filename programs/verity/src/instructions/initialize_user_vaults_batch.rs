@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address, AssociatedToken},
+    token::{self, Mint, Token, TokenAccount, Transfer},
+};
+use crate::state::{Config, UserVault, ACCOUNT_VERSION, MAX_BATCH_VAULTS};
+use crate::error::VerityError;
+use crate::utils::{create_pda_account, require_is_nft, require_is_semi_fungible};
+
+/// Vault several NFTs in one transaction - initialize_user_vault for each
+/// (mint, owner_token_account) pair, with the same checks
+#[derive(Accounts)]
+pub struct InitializeUserVaultsBatch<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// Remaining accounts: 1..=MAX_BATCH_VAULTS groups of four, one per NFT
+/// (the whole batch fails if any group is invalid):
+/// 1. mint
+/// 2. owner_token_account (writable) - the owner's account holding it
+/// 3. user_vault (writable) - PDA [b"user_vault", owner, mint], not yet created
+/// 4. vault_ata (writable) - user_vault's ATA for the mint, not yet created
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, InitializeUserVaultsBatch<'info>>,
+) -> Result<()> {
+    let groups = ctx.remaining_accounts;
+    require!(
+        !groups.is_empty() && groups.len() % 4 == 0 && groups.len() / 4 <= MAX_BATCH_VAULTS,
+        VerityError::InvalidBatchSize
+    );
+    
+    let owner = ctx.accounts.owner.key();
+    let owner_info = ctx.accounts.owner.to_account_info();
+    let system_program_info = ctx.accounts.system_program.to_account_info();
+    
+    for group in groups.chunks(4) {
+        let [mint_info, owner_token_info, user_vault_info, vault_ata_info] = group else {
+            return err!(VerityError::InvalidBatchSize);
+        };
+        
+        // Same mint rules as initialize_user_vault
+        let mint = Account::<Mint>::try_from(mint_info)?;
+        if mint.supply == 1 {
+            require_is_nft(&mint)?;
+        } else {
+            require_is_semi_fungible(&mint)?;
+        }
+        if ctx.accounts.config.reject_freezable_mints {
+            require!(mint.freeze_authority.is_none(), VerityError::UnsupportedMint);
+        }
+        
+        let owner_token_account = Account::<TokenAccount>::try_from(owner_token_info)?;
+        require_keys_eq!(owner_token_account.owner, owner, VerityError::UnauthorizedVaultOwner);
+        require_keys_eq!(owner_token_account.mint, mint.key(), VerityError::UnsupportedMint);
+        require!(owner_token_account.amount > 0, VerityError::InvalidTokenAmount);
+        require!(
+            owner_token_account.delegate.is_none(),
+            VerityError::DelegatedTokenAccount
+        );
+        let amount = owner_token_account.amount;
+        
+        // The vault PDA doubles as the vault ATA's authority
+        let (vault_address, bump) = Pubkey::find_program_address(
+            &[b"user_vault", owner.as_ref(), mint.key().as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(user_vault_info.key(), vault_address, VerityError::VaultMismatch);
+        require_keys_eq!(
+            vault_ata_info.key(),
+            get_associated_token_address(&vault_address, &mint.key()),
+            VerityError::VaultMismatch
+        );
+        
+        // Create the vault (fails if this NFT is already vaulted)
+        require!(
+            user_vault_info.owner == &System::id() && user_vault_info.data_is_empty(),
+            VerityError::VaultMismatch
+        );
+        create_pda_account(
+            user_vault_info,
+            UserVault::LEN,
+            &[b"user_vault", owner.as_ref(), mint.key().as_ref(), &[bump]],
+            &owner_info,
+            &system_program_info,
+        )?;
+        let vault = UserVault {
+            owner,
+            mint: mint.key(),
+            vault_ata: vault_ata_info.key(),
+            bump,
+            version: ACCOUNT_VERSION,
+            last_listed_ts: 0,
+        };
+        vault.try_serialize(&mut &mut user_vault_info.try_borrow_mut_data()?[..])?;
+        
+        associated_token::create(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: owner_info.clone(),
+                associated_token: vault_ata_info.clone(),
+                authority: user_vault_info.clone(),
+                mint: mint_info.clone(),
+                system_program: system_program_info.clone(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
+        
+        // Transfer the NFT (or all units) from owner to vault
+        let cpi_accounts = Transfer {
+            from: owner_token_info.clone(),
+            to: vault_ata_info.clone(),
+            authority: owner_info.clone(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+        
+        msg!(
+            "User vault created: owner={}, mint={}, vault_ata={}, amount={}",
+            owner,
+            mint.key(),
+            vault_ata_info.key(),
+            amount
+        );
+    }
+    
+    msg!("Vaults created: owner={}, count={}", owner, groups.len() / 4);
+    
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::{self, TokenAccount},
+};
+use crate::state::{Config, Listing, ListingState, SellerState, Stats, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::events::{CancelReason, ListingCancelled};
+use crate::utils::release_seller_slot;
+
+/// Close a listing that can no longer be filled because its vault no longer
+/// holds the listed units (permissionless crank)
+/// Active and deactivated listings qualify; rent and any deposit go back to
+/// the seller
+#[derive(Accounts)]
+pub struct InvalidateListing<'info> {
+    /// Listing to invalidate
+    #[account(
+        mut,
+        close = seller,
+        seeds = [b"listing", listing.seller.as_ref(), listing.mint.as_ref()],
+        bump = listing.bump,
+        constraint = matches!(listing.state, ListingState::Active | ListingState::Cancelled)
+            @ VerityError::ListingNotActive,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    /// The listing's vault ATA - may already be closed
+    /// CHECK: Address derived from listing.user_vault; contents read in the handler
+    #[account(
+        constraint = vault_ata.key() == get_associated_token_address(&listing.user_vault, &listing.mint)
+            @ VerityError::VaultMismatch
+    )]
+    pub vault_ata: UncheckedAccount<'info>,
+    
+    /// Seller (receives the listing rent)
+    /// CHECK: Must equal listing.seller
+    #[account(
+        mut,
+        constraint = seller.key() == listing.seller @ VerityError::UnauthorizedSeller
+    )]
+    pub seller: UncheckedAccount<'info>,
+    
+    /// Seller's listing counter (required when the listing holds a slot)
+    #[account(
+        mut,
+        seeds = [b"seller_state", listing.seller.as_ref()],
+        bump = seller_state.bump
+    )]
+    pub seller_state: Option<Account<'info, SellerState>>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Anyone can invalidate an unfillable listing
+    pub cranker: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<InvalidateListing>) -> Result<()> {
+    // Only fire when the units are genuinely gone: a closed vault ATA holds
+    // nothing, an open one must hold less than the listing still offers
+    let vault_ata = &ctx.accounts.vault_ata;
+    let held = if vault_ata.data_is_empty() || vault_ata.owner != &token::ID {
+        0
+    } else {
+        let data = vault_ata.try_borrow_data()?;
+        TokenAccount::try_deserialize(&mut &data[..])?.amount
+    };
+    let listing = &mut ctx.accounts.listing;
+    require!(held < listing.remaining_quantity(), VerityError::VaultNotEmpty);
+    
+    // Deactivated listings are already out of the active count
+    if listing.state == ListingState::Active {
+        let stats = &mut ctx.accounts.stats;
+        stats.active_listings = stats.active_listings.saturating_sub(1);
+    }
+    release_seller_slot(listing, ctx.accounts.seller_state.as_deref_mut())?;
+    listing.state = ListingState::Cancelled;
+    
+    let reason = CancelReason::Invalidated;
+    msg!(
+        "Listing invalidated: seller={}, mint={}, reason={:?} (vault holds {})",
+        listing.seller,
+        listing.mint,
+        reason,
+        held
+    );
+    
+    emit!(ListingCancelled {
+        listing: listing.key(),
+        seller: listing.seller,
+        mint: listing.mint,
+        reason,
+    });
+    
+    // Listing account closes automatically (close = seller)
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,218 @@
+use anchor_lang::prelude::*;
+use mpl_bubblegum::instructions::{DelegateCpi, DelegateCpiAccounts, DelegateInstructionArgs};
+use mpl_bubblegum::programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID};
+use mpl_bubblegum::utils::get_asset_id;
+use crate::state::{
+    CompressedLeafArgs, CompressedListing, Config, ListingConditions, ListingState, PriceConfig,
+    PriceType, SellerState, Stats, ACCOUNT_VERSION, relist_cooldown_passed
+};
+use crate::error::VerityError;
+use crate::utils::open_listing;
+
+/// List a compressed NFT (Bubblegum)
+/// The seller keeps the leaf; the listing PDA becomes its leaf delegate
+/// Proof nodes for the leaf are passed as remaining accounts
+/// Gated like create_listing: the seller's active-listing cap, the listing
+/// deposit and the relist cooldown all apply
+#[derive(Accounts)]
+#[instruction(leaf: CompressedLeafArgs)]
+pub struct ListCompressed<'info> {
+    /// Created on first listing; a listing cancelled inside its relist
+    /// cooldown is kept (see cancel_compressed_listing) and reused here
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = CompressedListing::LEN,
+        seeds = [
+            b"compressed_listing",
+            seller.key().as_ref(),
+            merkle_tree.key().as_ref(),
+            &leaf.nonce.to_le_bytes()
+        ],
+        bump
+    )]
+    pub compressed_listing: Account<'info, CompressedListing>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Seller's listing counter (created on first use)
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = SellerState::LEN,
+        seeds = [b"seller_state", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_state: Account<'info, SellerState>,
+    
+    /// Current leaf owner
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// Current leaf delegate (the seller if none was set)
+    /// CHECK: Verified by Bubblegum against the leaf proof
+    pub previous_leaf_delegate: UncheckedAccount<'info>,
+    
+    /// Bubblegum tree config PDA
+    /// CHECK: Derived from merkle_tree under the Bubblegum program
+    #[account(
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = bubblegum_program.key()
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+    
+    /// CHECK: Verified by Bubblegum / account compression
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Noop program
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    
+    /// CHECK: SPL Account Compression program
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    
+    /// CHECK: Bubblegum program
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, ListCompressed<'info>>,
+    leaf: CompressedLeafArgs,
+    price_type: PriceType,
+    start_price: u64,
+    min_price: u64,
+    start_ts: i64,
+    duration: i64,
+    valid_from: Option<i64>,
+    valid_until: Option<i64>,
+) -> Result<()> {
+    // Validate price configuration
+    let price_config = PriceConfig {
+        price_type,
+        start_price,
+        min_price,
+        start_ts,
+        duration,
+        min_price_is_floor_pct: None,
+    };
+    price_config.validate()?;
+    let config = &ctx.accounts.config;
+    config.require_min_listing_price(&price_config)?;
+    
+    // Same validity window and decay bounds as create_listing
+    let clock = Clock::get()?;
+    let conditions = ListingConditions {
+        min_floor: None,
+        valid_from,
+        valid_until,
+    };
+    config.validate_window(&price_config, &conditions, clock.unix_timestamp)?;
+    
+    // A kept listing account must have been cancelled, and relisting its
+    // leaf waits out Config.relist_cooldown, as relisting a vault does
+    // (a freshly created account is zeroed, so listed_ts is 0)
+    let listing = &ctx.accounts.compressed_listing;
+    require!(
+        listing.listed_ts == 0 || listing.state == ListingState::Cancelled,
+        VerityError::ListingAlreadyActive
+    );
+    require!(
+        relist_cooldown_passed(listing.listed_ts, config.relist_cooldown, clock.unix_timestamp),
+        VerityError::RelistCooldown
+    );
+    
+    // Seller's active-listing cap and the anti-spam deposit (shared with
+    // create_listing)
+    let seller_state = &mut ctx.accounts.seller_state;
+    seller_state.seller = ctx.accounts.seller.key();
+    seller_state.bump = ctx.bumps.seller_state;
+    let deposit = open_listing(
+        config,
+        seller_state,
+        &ctx.accounts.seller.to_account_info(),
+        &ctx.accounts.compressed_listing.to_account_info(),
+        ctx.accounts.compressed_listing.deposit,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    // Make the listing PDA the leaf delegate (seller keeps ownership)
+    let proof: Vec<(&AccountInfo<'info>, bool, bool)> = ctx
+        .remaining_accounts
+        .iter()
+        .map(|account| (account, false, false))
+        .collect();
+    
+    let listing_info = ctx.accounts.compressed_listing.to_account_info();
+    DelegateCpi::new(
+        &ctx.accounts.bubblegum_program.to_account_info(),
+        DelegateCpiAccounts {
+            tree_config: &ctx.accounts.tree_config.to_account_info(),
+            leaf_owner: &ctx.accounts.seller.to_account_info(),
+            previous_leaf_delegate: &ctx.accounts.previous_leaf_delegate.to_account_info(),
+            new_leaf_delegate: &listing_info,
+            merkle_tree: &ctx.accounts.merkle_tree.to_account_info(),
+            log_wrapper: &ctx.accounts.log_wrapper.to_account_info(),
+            compression_program: &ctx.accounts.compression_program.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+        },
+        DelegateInstructionArgs {
+            root: leaf.root,
+            data_hash: leaf.data_hash,
+            creator_hash: leaf.creator_hash,
+            nonce: leaf.nonce,
+            index: leaf.index,
+        },
+    )
+    .invoke_with_remaining_accounts(&proof)?;
+    
+    let merkle_tree = ctx.accounts.merkle_tree.key();
+    let listing = &mut ctx.accounts.compressed_listing;
+    listing.seller = ctx.accounts.seller.key();
+    listing.asset_id = get_asset_id(&merkle_tree, leaf.nonce);
+    listing.merkle_tree = merkle_tree;
+    listing.leaf_nonce = leaf.nonce;
+    listing.price_config = price_config;
+    listing.conditions = conditions;
+    listing.state = ListingState::Active;
+    listing.bump = ctx.bumps.compressed_listing;
+    listing.listed_ts = clock.unix_timestamp;
+    listing.deposit = deposit;
+    
+    let stats = &mut ctx.accounts.stats;
+    stats.active_listings = stats
+        .active_listings
+        .checked_add(1)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    
+    msg!(
+        "Compressed listing created: seller={}, asset_id={}, type={:?}, start_price={}, min_price={}",
+        ctx.accounts.seller.key(),
+        listing.asset_id,
+        price_type,
+        start_price,
+        min_price
+    );
+    
+    Ok(())
+}
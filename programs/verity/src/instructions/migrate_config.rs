@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use crate::state::{
+    Config, ACCOUNT_VERSION, DEFAULT_EXPIRY_GRACE, DEFAULT_MAX_DURATION, DEFAULT_MAX_ROYALTY_BPS,
+    DEFAULT_MIN_DURATION, DEFAULT_START_TS_GRACE
+};
+use crate::error::VerityError;
+use crate::utils::grow_account;
+
+/// Upgrade the Config written by an older program version to the current layout
+/// Grows the account (new fields start zeroed) and stamps ACCOUNT_VERSION
+/// The authority pays for any extra space
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: Older layouts may not deserialize until grown; the
+    /// discriminator and authority are checked in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateConfig>) -> Result<()> {
+    let config_info = ctx.accounts.config.to_account_info();
+    grow_account(
+        &config_info,
+        Config::LEN,
+        &ctx.accounts.authority.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    let mut config = Config::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+    require_keys_eq!(
+        config.authority,
+        ctx.accounts.authority.key(),
+        VerityError::UnauthorizedAuthority
+    );
+    require!(
+        config.version <= ACCOUNT_VERSION,
+        VerityError::UnsupportedAccountVersion
+    );
+    
+    let from_version = config.version;
+    if from_version < 1 {
+        // Settings added before versioning come back zeroed; a zero duration
+        // range or royalty cap would block listings and royalties, so start
+        // them from the defaults (the authority can adjust via update_config)
+        config.max_royalty_bps = DEFAULT_MAX_ROYALTY_BPS;
+        config.start_ts_grace = DEFAULT_START_TS_GRACE;
+        config.min_duration = DEFAULT_MIN_DURATION;
+        config.max_duration = DEFAULT_MAX_DURATION;
+    }
+    if from_version < 2 {
+        // Configs grown from an early v1 layout read zero here; a zero grace
+        // lets cranks front-run buyers at the deadline, so start from the
+        // default. init_ts was never recorded, so use the migration time
+        if config.expiry_grace == 0 {
+            config.expiry_grace = DEFAULT_EXPIRY_GRACE;
+        }
+        if config.init_ts == 0 {
+            config.init_ts = Clock::get()?.unix_timestamp;
+        }
+    }
+    config.version = ACCOUNT_VERSION;
+    config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+    
+    msg!(
+        "Config migrated: {} v{} -> v{}",
+        ctx.accounts.config.key(),
+        from_version,
+        ACCOUNT_VERSION
+    );
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, FloorSource, Listing, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::utils::grow_account;
+
+/// Upgrade a Listing written by an older program version to the current layout
+/// Grows the account (new fields start zeroed) and stamps ACCOUNT_VERSION
+/// The seller pays for any extra space
+#[derive(Accounts)]
+pub struct MigrateListing<'info> {
+    /// CHECK: Older layouts may not deserialize until grown; the
+    /// discriminator and seller are checked in the handler
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub listing: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateListing>) -> Result<()> {
+    let listing_info = ctx.accounts.listing.to_account_info();
+    grow_account(
+        &listing_info,
+        Listing::LEN,
+        &ctx.accounts.seller.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    let mut listing = Listing::try_deserialize(&mut &listing_info.try_borrow_data()?[..])?;
+    require_keys_eq!(
+        listing.seller,
+        ctx.accounts.seller.key(),
+        VerityError::UnauthorizedSeller
+    );
+    require!(
+        listing.version <= ACCOUNT_VERSION,
+        VerityError::UnsupportedAccountVersion
+    );
+    
+    let from_version = listing.version;
+    if from_version < 2 {
+        // Listings grown from an early v1 layout read a zero quantity;
+        // seed the single-unit default that predates partial fills
+        if listing.quantity == 0 {
+            listing.quantity = 1;
+        }
+    }
+    if from_version < 3 {
+        // Floors set before the seller could choose a source were checked
+        // against the collection's average sale price
+        if listing.conditions.min_floor.is_some() && listing.floor_source.is_none() {
+            listing.floor_source = Some(FloorSource::CollectionTwap);
+        }
+    }
+    listing.version = ACCOUNT_VERSION;
+    listing.try_serialize(&mut &mut listing_info.try_borrow_mut_data()?[..])?;
+    
+    msg!(
+        "Listing migrated: {} v{} -> v{}",
+        ctx.accounts.listing.key(),
+        from_version,
+        ACCOUNT_VERSION
+    );
+    Ok(())
+}
\ No newline at end of file
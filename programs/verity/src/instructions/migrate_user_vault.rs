@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, UserVault, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::utils::grow_account;
+
+/// Upgrade a UserVault written by an older program version to the current layout
+/// Grows the account (new fields start zeroed) and stamps ACCOUNT_VERSION
+/// The owner pays for any extra space
+#[derive(Accounts)]
+pub struct MigrateUserVault<'info> {
+    /// CHECK: Older layouts may not deserialize until grown; the
+    /// discriminator and owner are checked in the handler
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub user_vault: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigrateUserVault>) -> Result<()> {
+    let user_vault_info = ctx.accounts.user_vault.to_account_info();
+    grow_account(
+        &user_vault_info,
+        UserVault::LEN,
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    let mut user_vault = UserVault::try_deserialize(&mut &user_vault_info.try_borrow_data()?[..])?;
+    require_keys_eq!(
+        user_vault.owner,
+        ctx.accounts.owner.key(),
+        VerityError::UnauthorizedVaultOwner
+    );
+    require!(
+        user_vault.version <= ACCOUNT_VERSION,
+        VerityError::UnsupportedAccountVersion
+    );
+    
+    let from_version = user_vault.version;
+    if from_version < 2 {
+        // A vault grown from an early v1 layout may have listed before
+        // last_listed_ts existed, so start its relist cooldown now
+        if user_vault.last_listed_ts == 0 {
+            user_vault.last_listed_ts = Clock::get()?.unix_timestamp;
+        }
+    }
+    user_vault.version = ACCOUNT_VERSION;
+    user_vault.try_serialize(&mut &mut user_vault_info.try_borrow_mut_data()?[..])?;
+    
+    msg!(
+        "UserVault migrated: {} v{} -> v{}",
+        ctx.accounts.user_vault.key(),
+        from_version,
+        ACCOUNT_VERSION
+    );
+    Ok(())
+}
\ No newline at end of file
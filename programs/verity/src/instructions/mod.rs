@@ -1,13 +1,85 @@
+#![allow(ambiguous_glob_reexports)]
+
+pub mod add_payment_mint;
+pub mod buy_compressed;
 pub mod buy_now;
+pub mod cancel_compressed_listing;
 pub mod cancel_listing;
+pub mod cancel_listings_batch;
+pub mod check_vault_status;
+pub mod claim_proceeds;
+pub mod claim_royalty;
+pub mod close_config;
+pub mod close_user_vault;
 pub mod create_listing;
+pub mod deactivate_listing;
+pub mod dry_run_buy;
+pub mod emergency_withdraw;
+pub mod expire_listing;
+pub mod grant_fee_exemption;
 pub mod initialize_config;
 pub mod initialize_user_vault;
+pub mod initialize_user_vaults_batch;
+pub mod invalidate_listing;
+pub mod list_compressed;
+pub mod migrate_config;
+pub mod migrate_listing;
+pub mod migrate_user_vault;
+pub mod open_proceeds;
+pub mod preview_schedule;
+pub mod reactivate_listing;
+pub mod reconcile_vault;
+pub mod remove_payment_mint;
+pub mod resize_listing;
+pub mod revoke_fee_exemption;
+pub mod set_fee_recipients;
+pub mod set_royalty_policy;
+pub mod settle_otc;
+pub mod sweep_empty_vault_ata;
+pub mod transfer_vault_ownership;
+pub mod update_config;
+pub mod update_listing_price;
+pub mod update_listing_window;
 pub mod withdraw_from_vault;
 
+pub use add_payment_mint::*;
+pub use buy_compressed::*;
 pub use buy_now::*;
+pub use cancel_compressed_listing::*;
 pub use cancel_listing::*;
+pub use cancel_listings_batch::*;
+pub use check_vault_status::*;
+pub use claim_proceeds::*;
+pub use claim_royalty::*;
+pub use close_config::*;
+pub use close_user_vault::*;
 pub use create_listing::*;
+pub use deactivate_listing::*;
+pub use dry_run_buy::*;
+pub use emergency_withdraw::*;
+pub use expire_listing::*;
+pub use grant_fee_exemption::*;
 pub use initialize_config::*;
 pub use initialize_user_vault::*;
+pub use initialize_user_vaults_batch::*;
+pub use invalidate_listing::*;
+pub use list_compressed::*;
+pub use migrate_config::*;
+pub use migrate_listing::*;
+pub use migrate_user_vault::*;
+pub use open_proceeds::*;
+pub use preview_schedule::*;
+pub use reactivate_listing::*;
+pub use reconcile_vault::*;
+pub use remove_payment_mint::*;
+pub use resize_listing::*;
+pub use revoke_fee_exemption::*;
+pub use set_fee_recipients::*;
+pub use set_royalty_policy::*;
+pub use settle_otc::*;
+pub use sweep_empty_vault_ata::*;
+pub use transfer_vault_ownership::*;
+pub use update_config::*;
+pub use update_listing_price::*;
+pub use update_listing_window::*;
 pub use withdraw_from_vault::*;
\ No newline at end of file
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, Proceeds, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Create the seller's Proceeds PDA
+/// Needed before any listing with escrow_proceeds can be bought
+#[derive(Accounts)]
+pub struct OpenProceeds<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = Proceeds::LEN,
+        seeds = [b"proceeds", seller.key().as_ref()],
+        bump
+    )]
+    pub proceeds: Account<'info, Proceeds>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<OpenProceeds>) -> Result<()> {
+    let proceeds = &mut ctx.accounts.proceeds;
+    proceeds.seller = ctx.accounts.seller.key();
+    proceeds.bump = ctx.bumps.proceeds;
+    
+    msg!("Proceeds account opened: seller={}", proceeds.seller);
+    Ok(())
+}
\ No newline at end of file
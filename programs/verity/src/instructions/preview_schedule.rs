@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use crate::state::{calculate_price, Listing, ACCOUNT_VERSION, MAX_PREVIEW_SAMPLES};
+use crate::error::VerityError;
+use crate::events::{PricePoint, PriceSchedule};
+
+/// Preview a listing's price curve
+/// Read-only: emits a PriceSchedule event with `samples` points evenly
+/// spaced across [start_ts, start_ts + duration], priced by the same
+/// calculate_price buy_now uses. Oracle-tracked floors are not applied
+/// (the curve is shown down to the static min_price)
+#[derive(Accounts)]
+pub struct PreviewSchedule<'info> {
+    #[account(
+        seeds = [b"listing", listing.seller.as_ref(), listing.mint.as_ref()],
+        bump = listing.bump,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub listing: Account<'info, Listing>,
+}
+
+pub fn handler(ctx: Context<PreviewSchedule>, samples: u8) -> Result<()> {
+    require!(
+        samples > 0 && samples <= MAX_PREVIEW_SAMPLES,
+        VerityError::InvalidSampleCount
+    );
+    
+    let price_config = &ctx.accounts.listing.price_config;
+    let duration = price_config.duration.max(0) as i128;
+    let intervals = (samples as i128 - 1).max(1);
+    
+    let points = (0..samples as i128)
+        .map(|i| {
+            // i128 so start_ts + duration can't overflow
+            let offset = duration * i / intervals;
+            let timestamp = (price_config.start_ts as i128 + offset)
+                .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+            PricePoint {
+                timestamp,
+                price: calculate_price(price_config, timestamp),
+            }
+        })
+        .collect();
+    
+    emit!(PriceSchedule {
+        listing: ctx.accounts.listing.key(),
+        points,
+    });
+    
+    Ok(())
+}
\ No newline at end of file
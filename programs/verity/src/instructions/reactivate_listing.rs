@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+use crate::state::{
+    Config, Listing, ListingState, PriceConfig, PriceType, SellerState, Stats, UserVault, ACCOUNT_VERSION
+};
+use crate::error::VerityError;
+
+/// Put a deactivated listing back on the market with fresh price params
+/// Time window, floor settings and memo carry over from the original listing
+#[derive(Accounts)]
+pub struct ReactivateListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", seller.key().as_ref(), listing.mint.as_ref()],
+        bump = listing.bump,
+        has_one = seller @ VerityError::UnauthorizedSeller,
+        constraint = listing.state == ListingState::Cancelled @ VerityError::ListingNotActive,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    /// User vault must still exist
+    /// Records when it was listed, for Config.relist_cooldown
+    #[account(
+        mut,
+        seeds = [b"user_vault", seller.key().as_ref(), listing.mint.as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.key() == listing.user_vault @ VerityError::VaultMismatch,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    
+    /// Vault ATA must still contain the NFT
+    #[account(
+        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch,
+        constraint = vault_ata.amount >= listing.remaining_quantity() @ VerityError::NftNotInVault
+    )]
+    pub vault_ata: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Seller's listing counter (created on their first listing)
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = SellerState::LEN,
+        seeds = [b"seller_state", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_state: Account<'info, SellerState>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// Listed mint (its freeze authority is re-checked and recorded)
+    #[account(
+        constraint = mint.key() == listing.mint @ VerityError::VaultMismatch
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<ReactivateListing>,
+    price_type: PriceType,
+    start_price: u64,
+    min_price: u64,
+    start_ts: i64,
+    duration: i64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    
+    // The marketplace may have started rejecting freezable mints since the
+    // listing was created, and the freeze authority may have changed
+    let freezable = ctx.accounts.mint.freeze_authority.is_some();
+    if ctx.accounts.config.reject_freezable_mints {
+        require!(!freezable, VerityError::UnsupportedMint);
+    }
+    
+    // Same relist throttle as create_listing (deactivating stays free)
+    ctx.accounts
+        .user_vault
+        .require_relist_cooldown_passed(ctx.accounts.config.relist_cooldown, clock.unix_timestamp)?;
+    ctx.accounts.user_vault.last_listed_ts = clock.unix_timestamp;
+    
+    // Per-seller cap on active listings (Config.max_active_listings)
+    let seller_state = &mut ctx.accounts.seller_state;
+    seller_state.seller = ctx.accounts.seller.key();
+    seller_state.bump = ctx.bumps.seller_state;
+    seller_state.claim_slot(ctx.accounts.config.max_active_listings)?;
+    
+    let config = &ctx.accounts.config;
+    let listing = &mut ctx.accounts.listing;
+    
+    // Same price rules as create_listing (an oracle-tracked floor carries
+    // over, so it still needs a decaying price)
+    let price_config = PriceConfig {
+        price_type,
+        start_price,
+        min_price,
+        start_ts,
+        duration,
+        min_price_is_floor_pct: listing.price_config.min_price_is_floor_pct,
+    };
+    price_config.validate()?;
+    config.require_min_listing_price(&price_config)?;
+    
+    config.validate_window(&price_config, &listing.conditions, clock.unix_timestamp)?;
+    
+    listing.price_config = price_config;
+    listing.state = ListingState::Active;
+    listing.freezable = freezable;
+    listing.seller_counted = true;
+    listing.updated_ts = clock.unix_timestamp;
+    
+    let stats = &mut ctx.accounts.stats;
+    stats.active_listings = stats
+        .active_listings
+        .checked_add(1)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    
+    msg!(
+        "Listing reactivated: seller={}, mint={}, type={:?}, start={}, min={}",
+        listing.seller,
+        listing.mint,
+        price_type,
+        start_price,
+        min_price
+    );
+    
+    if freezable {
+        msg!("Warning: mint has a freeze authority");
+    }
+    
+    Ok(())
+}
\ No newline at end of file
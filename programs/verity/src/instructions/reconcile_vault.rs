@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount};
+use crate::state::{Config, Listing, ListingState, SellerState, Stats, UserVault, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::utils::release_seller_slot;
+
+/// Recover a vault whose NFT has gone missing
+/// Closes any listing still pointing at the vault (it can never be bought),
+/// then closes the empty vault ATA and the vault, returning rent to the owner
+#[derive(Accounts)]
+pub struct ReconcileVault<'info> {
+    /// User vault
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.owner == owner.key() @ VerityError::UnauthorizedVaultOwner,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    
+    /// Vault PDA authority
+    /// CHECK: PDA signer
+    #[account(
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump
+    )]
+    pub vault_pda: UncheckedAccount<'info>,
+    
+    /// Vault ATA - must have lost the NFT
+    #[account(
+        mut,
+        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch
+    )]
+    pub vault_ata: Account<'info, TokenAccount>,
+    
+    /// Stale listing for this vault, if one exists
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"listing", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = listing.bump,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub listing: Option<Account<'info, Listing>>,
+    
+    /// Seller's listing counter (required when the listing holds a slot)
+    #[account(
+        mut,
+        seeds = [b"seller_state", owner.key().as_ref()],
+        bump = seller_state.bump
+    )]
+    pub seller_state: Option<Account<'info, SellerState>>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<ReconcileVault>) -> Result<()> {
+    // Only for vaults whose NFT is gone - otherwise use withdraw_from_vault
+    require!(ctx.accounts.vault_ata.amount == 0, VerityError::VaultNotEmpty);
+    
+    // The listing can never be filled without the NFT, so drop it
+    if let Some(listing) = &mut ctx.accounts.listing {
+        if listing.state == ListingState::Active {
+            let stats = &mut ctx.accounts.stats;
+            stats.active_listings = stats.active_listings.saturating_sub(1);
+        }
+        release_seller_slot(listing, ctx.accounts.seller_state.as_deref_mut())?;
+        listing.state = ListingState::Cancelled;
+        msg!("Stale listing closed: mint={}", listing.mint);
+    }
+    
+    let user_vault = &ctx.accounts.user_vault;
+    
+    // Close vault ATA (signed by vault PDA)
+    let seeds = &[
+        b"user_vault",
+        user_vault.owner.as_ref(),
+        user_vault.mint.as_ref(),
+        &[user_vault.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let cpi_close = CloseAccount {
+        account: ctx.accounts.vault_ata.to_account_info(),
+        destination: ctx.accounts.owner.to_account_info(),
+        authority: ctx.accounts.vault_pda.to_account_info(),
+    };
+    let cpi_ctx_close = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_close,
+        signer,
+    );
+    token::close_account(cpi_ctx_close)?;
+    
+    msg!(
+        "Vault reconciled: owner={}, mint={} (NFT no longer in vault)",
+        ctx.accounts.owner.key(),
+        user_vault.mint
+    );
+    
+    // User vault and listing close automatically (close = owner)
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Stop allowing split payments priced in an SPL mint (authority only)
+#[derive(Accounts)]
+pub struct RemovePaymentMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        has_one = authority @ VerityError::UnauthorizedAuthority,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<RemovePaymentMint>, payment_mint: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.disallow_payment_mint(&payment_mint)?;
+    
+    msg!(
+        "Payment mint removed: {} ({} allowed)",
+        payment_mint,
+        config.payment_mints.len()
+    );
+    
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, Listing, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::utils::grow_account;
+
+/// Grow an existing listing account so it can hold fields added after it
+/// was created. New bytes are zeroed and the seller pays the extra rent.
+/// Can only grow (up to the current Listing::LEN), never shrink
+#[derive(Accounts)]
+pub struct ResizeListing<'info> {
+    /// CHECK: Older layouts may not deserialize until grown; the
+    /// discriminator and seller are checked in the handler
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub listing: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<ResizeListing>, new_len: u32) -> Result<()> {
+    let listing_info = ctx.accounts.listing.to_account_info();
+    let new_len = new_len as usize;
+    let old_len = listing_info.data_len();
+    require!(
+        new_len >= old_len && new_len <= Listing::LEN,
+        VerityError::InvalidAccountSize
+    );
+    
+    grow_account(
+        &listing_info,
+        new_len,
+        &ctx.accounts.seller.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+    
+    let listing = Listing::try_deserialize(&mut &listing_info.try_borrow_data()?[..])?;
+    require_keys_eq!(
+        listing.seller,
+        ctx.accounts.seller.key(),
+        VerityError::UnauthorizedSeller
+    );
+    
+    msg!(
+        "Listing resized: {} {} -> {} bytes",
+        ctx.accounts.listing.key(),
+        old_len,
+        new_len
+    );
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, FeeExemption, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Remove a seller's fee exemption (authority only)
+/// Rent goes back to the authority
+#[derive(Accounts)]
+pub struct RevokeFeeExemption<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = authority @ VerityError::UnauthorizedAuthority,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"fee_exempt", fee_exemption.seller.as_ref()],
+        bump = fee_exemption.bump
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<RevokeFeeExemption>) -> Result<()> {
+    msg!("Fee exemption revoked: seller={}", ctx.accounts.fee_exemption.seller);
+    Ok(())
+}
\ No newline at end of file
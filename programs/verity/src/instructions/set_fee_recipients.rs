@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, FeeShare, MAX_FEE_RECIPIENTS, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Route the marketplace fee to multiple recipients (e.g. DAO treasuries)
+/// An empty list sends the whole fee to config.fee_recipient again
+#[derive(Accounts)]
+pub struct SetFeeRecipients<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        has_one = authority @ VerityError::UnauthorizedAuthority,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<SetFeeRecipients>, fee_shares: Vec<FeeShare>) -> Result<()> {
+    require!(
+        fee_shares.len() <= MAX_FEE_RECIPIENTS,
+        VerityError::InvalidFeeRecipients
+    );
+    
+    if !fee_shares.is_empty() {
+        let mut total: u32 = 0;
+        for share in fee_shares.iter() {
+            require!(share.share_bps > 0, VerityError::InvalidFeeRecipients);
+            total += share.share_bps as u32;
+        }
+        require!(total == 10000, VerityError::InvalidFeeRecipients);
+    }
+    
+    let config = &mut ctx.accounts.config;
+    config.fee_shares = fee_shares;
+    
+    msg!("Fee recipients updated: count={}", config.fee_shares.len());
+    for share in config.fee_shares.iter() {
+        msg!("Fee share: recipient={}, share={}bps", share.recipient, share.share_bps);
+    }
+    
+    Ok(())
+}
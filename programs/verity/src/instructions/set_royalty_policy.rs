@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_spl::metadata::MetadataAccount;
+use crate::state::{Config, RoyaltyPolicy, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::events::RoyaltyPolicySet;
+
+/// Create or change a verified collection's royalty policy
+/// Signed by config.authority, or by a verified creator of the collection
+/// NFT (proven with its metadata account)
+#[derive(Accounts)]
+pub struct SetRoyaltyPolicy<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RoyaltyPolicy::LEN,
+        seeds = [b"royalty_policy", collection.key().as_ref()],
+        bump
+    )]
+    pub royalty_policy: Account<'info, RoyaltyPolicy>,
+    
+    /// Collection NFT mint (the key listings record as their collection)
+    /// CHECK: Only its key is used; the signer's authority over it is checked in the handler
+    pub collection: UncheckedAccount<'info>,
+    
+    /// Token Metadata account of the collection NFT - required when the
+    /// signer isn't config.authority
+    #[account(
+        constraint = collection_metadata.mint == collection.key() @ VerityError::InvalidMetadata
+    )]
+    pub collection_metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// config.authority or a verified creator of the collection
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<SetRoyaltyPolicy>, enforce: bool) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    let is_creator = ctx
+        .accounts
+        .collection_metadata
+        .as_ref()
+        .and_then(|metadata| metadata.creators.as_ref())
+        .is_some_and(|creators| {
+            creators
+                .iter()
+                .any(|creator| creator.verified && creator.address == authority)
+        });
+    require!(
+        authority == ctx.accounts.config.authority || is_creator,
+        VerityError::UnauthorizedRoyaltyPolicy
+    );
+    
+    let policy = &mut ctx.accounts.royalty_policy;
+    policy.collection = ctx.accounts.collection.key();
+    policy.enforce = enforce;
+    policy.bump = ctx.bumps.royalty_policy;
+    
+    emit!(RoyaltyPolicySet {
+        collection: policy.collection,
+        enforce,
+        authority,
+    });
+    
+    msg!(
+        "Royalty policy set: collection={}, enforce={}",
+        policy.collection,
+        enforce
+    );
+    Ok(())
+}
\ No newline at end of file
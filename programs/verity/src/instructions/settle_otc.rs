@@ -0,0 +1,333 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::MetadataAccount,
+    token::{self, Mint, Token, TokenAccount, Transfer},
+};
+use crate::state::{
+    Config, PaymentBreakdown, Stats, UserStats, UserVault, calculate_payment_breakdown, ACCOUNT_VERSION
+};
+use crate::oracle::read_oracle_price_lamports;
+use crate::error::VerityError;
+use crate::events::{OtcSettled, RoyaltyPaid};
+use crate::utils::{
+    is_listing_active, pay_community_cut, pay_creator_royalties, pay_marketplace_fee,
+    require_token_account, transfer_lamports
+};
+
+/// Settle a privately agreed sale in one transaction
+/// Both parties sign; the NFT moves from the seller's vault to the buyer and
+/// the agreed price is split like a sale (fee, royalty, proceeds) without a
+/// listing ever going live
+#[derive(Accounts)]
+pub struct SettleOtc<'info> {
+    /// Seller's vault holding the NFT
+    #[account(
+        seeds = [b"user_vault", seller.key().as_ref(), mint.key().as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.owner == seller.key() @ VerityError::UnauthorizedVaultOwner,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    
+    /// Vault PDA authority
+    /// CHECK: PDA signer for vault ATA
+    #[account(
+        seeds = [b"user_vault", seller.key().as_ref(), mint.key().as_ref()],
+        bump = user_vault.bump
+    )]
+    pub vault_pda: UncheckedAccount<'info>,
+    
+    /// Vault ATA holding the NFT
+    #[account(
+        mut,
+        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch,
+        constraint = vault_ata.amount == 1 @ VerityError::NftNotInVault
+    )]
+    pub vault_ata: Account<'info, TokenAccount>,
+    
+    /// Listing PDA for this vault (may not exist)
+    /// CHECK: Only inspected - an active listing must be cancelled first
+    #[account(
+        seeds = [b"listing", seller.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub listing: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// Must not be the seller: their UserStats PDAs would be the same account
+    #[account(
+        mut,
+        constraint = buyer.key() != seller.key() @ VerityError::SelfPurchase
+    )]
+    pub buyer: Signer<'info>,
+    
+    /// Buyer's ATA to receive the NFT (reused if the buyer already has one)
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_ata: Account<'info, TokenAccount>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"stats"],
+        bump = stats.bump
+    )]
+    pub stats: Account<'info, Stats>,
+    
+    /// Buyer's trading stats (created on first trade)
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserStats::LEN,
+        seeds = [b"user_stats", buyer.key().as_ref()],
+        bump
+    )]
+    pub buyer_stats: Account<'info, UserStats>,
+    
+    /// Seller's trading stats (created on first trade)
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = UserStats::LEN,
+        seeds = [b"user_stats", seller.key().as_ref()],
+        bump
+    )]
+    pub seller_stats: Account<'info, UserStats>,
+    
+    /// Fee recipient
+    /// CHECK: Validated via config.accepts_fee_recipient
+    #[account(
+        mut,
+        constraint = config.accepts_fee_recipient(&fee_recipient.key(), Clock::get()?.unix_timestamp)
+    )]
+    pub fee_recipient: UncheckedAccount<'info>,
+    
+    /// Community treasury (required while Config.community_bps is set)
+    /// CHECK: Validated via config.community_recipient
+    #[account(
+        mut,
+        constraint = community_recipient.key() == config.community_recipient @ VerityError::InvalidCommunityRecipient
+    )]
+    pub community_recipient: Option<UncheckedAccount<'info>>,
+    
+    /// Optional oracle quoting the collection floor in SOL - when supplied,
+    /// the agreed price may not be below it
+    /// CHECK: Owner and layout validated by read_oracle_price_lamports
+    pub oracle: Option<UncheckedAccount<'info>>,
+    
+    /// Token Metadata account for the mint - required while the marketplace
+    /// enforces royalties, which are paid to its creators
+    #[account(
+        constraint = metadata.mint == mint.key() @ VerityError::InvalidMetadata
+    )]
+    pub metadata: Option<Box<Account<'info, MetadataAccount>>>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+/// Remaining accounts: fee split recipients (when Config.fee_shares is set)
+/// in the same order as Config.fee_shares, followed - when the settlement
+/// pays a royalty - by the metadata creators in metadata order (their
+/// RoyaltyEscrow PDAs while Config.escrow_royalties is set)
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, SettleOtc<'info>>, price: u64) -> Result<()> {
+    require!(price > 0, VerityError::InvalidPrice);
+    require!(
+        !is_listing_active(&ctx.accounts.listing.to_account_info())?,
+        VerityError::VaultLocked
+    );
+    require_token_account(
+        &ctx.accounts.buyer_ata,
+        &ctx.accounts.mint.key(),
+        &ctx.accounts.buyer.key(),
+    )?;
+    
+    if let Some(oracle) = &ctx.accounts.oracle {
+        let floor = read_oracle_price_lamports(
+            &oracle.to_account_info(),
+            Clock::get()?.unix_timestamp,
+        )?;
+        require!(price >= floor, VerityError::PriceBelowOracleFloor);
+    }
+    
+    require!(
+        ctx.accounts.buyer.lamports() >= price,
+        VerityError::InsufficientFunds
+    );
+    
+    // Same royalty as a sale: seller_fee_basis_points from the metadata
+    // (which an enforced royalty requires), paid to its creators. Nothing
+    // marks the primary sale here, so it is never waived
+    let metadata = ctx.accounts.metadata.as_deref();
+    let creators = metadata
+        .and_then(|metadata| metadata.creators.clone())
+        .filter(|creators| !creators.is_empty());
+    let royalty_bps = if ctx.accounts.config.royalties_enforced(None) {
+        let metadata = metadata.ok_or(VerityError::MissingMetadata)?;
+        match &creators {
+            Some(_) => ctx.accounts.config.floored_royalty_bps(metadata.seller_fee_basis_points),
+            None => 0,
+        }
+    } else {
+        0
+    };
+    let enforced_bps = ctx.accounts.config.effective_royalty_bps(royalty_bps);
+    let breakdown = calculate_payment_breakdown(
+        price,
+        ctx.accounts.config.fee_bps,
+        enforced_bps,
+    )?;
+    let PaymentBreakdown {
+        marketplace_fee,
+        royalty,
+        seller_amount,
+    } = ctx.accounts.config.apply_dust_fee_policy(breakdown)?;
+    let community_amount = ctx.accounts.config.community_cut(price, seller_amount)?;
+    let seller_amount = seller_amount - community_amount;
+    
+    let buyer_info = ctx.accounts.buyer.to_account_info();
+    let seller_info = ctx.accounts.seller.to_account_info();
+    let system_program_info = ctx.accounts.system_program.to_account_info();
+    
+    let fee_owed_to_seller = pay_marketplace_fee(
+        &ctx.accounts.config,
+        marketplace_fee,
+        &buyer_info,
+        &ctx.accounts.fee_recipient.to_account_info(),
+        ctx.remaining_accounts,
+        &system_program_info,
+        seller_info.key,
+    )?;
+    pay_community_cut(
+        community_amount,
+        &buyer_info,
+        ctx.accounts
+            .community_recipient
+            .as_ref()
+            .map(|recipient| recipient.as_ref()),
+        &system_program_info,
+    )?;
+    
+    let royalty_owed_to_seller = match &creators {
+        Some(creators) if royalty > 0 => {
+            let creator_accounts = ctx
+                .remaining_accounts
+                .get(ctx.accounts.config.fee_shares.len()..)
+                .unwrap_or(&[]);
+            let payments = pay_creator_royalties(
+                &ctx.accounts.mint.key(),
+                creators,
+                royalty,
+                ctx.accounts.config.redistribute_unverified_royalty,
+                ctx.accounts.config.escrow_royalties,
+                &buyer_info,
+                creator_accounts,
+                &system_program_info,
+                seller_info.key,
+            )?;
+            let owed_to_seller = payments
+                .iter()
+                .filter(|payment| payment.creator == *seller_info.key)
+                .map(|payment| payment.amount)
+                .sum();
+            emit!(RoyaltyPaid {
+                mint: ctx.accounts.mint.key(),
+                payments,
+                total: royalty,
+                metadata_bps: metadata.map(|metadata| metadata.seller_fee_basis_points),
+                enforced_bps,
+                royalty_override: None,
+            });
+            owed_to_seller
+        }
+        _ => 0,
+    };
+    transfer_lamports(
+        &buyer_info,
+        &seller_info,
+        &system_program_info,
+        seller_amount
+            .checked_add(royalty_owed_to_seller)
+            .and_then(|total| total.checked_add(fee_owed_to_seller))
+            .ok_or(VerityError::ArithmeticOverflow)?,
+    )?;
+    
+    // Transfer NFT from vault to buyer (signed by vault PDA)
+    let user_vault = &ctx.accounts.user_vault;
+    let seeds = &[
+        b"user_vault",
+        user_vault.owner.as_ref(),
+        user_vault.mint.as_ref(),
+        &[user_vault.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.vault_ata.to_account_info(),
+        to: ctx.accounts.buyer_ata.to_account_info(),
+        authority: ctx.accounts.vault_pda.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer,
+    );
+    token::transfer(cpi_ctx, 1)?;
+    
+    // Counts as a sale, but never touched the active listing count
+    let stats = &mut ctx.accounts.stats;
+    stats.total_sales = stats
+        .total_sales
+        .checked_add(1)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    stats.total_volume = stats
+        .total_volume
+        .checked_add(price)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    
+    let buyer_stats = &mut ctx.accounts.buyer_stats;
+    buyer_stats.user = ctx.accounts.buyer.key();
+    buyer_stats.bump = ctx.bumps.buyer_stats;
+    buyer_stats.record_purchase(price)?;
+    
+    let seller_stats = &mut ctx.accounts.seller_stats;
+    seller_stats.user = ctx.accounts.seller.key();
+    seller_stats.bump = ctx.bumps.seller_stats;
+    seller_stats.record_sale(price)?;
+    
+    msg!(
+        "OTC sale settled: buyer={}, seller={}, price={}",
+        ctx.accounts.buyer.key(),
+        ctx.accounts.seller.key(),
+        price
+    );
+    
+    emit!(OtcSettled {
+        mint: ctx.accounts.mint.key(),
+        seller: ctx.accounts.seller.key(),
+        buyer: ctx.accounts.buyer.key(),
+        price,
+        marketplace_fee,
+        royalty,
+        community_amount,
+    });
+    
+    Ok(())
+}
\ No newline at end of file
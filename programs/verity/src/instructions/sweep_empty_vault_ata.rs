@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount};
+use crate::state::{Config, UserVault, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Close an orphaned vault whose ATA is empty (authority only)
+/// Maintenance counterpart of reconcile_vault for vaults their owner has
+/// abandoned: closes the empty vault ATA and the vault, returning all rent
+/// to the vault owner
+#[derive(Accounts)]
+pub struct SweepEmptyVaultAta<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump,
+        has_one = authority @ VerityError::UnauthorizedAuthority,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// User vault being swept
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.owner == owner.key() @ VerityError::UnauthorizedVaultOwner,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub user_vault: Account<'info, UserVault>,
+    
+    /// Vault PDA authority
+    /// CHECK: PDA signer
+    #[account(
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump
+    )]
+    pub vault_pda: UncheckedAccount<'info>,
+    
+    /// Vault ATA - must hold nothing
+    #[account(
+        mut,
+        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch,
+        constraint = vault_ata.amount == 0 @ VerityError::VaultNotEmpty
+    )]
+    pub vault_ata: Account<'info, TokenAccount>,
+    
+    /// Listing PDA for this vault - must not exist
+    /// CHECK: Only inspected; a vault with any listing is left to its owner
+    #[account(
+        seeds = [b"listing", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump
+    )]
+    pub listing: UncheckedAccount<'info>,
+    
+    /// Vault owner (receives the rent)
+    /// CHECK: Must equal user_vault.owner
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn handler(ctx: Context<SweepEmptyVaultAta>) -> Result<()> {
+    // A listing (active or deactivated) still references this vault - the
+    // owner has to clean it up with reconcile_vault
+    require!(
+        ctx.accounts.listing.data_is_empty(),
+        VerityError::VaultHasListing
+    );
+    
+    let user_vault = &ctx.accounts.user_vault;
+    
+    // Close vault ATA (signed by vault PDA)
+    let seeds = &[
+        b"user_vault",
+        user_vault.owner.as_ref(),
+        user_vault.mint.as_ref(),
+        &[user_vault.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let cpi_close = CloseAccount {
+        account: ctx.accounts.vault_ata.to_account_info(),
+        destination: ctx.accounts.owner.to_account_info(),
+        authority: ctx.accounts.vault_pda.to_account_info(),
+    };
+    let cpi_ctx_close = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_close,
+        signer,
+    );
+    token::close_account(cpi_ctx_close)?;
+    
+    msg!(
+        "Empty vault swept: owner={}, mint={}",
+        ctx.accounts.owner.key(),
+        user_vault.mint
+    );
+    
+    // User vault closes automatically (close = owner)
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,168 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer},
+};
+use crate::state::{Config, UserVault, ACCOUNT_VERSION};
+use crate::error::VerityError;
+use crate::utils::is_listing_active;
+
+/// Hand a vault (and the NFT in it) to another wallet
+/// The vault's seeds include its owner, so the old UserVault and its ATA
+/// are closed to the current owner and a new pair is opened for
+/// `new_owner` in the same transaction, the NFT moving between the ATAs
+/// Not allowed while an active listing references the vault
+#[derive(Accounts)]
+pub struct TransferVaultOwnership<'info> {
+    /// Current user vault (closed to the owner)
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump,
+        constraint = user_vault.owner == owner.key() @ VerityError::UnauthorizedVaultOwner,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub user_vault: Box<Account<'info, UserVault>>,
+    
+    /// Current vault PDA authority
+    /// CHECK: PDA signer
+    #[account(
+        seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump = user_vault.bump
+    )]
+    pub vault_pda: UncheckedAccount<'info>,
+    
+    /// Current vault ATA (emptied, then closed)
+    #[account(
+        mut,
+        constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch
+    )]
+    pub vault_ata: Box<Account<'info, TokenAccount>>,
+    
+    /// Listing PDA for the current vault (may not exist)
+    /// CHECK: Only inspected to make sure no active listing references the vault
+    #[account(
+        seeds = [b"listing", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump
+    )]
+    pub listing: UncheckedAccount<'info>,
+    
+    /// Wallet taking over the vault
+    /// CHECK: Any wallet can own a vault; only its key seeds the new one
+    pub new_owner: UncheckedAccount<'info>,
+    
+    /// New user vault PDA, owned by new_owner via seeds
+    #[account(
+        init,
+        payer = owner,
+        space = UserVault::LEN,
+        seeds = [b"user_vault", new_owner.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub new_user_vault: Box<Account<'info, UserVault>>,
+    
+    /// New vault PDA authority (derived from new_user_vault seeds)
+    /// CHECK: PDA signer for the new vault ATA
+    #[account(
+        seeds = [b"user_vault", new_owner.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub new_vault_pda: UncheckedAccount<'info>,
+    
+    /// New vault's ATA - receives the NFT (or all units)
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = new_vault_pda
+    )]
+    pub new_vault_ata: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        constraint = mint.key() == user_vault.mint @ VerityError::VaultMismatch
+    )]
+    pub mint: Box<Account<'info, Mint>>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+pub fn handler(ctx: Context<TransferVaultOwnership>) -> Result<()> {
+    require!(
+        !is_listing_active(&ctx.accounts.listing.to_account_info())?,
+        VerityError::VaultLocked
+    );
+    
+    let user_vault = &ctx.accounts.user_vault;
+    let amount = ctx.accounts.vault_ata.amount;
+    
+    // The new vault carries over last_listed_ts so a handoff can't be used
+    // to skip the relist cooldown
+    let new_vault = &mut ctx.accounts.new_user_vault;
+    new_vault.owner = ctx.accounts.new_owner.key();
+    new_vault.mint = user_vault.mint;
+    new_vault.vault_ata = ctx.accounts.new_vault_ata.key();
+    new_vault.bump = ctx.bumps.new_user_vault;
+    new_vault.version = ACCOUNT_VERSION;
+    new_vault.last_listed_ts = user_vault.last_listed_ts;
+    
+    // Move everything to the new vault ATA, then close the old one
+    // (both signed by the old vault PDA)
+    let seeds = &[
+        b"user_vault",
+        user_vault.owner.as_ref(),
+        user_vault.mint.as_ref(),
+        &[user_vault.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let cpi_transfer = Transfer {
+        from: ctx.accounts.vault_ata.to_account_info(),
+        to: ctx.accounts.new_vault_ata.to_account_info(),
+        authority: ctx.accounts.vault_pda.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_transfer,
+            signer,
+        ),
+        amount,
+    )?;
+    
+    let cpi_close = CloseAccount {
+        account: ctx.accounts.vault_ata.to_account_info(),
+        destination: ctx.accounts.owner.to_account_info(),
+        authority: ctx.accounts.vault_pda.to_account_info(),
+    };
+    token::close_account(CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_close,
+        signer,
+    ))?;
+    
+    msg!(
+        "Vault ownership transferred: mint={}, from={}, to={}, amount={}",
+        user_vault.mint,
+        ctx.accounts.owner.key(),
+        ctx.accounts.new_owner.key(),
+        amount
+    );
+    
+    // Old user vault closes automatically (close = owner)
+    Ok(())
+}
\ No newline at end of file
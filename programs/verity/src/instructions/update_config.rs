@@ -0,0 +1,254 @@
+use anchor_lang::prelude::*;
+use crate::state::{
+    Config, DustFeePolicy, RoyaltyDefault, MAX_EMERGENCY_DURATION, MAX_FEE_BPS, ACCOUNT_VERSION
+};
+use crate::error::VerityError;
+
+/// Marketplace settings the authority can change after initialization
+/// Fields left as None are unchanged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct UpdateConfigArgs {
+    pub max_royalty_bps: Option<u16>,
+    pub start_ts_grace: Option<i64>,
+    pub min_duration: Option<i64>,
+    pub max_duration: Option<i64>,
+    pub dust_fee_policy: Option<DustFeePolicy>,
+    pub min_fee_lamports: Option<u64>,
+    pub fee_bps: Option<u16>,
+    /// Unix timestamp until which user instructions are blocked
+    /// At most MAX_EMERGENCY_DURATION from now; any past value (e.g. 0) lifts it
+    pub emergency_until: Option<i64>,
+    pub royalty_default: Option<RoyaltyDefault>,
+    pub allow_royalty_opt_out: Option<bool>,
+    pub max_fee_discount_bps: Option<u16>,
+    /// Share of each sale price paid to community_recipient (0 disables it)
+    pub community_bps: Option<u16>,
+    pub community_recipient: Option<Pubkey>,
+    pub expiry_grace: Option<i64>,
+    pub min_royalty_bps: Option<u16>,
+    /// Refundable lamports locked in each new listing (forfeited on expiry)
+    pub listing_deposit: Option<u64>,
+    /// Seconds a vault must wait between listings (0 disables the cooldown)
+    pub relist_cooldown: Option<i64>,
+    /// New fee recipient; the old one stays valid for FEE_RECIPIENT_GRACE
+    pub fee_recipient: Option<Pubkey>,
+    /// Cap on each seller's active listings (0 = unlimited)
+    pub max_active_listings: Option<u32>,
+    /// SOL/USD oracle used for listings' min_usd_value
+    pub sol_usd_feed: Option<Pubkey>,
+    /// Skip unverified metadata creators, spreading their royalty share
+    /// over the verified ones
+    pub redistribute_unverified_royalty: Option<bool>,
+    /// Accrue creator royalties in RoyaltyEscrow PDAs for claim_royalty
+    pub escrow_royalties: Option<bool>,
+    /// Refuse to vault or list mints whose freeze authority is still set
+    pub reject_freezable_mints: Option<bool>,
+    /// Lowest start / min price for new listings (0 = no minimum)
+    pub min_listing_price: Option<u64>,
+    /// Governance token whose holders pay a reduced fee (default key disables it)
+    pub discount_mint: Option<Pubkey>,
+    /// discount_mint balance a buyer needs for the discount
+    pub discount_threshold: Option<u64>,
+    /// Fee bps taken off for qualifying buyers (the fee never goes below 0)
+    pub discount_bps: Option<u16>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        has_one = authority @ VerityError::UnauthorizedAuthority,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub authority: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<UpdateConfig>, args: UpdateConfigArgs) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    
+    if let Some(fee_bps) = args.fee_bps {
+        Config::validate_fee_bps(fee_bps)?;
+        config.fee_bps = fee_bps;
+        msg!("Marketplace fee updated: {}bps", fee_bps);
+    }
+    
+    if let Some(fee_recipient) = args.fee_recipient {
+        if fee_recipient != config.fee_recipient {
+            config.previous_fee_recipient = config.fee_recipient;
+            config.fee_recipient_changed_ts = Clock::get()?.unix_timestamp;
+            config.fee_recipient = fee_recipient;
+        }
+        msg!("Fee recipient updated: {}", fee_recipient);
+    }
+    
+    if let Some(max_royalty_bps) = args.max_royalty_bps {
+        config.max_royalty_bps = max_royalty_bps;
+        msg!("Royalty cap updated: {}bps", max_royalty_bps);
+    }
+    
+    if let Some(min_royalty_bps) = args.min_royalty_bps {
+        config.min_royalty_bps = min_royalty_bps;
+        msg!("Royalty floor updated: {}bps", min_royalty_bps);
+    }
+    
+    // The floor must sit under the cap, so it is also covered by the
+    // fee + royalty check below
+    require!(
+        config.min_royalty_bps <= config.max_royalty_bps,
+        VerityError::InvalidPrice
+    );
+    
+    if let Some(max_fee_discount_bps) = args.max_fee_discount_bps {
+        require!(max_fee_discount_bps <= MAX_FEE_BPS, VerityError::InvalidPrice);
+        config.max_fee_discount_bps = max_fee_discount_bps;
+        msg!("Listing fee discount cap updated: {}bps", max_fee_discount_bps);
+    }
+    
+    if let Some(community_recipient) = args.community_recipient {
+        config.community_recipient = community_recipient;
+        msg!("Community recipient updated: {}", community_recipient);
+    }
+    
+    if let Some(community_bps) = args.community_bps {
+        config.community_bps = community_bps;
+        msg!("Community share updated: {}bps", community_bps);
+    }
+    
+    require!(
+        config.community_bps == 0 || config.community_recipient != Pubkey::default(),
+        VerityError::InvalidCommunityRecipient
+    );
+    
+    // Fee, royalty and community share can never exceed the sale price
+    require!(
+        (config.max_royalty_bps as u32)
+            + (config.fee_bps as u32)
+            + (config.community_bps as u32)
+            <= 10000,
+        VerityError::InvalidPrice
+    );
+    
+    if let Some(start_ts_grace) = args.start_ts_grace {
+        require!(start_ts_grace >= 0, VerityError::InvalidTimeWindow);
+        config.start_ts_grace = start_ts_grace;
+        msg!("Decay start_ts grace updated: {}s", start_ts_grace);
+    }
+    
+    if let Some(expiry_grace) = args.expiry_grace {
+        require!(expiry_grace >= 0, VerityError::InvalidTimeWindow);
+        config.expiry_grace = expiry_grace;
+        msg!("Expiry grace updated: {}s", expiry_grace);
+    }
+    
+    if let Some(sol_usd_feed) = args.sol_usd_feed {
+        config.sol_usd_feed = sol_usd_feed;
+        msg!("SOL/USD feed updated: {}", sol_usd_feed);
+    }
+    
+    if let Some(max_active_listings) = args.max_active_listings {
+        config.max_active_listings = max_active_listings;
+        msg!("Active listing cap per seller updated: {}", max_active_listings);
+    }
+    
+    if let Some(relist_cooldown) = args.relist_cooldown {
+        require!(relist_cooldown >= 0, VerityError::InvalidTimeWindow);
+        config.relist_cooldown = relist_cooldown;
+        msg!("Relist cooldown updated: {}s", relist_cooldown);
+    }
+    
+    if args.min_duration.is_some() || args.max_duration.is_some() {
+        let min_duration = args.min_duration.unwrap_or(config.min_duration);
+        let max_duration = args.max_duration.unwrap_or(config.max_duration);
+        require!(min_duration > 0, VerityError::InvalidDuration);
+        require!(min_duration <= max_duration, VerityError::InvalidDuration);
+        config.min_duration = min_duration;
+        config.max_duration = max_duration;
+        msg!("Listing duration bounds updated: {}s..={}s", min_duration, max_duration);
+    }
+    
+    if let Some(dust_fee_policy) = args.dust_fee_policy {
+        config.dust_fee_policy = dust_fee_policy;
+        msg!("Dust fee policy updated: {:?}", dust_fee_policy);
+    }
+    
+    if let Some(min_fee_lamports) = args.min_fee_lamports {
+        config.min_fee_lamports = min_fee_lamports;
+        msg!("Minimum fee updated: {} lamports", min_fee_lamports);
+    }
+    
+    if let Some(listing_deposit) = args.listing_deposit {
+        config.listing_deposit = listing_deposit;
+        msg!("Listing deposit updated: {} lamports", listing_deposit);
+    }
+    
+    if let Some(redistribute) = args.redistribute_unverified_royalty {
+        config.redistribute_unverified_royalty = redistribute;
+        msg!("Unverified creator royalty redistribution: {}", redistribute);
+    }
+    
+    if let Some(escrow_royalties) = args.escrow_royalties {
+        config.escrow_royalties = escrow_royalties;
+        msg!("Creator royalty escrow: {}", escrow_royalties);
+    }
+    
+    if let Some(reject_freezable_mints) = args.reject_freezable_mints {
+        config.reject_freezable_mints = reject_freezable_mints;
+        msg!("Reject freezable mints: {}", reject_freezable_mints);
+    }
+    
+    if let Some(min_listing_price) = args.min_listing_price {
+        config.min_listing_price = min_listing_price;
+        msg!("Minimum listing price: {} lamports", min_listing_price);
+    }
+    
+    if args.discount_mint.is_some() || args.discount_threshold.is_some() || args.discount_bps.is_some() {
+        let discount_mint = args.discount_mint.unwrap_or(config.discount_mint);
+        let discount_threshold = args.discount_threshold.unwrap_or(config.discount_threshold);
+        let discount_bps = args.discount_bps.unwrap_or(config.discount_bps);
+        require!(discount_bps <= MAX_FEE_BPS, VerityError::InvalidPrice);
+        // A zero threshold would discount anyone passing an empty token account
+        require!(
+            discount_mint == Pubkey::default() || discount_bps == 0 || discount_threshold > 0,
+            VerityError::InvalidDiscountThreshold
+        );
+        config.discount_mint = discount_mint;
+        config.discount_threshold = discount_threshold;
+        config.discount_bps = discount_bps;
+        msg!(
+            "Fee discount updated: {}bps for holding {} of {}",
+            discount_bps,
+            discount_threshold,
+            discount_mint
+        );
+    }
+    
+    if let Some(royalty_default) = args.royalty_default {
+        config.royalty_default = royalty_default;
+        msg!("Royalty default updated: {:?}", royalty_default);
+    }
+    
+    if let Some(allow_royalty_opt_out) = args.allow_royalty_opt_out {
+        config.allow_royalty_opt_out = allow_royalty_opt_out;
+        msg!("Royalty opt-out allowed: {}", allow_royalty_opt_out);
+    }
+    
+    if let Some(emergency_until) = args.emergency_until {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            emergency_until <= now.saturating_add(MAX_EMERGENCY_DURATION),
+            VerityError::EmergencyTooLong
+        );
+        config.emergency_until = emergency_until;
+        if config.in_emergency(now) {
+            msg!("Emergency mode on until {}", emergency_until);
+        } else {
+            msg!("Emergency mode off");
+        }
+    }
+    
+    Ok(())
+}
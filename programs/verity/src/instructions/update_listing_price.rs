@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, Listing, ListingState, PriceConfig, PriceType, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Reprice an active listing in place
+/// Only the listing account is needed: its seeds come from the seller and
+/// mint stored on it, so clients don't have to re-derive the PDA
+#[derive(Accounts)]
+pub struct UpdateListingPrice<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", listing.seller.as_ref(), listing.mint.as_ref()],
+        bump = listing.bump,
+        has_one = seller @ VerityError::UnauthorizedSeller,
+        constraint = listing.state == ListingState::Active @ VerityError::ListingNotActive,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub seller: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+}
+
+pub fn handler(
+    ctx: Context<UpdateListingPrice>,
+    price_type: PriceType,
+    start_price: u64,
+    min_price: u64,
+    start_ts: i64,
+    duration: i64,
+) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let listing = &mut ctx.accounts.listing;
+    let now = Clock::get()?.unix_timestamp;
+    
+    // Same price rules as reactivate_listing (an oracle-tracked floor
+    // carries over, so it still needs a decaying price)
+    let price_config = PriceConfig {
+        price_type,
+        start_price,
+        min_price,
+        start_ts,
+        duration,
+        min_price_is_floor_pct: listing.price_config.min_price_is_floor_pct,
+    };
+    price_config.validate()?;
+    config.require_min_listing_price(&price_config)?;
+    
+    config.validate_window(&price_config, &listing.conditions, now)?;
+    
+    listing.price_config = price_config;
+    listing.updated_ts = now;
+    
+    msg!(
+        "Listing repriced: seller={}, mint={}, type={:?}, start={}, min={}",
+        listing.seller,
+        listing.mint,
+        price_type,
+        start_price,
+        min_price
+    );
+    
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use crate::state::{Config, Listing, ListingState, PriceType, ACCOUNT_VERSION};
+use crate::error::VerityError;
+
+/// Change a listing's valid_from / valid_until without relisting
+#[derive(Accounts)]
+pub struct UpdateListingWindow<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", listing.seller.as_ref(), listing.mint.as_ref()],
+        bump = listing.bump,
+        has_one = seller @ VerityError::UnauthorizedSeller,
+        constraint = listing.state == ListingState::Active @ VerityError::ListingNotActive,
+        constraint = listing.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
+    )]
+    pub listing: Account<'info, Listing>,
+    
+    pub seller: Signer<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+}
+
+pub fn handler(
+    ctx: Context<UpdateListingWindow>,
+    valid_from: Option<i64>,
+    valid_until: Option<i64>,
+) -> Result<()> {
+    let listing = &mut ctx.accounts.listing;
+    let now = Clock::get()?.unix_timestamp;
+    
+    // Same rule as create_listing
+    if let (Some(from), Some(until)) = (valid_from, valid_until) {
+        require!(from < until, VerityError::InvalidTimeWindow);
+    }
+    
+    // No retroactive changes: a window can't be closed in the past, and a
+    // listing that is already live can't be pushed back to "not yet valid"
+    if let Some(until) = valid_until {
+        require!(until > now, VerityError::InvalidTimeWindow);
+    }
+    let already_live = !matches!(listing.conditions.valid_from, Some(from) if from > now);
+    if let (true, Some(from)) = (already_live, valid_from) {
+        require!(from <= now, VerityError::InvalidTimeWindow);
+    }
+    
+    // Decay listings: the window must still overlap the decay window
+    let price_config = &listing.price_config;
+    if price_config.price_type != PriceType::Fixed {
+        if let Some(until) = valid_until {
+            require!(until > price_config.start_ts, VerityError::InvalidTimeWindow);
+        }
+        if let Some(from) = valid_from {
+            let decay_end = price_config
+                .start_ts
+                .checked_add(price_config.duration)
+                .ok_or(VerityError::ArithmeticOverflow)?;
+            require!(from < decay_end, VerityError::InvalidTimeWindow);
+        }
+    }
+    
+    listing.conditions.valid_from = valid_from;
+    listing.conditions.valid_until = valid_until;
+    listing.updated_ts = now;
+    
+    msg!(
+        "Listing window updated: mint={}, valid_from={:?}, valid_until={:?}",
+        listing.mint,
+        valid_from,
+        valid_until
+    );
+    
+    Ok(())
+}
\ No newline at end of file
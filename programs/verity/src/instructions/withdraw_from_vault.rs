@@ -1,10 +1,12 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer};
-use crate::state::UserVault;
+use crate::state::{Config, UserVault, ACCOUNT_VERSION};
 use crate::error::VerityError;
+use crate::utils::is_listing_active;
 
 /// Withdraw NFT from user vault back to owner
-/// Can only be done when no active listing exists
+/// Can only be done when no active listing exists and the vault's relist
+/// cooldown has passed
 #[derive(Accounts)]
 pub struct WithdrawFromVault<'info> {
     /// User vault
@@ -13,7 +15,8 @@ pub struct WithdrawFromVault<'info> {
         close = owner,
         seeds = [b"user_vault", owner.key().as_ref(), user_vault.mint.as_ref()],
         bump = user_vault.bump,
-        constraint = user_vault.owner == owner.key() @ VerityError::UnauthorizedVaultOwner
+        constraint = user_vault.owner == owner.key() @ VerityError::UnauthorizedVaultOwner,
+        constraint = user_vault.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion
     )]
     pub user_vault: Account<'info, UserVault>,
     
@@ -25,11 +28,11 @@ pub struct WithdrawFromVault<'info> {
     )]
     pub vault_pda: UncheckedAccount<'info>,
     
-    /// Vault ATA holding the NFT
+    /// Vault ATA holding the NFT (or semi-fungible units)
     #[account(
         mut,
         constraint = vault_ata.key() == user_vault.vault_ata @ VerityError::VaultMismatch,
-        constraint = vault_ata.amount == 1 @ VerityError::InvalidTokenAmount
+        constraint = vault_ata.amount > 0 @ VerityError::NftNotInVault
     )]
     pub vault_ata: Account<'info, TokenAccount>,
     
@@ -44,12 +47,42 @@ pub struct WithdrawFromVault<'info> {
     )]
     pub owner_token_account: Account<'info, TokenAccount>,
     
+    /// Listing PDA for this vault (may not exist)
+    /// CHECK: Only inspected to make sure no active listing references the vault
+    #[account(
+        seeds = [b"listing", owner.key().as_ref(), user_vault.mint.as_ref()],
+        bump
+    )]
+    pub listing: UncheckedAccount<'info>,
+    
+    /// Marketplace config (blocks this instruction during an emergency)
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.version == ACCOUNT_VERSION @ VerityError::UnsupportedAccountVersion,
+        constraint = !config.in_emergency(Clock::get()?.unix_timestamp) @ VerityError::EmergencyActive
+    )]
+    pub config: Account<'info, Config>,
+    
     pub token_program: Program<'info, Token>,
 }
 
 pub fn handler(ctx: Context<WithdrawFromVault>) -> Result<()> {
+    require!(
+        !is_listing_active(&ctx.accounts.listing.to_account_info())?,
+        VerityError::VaultLocked
+    );
+    
     let user_vault = &ctx.accounts.user_vault;
     
+    // Closing the vault drops last_listed_ts, so a fresh deposit could be
+    // listed straight away: the vault stays open until the relist cooldown
+    // has passed (emergency_withdraw is never throttled)
+    user_vault.require_relist_cooldown_passed(
+        ctx.accounts.config.relist_cooldown,
+        Clock::get()?.unix_timestamp,
+    )?;
+    
     // Transfer NFT from vault back to owner
     let seeds = &[
         b"user_vault",
@@ -69,7 +102,7 @@ pub fn handler(ctx: Context<WithdrawFromVault>) -> Result<()> {
         cpi_accounts,
         signer,
     );
-    token::transfer(cpi_ctx, 1)?;
+    token::transfer(cpi_ctx, ctx.accounts.vault_ata.amount)?;
     
     // Close vault ATA
     let cpi_close = CloseAccount {
@@ -1,8 +1,13 @@
+#![allow(unexpected_cfgs)]
+
 use anchor_lang::prelude::*;
 
 pub mod error;
+pub mod events;
 pub mod instructions;
+pub mod oracle;
 pub mod state;
+pub mod utils;
 
 use instructions::*;
 
@@ -20,10 +25,25 @@ pub mod verity {
         initialize_config::handler(ctx, fee_bps, fee_recipient)
     }
 
+    pub fn update_config(ctx: Context<UpdateConfig>, args: UpdateConfigArgs) -> Result<()> {
+        update_config::handler(ctx, args)
+    }
+
+    pub fn close_config(ctx: Context<CloseConfig>) -> Result<()> {
+        close_config::handler(ctx)
+    }
+
     pub fn initialize_user_vault(ctx: Context<InitializeUserVault>) -> Result<()> {
         initialize_user_vault::handler(ctx)
     }
 
+    pub fn initialize_user_vaults_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, InitializeUserVaultsBatch<'info>>,
+    ) -> Result<()> {
+        initialize_user_vaults_batch::handler(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn create_listing(
         ctx: Context<CreateListing>,
         price_type: state::PriceType,
@@ -34,6 +54,18 @@ pub mod verity {
         min_floor: Option<u64>,
         valid_from: Option<i64>,
         valid_until: Option<i64>,
+        min_price_is_floor_pct: Option<u16>,
+        memo: Option<String>,
+        oracle_feed: Option<Pubkey>,
+        royalty_override: Option<Pubkey>,
+        enforce_royalties: Option<bool>,
+        override_fee_bps: Option<u16>,
+        escrow_proceeds: bool,
+        quantity: Option<u64>,
+        min_usd_value: Option<u64>,
+        boost_lamports: Option<u64>,
+        split_payment: Option<state::SplitPayment>,
+        floor_source: Option<state::FloorSource>,
     ) -> Result<()> {
         create_listing::handler(
             ctx,
@@ -45,18 +77,212 @@ pub mod verity {
             min_floor,
             valid_from,
             valid_until,
+            min_price_is_floor_pct,
+            memo,
+            oracle_feed,
+            royalty_override,
+            enforce_royalties,
+            override_fee_bps,
+            escrow_proceeds,
+            quantity,
+            min_usd_value,
+            boost_lamports,
+            split_payment,
+            floor_source,
         )
     }
 
-    pub fn buy_now(ctx: Context<BuyNow>) -> Result<()> {
-        buy_now::handler(ctx)
+    pub fn buy_now<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyNow<'info>>,
+        quantity: u64,
+    ) -> Result<()> {
+        buy_now::handler(ctx, quantity)
+    }
+
+    pub fn settle_otc<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleOtc<'info>>,
+        price: u64,
+    ) -> Result<()> {
+        settle_otc::handler(ctx, price)
     }
 
     pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
         cancel_listing::handler(ctx)
     }
 
+    pub fn cancel_listings_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelListingsBatch<'info>>,
+    ) -> Result<()> {
+        cancel_listings_batch::handler(ctx)
+    }
+
+    pub fn expire_listing(ctx: Context<ExpireListing>) -> Result<()> {
+        expire_listing::handler(ctx)
+    }
+
+    pub fn invalidate_listing(ctx: Context<InvalidateListing>) -> Result<()> {
+        invalidate_listing::handler(ctx)
+    }
+
+    pub fn deactivate_listing(ctx: Context<DeactivateListing>) -> Result<()> {
+        deactivate_listing::handler(ctx)
+    }
+
+    pub fn reactivate_listing(
+        ctx: Context<ReactivateListing>,
+        price_type: state::PriceType,
+        start_price: u64,
+        min_price: u64,
+        start_ts: i64,
+        duration: i64,
+    ) -> Result<()> {
+        reactivate_listing::handler(ctx, price_type, start_price, min_price, start_ts, duration)
+    }
+
+    pub fn update_listing_window(
+        ctx: Context<UpdateListingWindow>,
+        valid_from: Option<i64>,
+        valid_until: Option<i64>,
+    ) -> Result<()> {
+        update_listing_window::handler(ctx, valid_from, valid_until)
+    }
+
+    pub fn update_listing_price(
+        ctx: Context<UpdateListingPrice>,
+        price_type: state::PriceType,
+        start_price: u64,
+        min_price: u64,
+        start_ts: i64,
+        duration: i64,
+    ) -> Result<()> {
+        update_listing_price::handler(ctx, price_type, start_price, min_price, start_ts, duration)
+    }
+
     pub fn withdraw_from_vault(ctx: Context<WithdrawFromVault>) -> Result<()> {
         withdraw_from_vault::handler(ctx)
     }
+
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+        emergency_withdraw::handler(ctx)
+    }
+
+    pub fn close_user_vault(ctx: Context<CloseUserVault>) -> Result<()> {
+        close_user_vault::handler(ctx)
+    }
+
+    pub fn transfer_vault_ownership(ctx: Context<TransferVaultOwnership>) -> Result<()> {
+        transfer_vault_ownership::handler(ctx)
+    }
+
+    pub fn reconcile_vault(ctx: Context<ReconcileVault>) -> Result<()> {
+        reconcile_vault::handler(ctx)
+    }
+
+    pub fn sweep_empty_vault_ata(ctx: Context<SweepEmptyVaultAta>) -> Result<()> {
+        sweep_empty_vault_ata::handler(ctx)
+    }
+
+    pub fn check_vault_status(ctx: Context<CheckVaultStatus>) -> Result<()> {
+        check_vault_status::handler(ctx)
+    }
+
+    pub fn preview_schedule(ctx: Context<PreviewSchedule>, samples: u8) -> Result<()> {
+        preview_schedule::handler(ctx, samples)
+    }
+
+    pub fn dry_run_buy(ctx: Context<DryRunBuy>, quantity: u64) -> Result<()> {
+        dry_run_buy::handler(ctx, quantity)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn list_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, ListCompressed<'info>>,
+        leaf: state::CompressedLeafArgs,
+        price_type: state::PriceType,
+        start_price: u64,
+        min_price: u64,
+        start_ts: i64,
+        duration: i64,
+        valid_from: Option<i64>,
+        valid_until: Option<i64>,
+    ) -> Result<()> {
+        list_compressed::handler(
+            ctx,
+            leaf,
+            price_type,
+            start_price,
+            min_price,
+            start_ts,
+            duration,
+            valid_from,
+            valid_until,
+        )
+    }
+
+    pub fn buy_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuyCompressed<'info>>,
+        leaf: state::CompressedLeafArgs,
+        metadata: Option<state::CompressedMetadataArgs>,
+    ) -> Result<()> {
+        buy_compressed::handler(ctx, leaf, metadata)
+    }
+
+    pub fn cancel_compressed_listing(ctx: Context<CancelCompressedListing>) -> Result<()> {
+        cancel_compressed_listing::handler(ctx)
+    }
+
+    pub fn set_fee_recipients(
+        ctx: Context<SetFeeRecipients>,
+        fee_shares: Vec<state::FeeShare>,
+    ) -> Result<()> {
+        set_fee_recipients::handler(ctx, fee_shares)
+    }
+
+    pub fn add_payment_mint(ctx: Context<AddPaymentMint>) -> Result<()> {
+        add_payment_mint::handler(ctx)
+    }
+
+    pub fn remove_payment_mint(ctx: Context<RemovePaymentMint>, payment_mint: Pubkey) -> Result<()> {
+        remove_payment_mint::handler(ctx, payment_mint)
+    }
+
+    pub fn grant_fee_exemption(ctx: Context<GrantFeeExemption>) -> Result<()> {
+        grant_fee_exemption::handler(ctx)
+    }
+
+    pub fn revoke_fee_exemption(ctx: Context<RevokeFeeExemption>) -> Result<()> {
+        revoke_fee_exemption::handler(ctx)
+    }
+
+    pub fn open_proceeds(ctx: Context<OpenProceeds>) -> Result<()> {
+        open_proceeds::handler(ctx)
+    }
+
+    pub fn claim_proceeds(ctx: Context<ClaimProceeds>) -> Result<()> {
+        claim_proceeds::handler(ctx)
+    }
+
+    pub fn claim_royalty(ctx: Context<ClaimRoyalty>) -> Result<()> {
+        claim_royalty::handler(ctx)
+    }
+
+    pub fn set_royalty_policy(ctx: Context<SetRoyaltyPolicy>, enforce: bool) -> Result<()> {
+        set_royalty_policy::handler(ctx, enforce)
+    }
+
+    pub fn migrate_listing(ctx: Context<MigrateListing>) -> Result<()> {
+        migrate_listing::handler(ctx)
+    }
+
+    pub fn resize_listing(ctx: Context<ResizeListing>, new_len: u32) -> Result<()> {
+        resize_listing::handler(ctx, new_len)
+    }
+
+    pub fn migrate_user_vault(ctx: Context<MigrateUserVault>) -> Result<()> {
+        migrate_user_vault::handler(ctx)
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        migrate_config::handler(ctx)
+    }
 }
\ No newline at end of file
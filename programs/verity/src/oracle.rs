@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use crate::error::VerityError;
+
+/// Pyth oracle program ids (mainnet-beta, devnet)
+pub const PYTH_PROGRAM_IDS: [Pubkey; 2] = [
+    pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH"),
+    pubkey!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s"),
+];
+
+/// Oldest oracle update accepted, in seconds
+pub const MAX_ORACLE_STALENESS: i64 = 60;
+
+/// Widest confidence interval accepted, in bps of the price
+pub const MAX_ORACLE_CONF_BPS: u64 = 200;
+
+// Pyth price account layout (v2)
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_ATYPE_PRICE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const OFFSET_MAGIC: usize = 0;
+const OFFSET_ATYPE: usize = 8;
+const OFFSET_EXPO: usize = 20;
+const OFFSET_TIMESTAMP: usize = 96;
+const OFFSET_AGG_PRICE: usize = 208;
+const OFFSET_AGG_CONF: usize = 216;
+const OFFSET_AGG_STATUS: usize = 224;
+const PYTH_PRICE_MIN_LEN: usize = 240;
+
+/// Read a Pyth price feed quoted in SOL and return it in lamports
+/// Rejects feeds that are not trading, stale, or too uncertain
+pub fn read_oracle_price_lamports(oracle: &AccountInfo, current_ts: i64) -> Result<u64> {
+    let (price, expo) = read_pyth_price(oracle, current_ts)?;
+    // price * 10^expo SOL -> lamports (1 SOL = 10^9 lamports)
+    scale_price(price, expo, 9)
+}
+
+/// Read a SOL/USD Pyth feed and return the USD value of one SOL in
+/// micro-USD (10^-6 USD), with the same trading / staleness / confidence
+/// checks as read_oracle_price_lamports
+pub fn read_sol_usd_price_micros(oracle: &AccountInfo, current_ts: i64) -> Result<u64> {
+    let (price, expo) = read_pyth_price(oracle, current_ts)?;
+    scale_price(price, expo, 6)
+}
+
+/// Validated (price, expo) from a Pyth v2 price account
+fn read_pyth_price(oracle: &AccountInfo, current_ts: i64) -> Result<(u64, i32)> {
+    require!(
+        PYTH_PROGRAM_IDS.contains(oracle.owner),
+        VerityError::InvalidOracle
+    );
+    
+    let data = oracle.try_borrow_data()?;
+    require!(data.len() >= PYTH_PRICE_MIN_LEN, VerityError::InvalidOracle);
+    require!(read_u32(&data, OFFSET_MAGIC)? == PYTH_MAGIC, VerityError::InvalidOracle);
+    require!(read_u32(&data, OFFSET_ATYPE)? == PYTH_ATYPE_PRICE, VerityError::InvalidOracle);
+    
+    let expo = read_u32(&data, OFFSET_EXPO)? as i32;
+    let timestamp = read_u64(&data, OFFSET_TIMESTAMP)? as i64;
+    let price = read_u64(&data, OFFSET_AGG_PRICE)? as i64;
+    let conf = read_u64(&data, OFFSET_AGG_CONF)?;
+    let status = read_u32(&data, OFFSET_AGG_STATUS)?;
+    
+    require!(status == PYTH_STATUS_TRADING, VerityError::InvalidOracle);
+    require!(price > 0, VerityError::InvalidOracle);
+    require!(
+        current_ts.saturating_sub(timestamp) <= MAX_ORACLE_STALENESS,
+        VerityError::InvalidOracle
+    );
+    
+    // Confidence must be within MAX_ORACLE_CONF_BPS of the price
+    let price = price as u64;
+    require!(
+        (conf as u128) * 10000 <= (price as u128) * (MAX_ORACLE_CONF_BPS as u128),
+        VerityError::InvalidOracle
+    );
+    
+    Ok((price, expo))
+}
+
+/// price * 10^expo, expressed in units of 10^-decimals
+fn scale_price(price: u64, expo: i32, decimals: i32) -> Result<u64> {
+    let scale = expo.checked_add(decimals).ok_or(VerityError::ArithmeticOverflow)?;
+    let scaled = if scale >= 0 {
+        (price as u128).checked_mul(
+            10u128
+                .checked_pow(scale as u32)
+                .ok_or(VerityError::ArithmeticOverflow)?,
+        )
+    } else {
+        (price as u128).checked_div(
+            10u128
+                .checked_pow(scale.unsigned_abs())
+                .ok_or(VerityError::ArithmeticOverflow)?,
+        )
+    }
+    .ok_or(VerityError::ArithmeticOverflow)?;
+    
+    u64::try_from(scaled).map_err(|_| error!(VerityError::ArithmeticOverflow))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(VerityError::InvalidOracle)?;
+    Ok(u32::from_le_bytes(bytes.try_into().map_err(|_| error!(VerityError::InvalidOracle))?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    let bytes = data
+        .get(offset..offset + 8)
+        .ok_or(VerityError::InvalidOracle)?;
+    Ok(u64::from_le_bytes(bytes.try_into().map_err(|_| error!(VerityError::InvalidOracle))?))
+}
@@ -9,6 +9,8 @@ pub struct UserVault {
     pub mint: Pubkey,            // NFT mint stored in this vault
     pub vault_ata: Pubkey,       // ATA holding the NFT
     pub bump: u8,
+    pub version: u8,             // Layout version (ACCOUNT_VERSION at creation)
+    pub last_listed_ts: i64,     // When create_listing last listed this vault (0 = never; migration time on older vaults)
 }
 
 impl UserVault {
@@ -16,7 +18,25 @@ impl UserVault {
         32 +                      // owner
         32 +                      // mint
         32 +                      // vault_ata
-        1;                        // bump
+        1 +                       // bump
+        1 +                       // version
+        8;                        // last_listed_ts
+    
+    /// Require Config.relist_cooldown to have passed since the vault was
+    /// last listed (a never-listed vault is always free)
+    pub fn require_relist_cooldown_passed(&self, relist_cooldown: i64, now: i64) -> Result<()> {
+        require!(
+            relist_cooldown_passed(self.last_listed_ts, relist_cooldown, now),
+            crate::error::VerityError::RelistCooldown
+        );
+        Ok(())
+    }
+}
+
+/// Whether Config.relist_cooldown has passed since something was last
+/// listed at `last_listed_ts` (0 = never listed, always free)
+pub fn relist_cooldown_passed(last_listed_ts: i64, relist_cooldown: i64, now: i64) -> bool {
+    last_listed_ts == 0 || now >= last_listed_ts.saturating_add(relist_cooldown)
 }
 
 /// Listing references the user vault, doesn't custody the NFT
@@ -27,8 +47,26 @@ pub struct Listing {
     pub user_vault: Pubkey,      // Reference to UserVault, not direct custody
     pub price_config: PriceConfig,
     pub conditions: ListingConditions,
-    pub state: u8,               // 0 = Active, 1 = Cancelled, 2 = Sold
+    pub state: ListingState,
     pub bump: u8,
+    pub collection: Option<Pubkey>, // Verified collection (if metadata was supplied)
+    pub created_ts: i64,         // Set at create_listing
+    pub updated_ts: i64,         // Bumped whenever listing terms change
+    pub memo: String,            // Optional seller note / URI (max MAX_MEMO_LEN bytes)
+    pub oracle_feed: Option<Pubkey>, // Only oracle buy_now will accept for this listing
+    pub version: u8,             // Layout version (ACCOUNT_VERSION at creation)
+    pub royalty_override: Option<Pubkey>, // Receives the whole royalty instead of the creators
+    pub enforce_royalties: Option<bool>, // Royalty choice; None follows Config.royalty_default
+    pub override_fee_bps: Option<u16>, // Promotional fee; None uses Config.fee_bps
+    pub escrow_proceeds: bool,   // Credit the seller's Proceeds PDA instead of paying directly
+    pub quantity: u64,           // Units still for sale (prices are per unit)
+    pub deposit: u64,            // Config.listing_deposit paid in at creation (held in lamports)
+    pub seller_counted: bool,    // Holds one of the seller's SellerState.active_listings slots
+    pub min_usd_value: Option<u64>, // Lowest sale value in micro-USD, checked via Config.sol_usd_feed
+    pub boost_score: u64,         // Lamports paid at listing time for placement (see boost_score_at)
+    pub freezable: bool,          // Mint still has a freeze authority that could trap the NFT
+    pub split_payment: Option<SplitPayment>, // SPL-token leg charged on top of the SOL price
+    pub floor_source: Option<FloorSource>, // What min_floor is checked against (set exactly when min_floor is)
 }
 
 impl Listing {
@@ -39,9 +77,105 @@ impl Listing {
         PriceConfig::LEN +
         ListingConditions::LEN +
         1 +                       // state
-        1;                        // bump
+        1 +                       // bump
+        33 +                      // collection (1 + 32)
+        8 +                       // created_ts
+        8 +                       // updated_ts
+        4 + MAX_MEMO_LEN +        // memo
+        33 +                      // oracle_feed (1 + 32)
+        1 +                       // version
+        33 +                      // royalty_override (1 + 32)
+        2 +                       // enforce_royalties (1 + 1)
+        3 +                       // override_fee_bps (1 + 2)
+        1 +                       // escrow_proceeds
+        8 +                       // quantity
+        8 +                       // deposit
+        1 +                       // seller_counted
+        9 +                       // min_usd_value (1 + 8)
+        8 +                       // boost_score
+        1 +                       // freezable
+        1 + SplitPayment::LEN +   // split_payment
+        2;                        // floor_source (1 + 1)
+
+    /// Units still for sale - listings grown from an older layout have a
+    /// zeroed quantity and sell their single NFT
+    pub fn remaining_quantity(&self) -> u64 {
+        self.quantity.max(1)
+    }
+    
+    /// Take `quantity` units off the listing, marking it Sold once none are
+    /// left. Returns whether it sold out. buy_now persists the result
+    /// before any CPI, so nothing it calls sees a listing still for sale
+    pub fn take_units(&mut self, quantity: u64) -> Result<bool> {
+        self.quantity = self
+            .remaining_quantity()
+            .checked_sub(quantity)
+            .ok_or(crate::error::VerityError::InvalidQuantity)?;
+        let sold_out = self.quantity == 0;
+        if sold_out {
+            self.state = ListingState::Sold;
+        }
+        Ok(sold_out)
+    }
+    
+    /// Boost for ranking at `now`: boost_score halves every
+    /// BOOST_HALF_LIFE seconds since the listing was created
+    pub fn boost_score_at(&self, now: i64) -> u64 {
+        let halvings = now.saturating_sub(self.created_ts).max(0) / BOOST_HALF_LIFE;
+        self.boost_score.checked_shr(halvings as u32).unwrap_or(0)
+    }
+    
+    /// The terms utils::quote_sale prices a purchase of this listing by
+    pub fn sale_terms(&self) -> SaleTerms {
+        SaleTerms {
+            price_config: self.price_config,
+            conditions: self.conditions,
+            remaining_quantity: self.remaining_quantity(),
+            collection: self.collection,
+            oracle_feed: self.oracle_feed,
+            floor_source: self.floor_source,
+            min_usd_value: self.min_usd_value,
+            override_fee_bps: self.override_fee_bps,
+            enforce_royalties: self.enforce_royalties,
+            payment_mint: self.split_payment.map(|split| split.payment_mint),
+        }
+    }
 }
 
+/// What a sale is priced and gated by, shared by normal and compressed
+/// listings so both are charged the same way (see utils::quote_sale)
+#[derive(Clone, Copy)]
+pub struct SaleTerms {
+    pub price_config: PriceConfig,
+    pub conditions: ListingConditions,
+    pub remaining_quantity: u64,
+    pub collection: Option<Pubkey>, // Recorded at listing time (else read from the metadata)
+    pub oracle_feed: Option<Pubkey>,
+    pub floor_source: Option<FloorSource>,
+    pub min_usd_value: Option<u64>,
+    pub override_fee_bps: Option<u16>,
+    pub enforce_royalties: Option<bool>,
+    pub payment_mint: Option<Pubkey>, // Split payment's token, checked against Config.payment_mints
+}
+
+/// Layout version written to UserVault, Listing and Config on creation
+/// Fields are only ever appended, so older accounts are upgraded by growing
+/// them (new bytes zeroed) with migrate_* and stamping the new version.
+/// Bump this on every layout change and give migrate_* a branch seeding
+/// the fields whose zero value would be wrong.
+/// 0: predates versioning
+/// 1: fields appended while the version stayed at 1 (a v1 account may be
+///    shorter than the final v1 layout)
+/// 2: same layout as the last v1; bumped so those accounts get migrated
+/// 3: Listing.floor_source and Config.payment_mints appended
+pub const ACCOUNT_VERSION: u8 = 3;
+
+/// Program release, recorded in ConfigInitialized
+pub const PROGRAM_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Maximum listing memo length in bytes
+pub const MAX_MEMO_LEN: usize = 64;
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
 pub struct PriceConfig {
     pub price_type: PriceType,
@@ -49,6 +183,7 @@ pub struct PriceConfig {
     pub min_price: u64,
     pub start_ts: i64,
     pub duration: i64,
+    pub min_price_is_floor_pct: Option<u16>, // Decay floor tracks oracle floor (bps)
 }
 
 impl PriceConfig {
@@ -56,7 +191,36 @@ impl PriceConfig {
         8 +                       // start_price
         8 +                       // min_price
         8 +                       // start_ts
-        8;                        // duration
+        8 +                       // duration
+        3;                        // min_price_is_floor_pct (1 + 2)
+    
+    /// Price rules shared by every listing path (create, reactivate,
+    /// compressed). Marketplace bounds on duration / start_ts depend on
+    /// Config and stay with the caller
+    pub fn validate(&self) -> Result<()> {
+        require!(self.start_price > 0, crate::error::VerityError::InvalidPrice);
+        require!(self.min_price > 0, crate::error::VerityError::InvalidPrice);
+        require!(
+            self.start_price >= self.min_price,
+            crate::error::VerityError::InvalidPrice
+        );
+        
+        // Decay needs a window to decay over
+        if self.price_type != PriceType::Fixed {
+            require!(self.duration > 0, crate::error::VerityError::InvalidDuration);
+        }
+        
+        // Oracle-tracked floor only makes sense for decaying prices
+        if let Some(pct) = self.min_price_is_floor_pct {
+            require!(pct > 0, crate::error::VerityError::InvalidPrice);
+            require!(
+                self.price_type != PriceType::Fixed,
+                crate::error::VerityError::InvalidPrice
+            );
+        }
+        
+        Ok(())
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
@@ -66,6 +230,13 @@ pub enum PriceType {
     Exponential,                  // Exponential decay (future)
 }
 
+/// Where a listing's min_floor is read at sale time (chosen by the seller)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FloorSource {
+    CollectionTwap,               // The collection's CollectionTwap average (needs a verified collection)
+    Oracle,                       // The listing's oracle_feed (Pyth collection floor, in lamports)
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
 pub struct ListingConditions {
     pub min_floor: Option<u64>,   // Pyth oracle floor validation
@@ -79,21 +250,766 @@ impl ListingConditions {
         9;                        // valid_until (1 + 8)
 }
 
+/// Token leg of a split-payment listing: each unit also costs
+/// `token_amount` of `payment_mint`, split into fee / royalty / seller
+/// shares at the same rates as the SOL price
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SplitPayment {
+    pub payment_mint: Pubkey,
+    pub token_amount: u64,       // Per unit, in base units of payment_mint
+    pub payment_decimals: u8,    // payment_mint's decimals (checked at create_listing)
+}
+
+impl SplitPayment {
+    pub const LEN: usize = 32 +  // payment_mint
+        8 +                       // token_amount
+        1;                        // payment_decimals
+}
+
 #[account]
 pub struct Config {
     pub authority: Pubkey,
     pub fee_bps: u16,
-    pub fee_recipient: Pubkey,
+    pub fee_recipient: Pubkey,    // Receives the whole fee when fee_shares is empty
+    pub fee_shares: Vec<FeeShare>, // Optional split of the fee (max MAX_FEE_RECIPIENTS)
+    pub max_royalty_bps: u16,     // Cap on royalties charged to buyers
+    pub start_ts_grace: i64,      // How far in the past a decay start_ts may be (seconds)
+    pub min_duration: i64,        // Shortest allowed decay duration (seconds)
+    pub max_duration: i64,        // Longest allowed decay duration (seconds)
+    pub dust_fee_policy: DustFeePolicy, // How sales with a sub-minimum fee are charged
+    pub min_fee_lamports: u64,    // Minimum fee used by dust_fee_policy
+    pub version: u8,              // Layout version (ACCOUNT_VERSION at creation)
+    pub emergency_until: i64,     // User instructions are blocked while now < emergency_until
+    pub royalty_default: RoyaltyDefault, // Royalty policy for listings that don't choose
+    pub allow_royalty_opt_out: bool, // Whether listings may opt out of an enforced default
+    pub max_fee_discount_bps: u16, // How far below fee_bps a listing's override_fee_bps may go
+    pub community_bps: u16,       // Share of each sale price routed to community_recipient
+    pub community_recipient: Pubkey, // Community treasury (unused while community_bps is 0)
+    pub expiry_grace: i64,        // How long after valid_until expire_listing has to wait (seconds)
+    pub min_royalty_bps: u16,     // Floor on enforced royalties (at most max_royalty_bps)
+    pub listing_deposit: u64,     // Lamports a seller locks in each new listing (0 = none)
+    pub relist_cooldown: i64,     // Seconds before a vault can be listed again (0 = none)
+    pub previous_fee_recipient: Pubkey, // Still accepted for FEE_RECIPIENT_GRACE after a rotation
+    pub fee_recipient_changed_ts: i64, // When update_config last rotated fee_recipient (0 = never)
+    pub max_active_listings: u32, // Cap on active listings per seller (0 = unlimited)
+    pub sol_usd_feed: Pubkey,     // SOL/USD Pyth feed for listings' min_usd_value
+    pub redistribute_unverified_royalty: bool, // Pay unverified creators' shares to verified ones
+    pub escrow_royalties: bool,   // Accrue creator royalties in RoyaltyEscrow PDAs instead of paying wallets
+    pub reject_freezable_mints: bool, // Refuse mints whose freeze authority is still set
+    pub init_ts: i64,             // When initialize_config ran (migration time for older configs)
+    pub min_listing_price: u64,   // Lowest start / min price a new listing may use (0 = none)
+    pub discount_mint: Pubkey,    // Governance token whose holders get a fee discount (default = none)
+    pub discount_threshold: u64,  // discount_mint balance (base units) that earns the discount
+    pub discount_bps: u16,        // Taken off the fee rate for qualifying buyers
+    pub payment_mints: Vec<Pubkey>, // SPL mints split payments may use (max MAX_PAYMENT_MINTS)
 }
 
 impl Config {
-    pub const LEN: usize = 8 + 32 + 2 + 32;
+    pub const LEN: usize = 8 +   // discriminator
+        32 +                      // authority
+        2 +                       // fee_bps
+        32 +                      // fee_recipient
+        4 + MAX_FEE_RECIPIENTS * FeeShare::LEN + // fee_shares
+        2 +                       // max_royalty_bps
+        8 +                       // start_ts_grace
+        8 +                       // min_duration
+        8 +                       // max_duration
+        1 +                       // dust_fee_policy
+        8 +                       // min_fee_lamports
+        1 +                       // version
+        8 +                       // emergency_until
+        1 +                       // royalty_default
+        1 +                       // allow_royalty_opt_out
+        2 +                       // max_fee_discount_bps
+        2 +                       // community_bps
+        32 +                      // community_recipient
+        8 +                       // expiry_grace
+        2 +                       // min_royalty_bps
+        8 +                       // listing_deposit
+        8 +                       // relist_cooldown
+        32 +                      // previous_fee_recipient
+        8 +                       // fee_recipient_changed_ts
+        4 +                       // max_active_listings
+        32 +                      // sol_usd_feed
+        1 +                       // redistribute_unverified_royalty
+        1 +                       // escrow_royalties
+        1 +                       // reject_freezable_mints
+        8 +                       // init_ts
+        8 +                       // min_listing_price
+        32 +                      // discount_mint
+        8 +                       // discount_threshold
+        2 +                       // discount_bps
+        4 + MAX_PAYMENT_MINTS * 32; // payment_mints
+
+    /// Whether emergency mode is on at `now`
+    pub fn in_emergency(&self, now: i64) -> bool {
+        now < self.emergency_until
+    }
+    
+    /// Whether split payments may be priced in `mint`
+    pub fn accepts_payment_mint(&self, mint: &Pubkey) -> bool {
+        self.payment_mints.contains(mint)
+    }
+    
+    /// Add `mint` to the payment mint allowlist (a no-op if it is already
+    /// there), within MAX_PAYMENT_MINTS
+    pub fn allow_payment_mint(&mut self, mint: Pubkey) -> Result<()> {
+        if self.accepts_payment_mint(&mint) {
+            return Ok(());
+        }
+        require!(
+            self.payment_mints.len() < MAX_PAYMENT_MINTS,
+            crate::error::VerityError::TooManyPaymentMints
+        );
+        self.payment_mints.push(mint);
+        Ok(())
+    }
+    
+    /// Take `mint` off the payment mint allowlist
+    pub fn disallow_payment_mint(&mut self, mint: &Pubkey) -> Result<()> {
+        let index = self
+            .payment_mints
+            .iter()
+            .position(|allowed| allowed == mint)
+            .ok_or(crate::error::VerityError::UnsupportedPaymentMint)?;
+        self.payment_mints.remove(index);
+        Ok(())
+    }
+    
+    /// Require a marketplace fee of at most MAX_FEE_BPS
+    pub fn validate_fee_bps(fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, crate::error::VerityError::InvalidFeeBps);
+        Ok(())
+    }
+    
+    /// Require both of a new listing's prices to be at least
+    /// min_listing_price, keeping dust listings off the book
+    pub fn require_min_listing_price(&self, price_config: &PriceConfig) -> Result<()> {
+        require!(
+            price_config.start_price >= self.min_listing_price
+                && price_config.min_price >= self.min_listing_price,
+            crate::error::VerityError::InvalidPrice
+        );
+        Ok(())
+    }
+    
+    /// Check a listing's validity window against its price at `now`: the
+    /// window must be ordered and, for decaying prices, the decay must last
+    /// min_duration to max_duration, start no earlier than start_ts_grace
+    /// ago (a fresh listing shouldn't open at or near min_price), and overlap
+    /// the window (otherwise buyers only ever see start_price or min_price)
+    pub fn validate_window(
+        &self,
+        price_config: &PriceConfig,
+        conditions: &ListingConditions,
+        now: i64,
+    ) -> Result<()> {
+        use crate::error::VerityError;
+        
+        if let (Some(from), Some(until)) = (conditions.valid_from, conditions.valid_until) {
+            require!(from < until, VerityError::InvalidTimeWindow);
+        }
+        
+        if price_config.price_type == PriceType::Fixed {
+            return Ok(());
+        }
+        
+        require!(
+            price_config.duration >= self.min_duration && price_config.duration <= self.max_duration,
+            VerityError::InvalidDuration
+        );
+        
+        let earliest_start = now
+            .checked_sub(self.start_ts_grace)
+            .ok_or(VerityError::ArithmeticOverflow)?;
+        require!(price_config.start_ts >= earliest_start, VerityError::InvalidTimeWindow);
+        
+        if let Some(until) = conditions.valid_until {
+            require!(until > price_config.start_ts, VerityError::InvalidTimeWindow);
+        }
+        if let Some(from) = conditions.valid_from {
+            let decay_end = price_config
+                .start_ts
+                .checked_add(price_config.duration)
+                .ok_or(VerityError::ArithmeticOverflow)?;
+            require!(from < decay_end, VerityError::InvalidTimeWindow);
+        }
+        Ok(())
+    }
+    
+    /// Whether a buyer holding `balance` of discount_mint earns the fee discount
+    /// (a zero threshold counts as disabled, like the default mint)
+    pub fn fee_discount_applies(&self, balance: u64) -> bool {
+        self.discount_mint != Pubkey::default()
+            && self.discount_bps > 0
+            && self.discount_threshold > 0
+            && balance >= self.discount_threshold
+    }
+    
+    /// Whether `key` may be passed as the fee recipient at `now`: the current
+    /// one, or the one it replaced for FEE_RECIPIENT_GRACE after a rotation
+    /// (so transactions built just before the change still land)
+    pub fn accepts_fee_recipient(&self, key: &Pubkey, now: i64) -> bool {
+        if *key == self.fee_recipient {
+            return true;
+        }
+        self.fee_recipient_changed_ts != 0
+            && *key == self.previous_fee_recipient
+            && now < self.fee_recipient_changed_ts.saturating_add(FEE_RECIPIENT_GRACE)
+    }
+
+    /// Whether a sale pays royalties, given the listing's enforce_royalties
+    /// choice (None = follow royalty_default). A listing's choice wins, except
+    /// that opting out of an enforced default only counts while
+    /// allow_royalty_opt_out is set
+    pub fn royalties_enforced(&self, listing_choice: Option<bool>) -> bool {
+        match (listing_choice, self.royalty_default) {
+            (Some(true), _) => true,
+            (Some(false), RoyaltyDefault::Enforce) => !self.allow_royalty_opt_out,
+            (Some(false), RoyaltyDefault::Skip) => false,
+            (None, default) => default == RoyaltyDefault::Enforce,
+        }
+    }
+
+    /// Lowest fee a listing may choose with override_fee_bps
+    pub fn min_listing_fee_bps(&self) -> u16 {
+        self.fee_bps.saturating_sub(self.max_fee_discount_bps)
+    }
+
+    /// Fee charged on a listing: its override_fee_bps clamped between
+    /// min_listing_fee_bps and fee_bps (the global fee when it has none)
+    pub fn listing_fee_bps(&self, override_fee_bps: Option<u16>) -> u16 {
+        match override_fee_bps {
+            Some(bps) => bps.clamp(self.min_listing_fee_bps(), self.fee_bps),
+            None => self.fee_bps,
+        }
+    }
+
+    /// Community treasury's cut of a sale: community_bps of the price, taken
+    /// out of the seller's proceeds (never more than `seller_amount`)
+    pub fn community_cut(&self, price: u64, seller_amount: u64) -> Result<u64> {
+        let cut = (price as u128)
+            .checked_mul(self.community_bps as u128)
+            .ok_or(crate::error::VerityError::ArithmeticOverflow)?
+            .checked_div(10000)
+            .ok_or(crate::error::VerityError::ArithmeticOverflow)? as u64;
+        Ok(cut.min(seller_amount))
+    }
+
+    /// Royalty rate for a sale that enforces royalties: the NFT's own rate,
+    /// raised to min_royalty_bps when the metadata understates it
+    pub fn floored_royalty_bps(&self, requested_bps: u16) -> u16 {
+        requested_bps.max(self.min_royalty_bps)
+    }
+
+    /// Royalty actually charged: the requested bps clamped to max_royalty_bps
+    pub fn effective_royalty_bps(&self, requested_bps: u16) -> u16 {
+        requested_bps.min(self.max_royalty_bps)
+    }
+
+    /// Apply dust_fee_policy to a breakdown computed from fee_bps
+    /// fee_bps rounds down, so tiny sales can compute a zero or sub-minimum
+    /// fee: MinimumFee raises it to min_fee_lamports (never more than what
+    /// is left after the royalty), ExemptBelowMinimum waives it entirely
+    pub fn apply_dust_fee_policy(&self, breakdown: PaymentBreakdown) -> Result<PaymentBreakdown> {
+        if breakdown.marketplace_fee >= self.min_fee_lamports {
+            return Ok(breakdown);
+        }
+        
+        let available = breakdown
+            .marketplace_fee
+            .checked_add(breakdown.seller_amount)
+            .ok_or(crate::error::VerityError::ArithmeticOverflow)?;
+        let marketplace_fee = match self.dust_fee_policy {
+            DustFeePolicy::Proportional => return Ok(breakdown),
+            DustFeePolicy::MinimumFee => self.min_fee_lamports.min(available),
+            DustFeePolicy::ExemptBelowMinimum => 0,
+        };
+        
+        Ok(PaymentBreakdown {
+            marketplace_fee,
+            royalty: breakdown.royalty,
+            seller_amount: available
+                .checked_sub(marketplace_fee)
+                .ok_or(crate::error::VerityError::ArithmeticOverflow)?,
+        })
+    }
+
+    /// Split a marketplace fee across fee_shares (in order)
+    /// Rounding dust goes to the last recipient so the split sums to the fee
+    pub fn split_marketplace_fee(&self, marketplace_fee: u64) -> Result<Vec<(Pubkey, u64)>> {
+        let mut remaining = marketplace_fee;
+        let mut split = Vec::with_capacity(self.fee_shares.len());
+        
+        for (i, share) in self.fee_shares.iter().enumerate() {
+            let amount = if i + 1 == self.fee_shares.len() {
+                remaining
+            } else {
+                (marketplace_fee as u128)
+                    .checked_mul(share.share_bps as u128)
+                    .ok_or(crate::error::VerityError::ArithmeticOverflow)?
+                    .checked_div(10000)
+                    .ok_or(crate::error::VerityError::ArithmeticOverflow)? as u64
+            };
+            remaining = remaining
+                .checked_sub(amount)
+                .ok_or(crate::error::VerityError::ArithmeticOverflow)?;
+            split.push((share.recipient, amount));
+        }
+        
+        Ok(split)
+    }
+}
+
+/// How sales whose proportional fee falls below Config.min_fee_lamports are charged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DustFeePolicy {
+    Proportional,                 // Always price * fee_bps / 10000 (rounded down)
+    MinimumFee,                   // Charge at least min_fee_lamports
+    ExemptBelowMinimum,           // Charge nothing when the fee would be below min_fee_lamports
+}
+
+/// Marketplace-wide royalty policy for listings that don't set enforce_royalties
+/// Enforce comes first so configs grown by migrate_config keep paying royalties
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RoyaltyDefault {
+    Enforce,                      // Buyers pay royalties unless the listing opts out (and may)
+    Skip,                         // No royalties unless the listing opts in
+}
+
+/// Maximum number of marketplace fee recipients
+pub const MAX_FEE_RECIPIENTS: usize = 4;
+
+/// Maximum number of SPL mints on the payment mint allowlist
+pub const MAX_PAYMENT_MINTS: usize = 8;
+
+/// How quickly a listing's boost fades for ranking (one day half-life)
+pub const BOOST_HALF_LIFE: i64 = 24 * 60 * 60;
+
+/// Weight of each new sale in a CollectionTwap's moving average (20%)
+pub const TWAP_WEIGHT_BPS: u64 = 2_000;
+
+/// Maximum number of listings cancel_listings_batch closes in one transaction
+pub const MAX_BATCH_CANCEL: usize = 10;
+
+/// Maximum number of NFTs initialize_user_vaults_batch vaults in one
+/// transaction (each costs two account creations and a transfer)
+pub const MAX_BATCH_VAULTS: usize = 5;
+
+/// Maximum number of (timestamp, price) points preview_schedule emits
+pub const MAX_PREVIEW_SAMPLES: u8 = 32;
+
+/// One recipient's share of the marketplace fee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct FeeShare {
+    pub recipient: Pubkey,
+    pub share_bps: u16,           // Share of the fee, all shares sum to 10000
+}
+
+impl FeeShare {
+    pub const LEN: usize = 32 +  // recipient
+        2;                        // share_bps
+}
+
+/// Global marketplace statistics
+/// Seeds: [b"stats"]
+/// On-chain source of truth for dashboards (no transaction indexing needed)
+#[account]
+pub struct Stats {
+    pub total_volume: u64,        // Cumulative lamports across all sales
+    pub total_sales: u64,         // Number of completed sales
+    pub active_listings: u64,     // Listings currently active
+    pub bump: u8,
+}
+
+impl Stats {
+    pub const LEN: usize = 8 +   // discriminator
+        8 +                       // total_volume
+        8 +                       // total_sales
+        8 +                       // active_listings
+        1;                        // bump
+}
+
+/// Per-seller listing counter, enforcing Config.max_active_listings
+/// Seeds: [b"seller_state", seller]
+/// Created by the seller's first create_listing
+#[account]
+pub struct SellerState {
+    pub seller: Pubkey,
+    pub active_listings: u32,     // Counted listings currently active
+    pub bump: u8,
+}
+
+impl SellerState {
+    pub const LEN: usize = 8 +   // discriminator
+        32 +                      // seller
+        4 +                       // active_listings
+        1;                        // bump
+    
+    /// Take a slot for a listing going active, within `max` (0 = unlimited)
+    pub fn claim_slot(&mut self, max: u32) -> Result<()> {
+        require!(
+            max == 0 || self.active_listings < max,
+            crate::error::VerityError::TooManyActiveListings
+        );
+        self.active_listings = self
+            .active_listings
+            .checked_add(1)
+            .ok_or(crate::error::VerityError::ArithmeticOverflow)?;
+        Ok(())
+    }
+    
+    /// Give back a slot when a listing stops being active
+    pub fn release_slot(&mut self) {
+        self.active_listings = self.active_listings.saturating_sub(1);
+    }
+}
+
+/// Per-user lifetime trading statistics
+/// Seeds: [b"user_stats", user]
+/// Created lazily on a user's first trade; readable by future rewards logic
+#[account]
+pub struct UserStats {
+    pub user: Pubkey,
+    pub bought_volume: u64,       // Lamports spent as buyer
+    pub bought_count: u64,        // Purchases made
+    pub sold_volume: u64,         // Lamports of sales as seller (gross price)
+    pub sold_count: u64,          // Sales completed
+    pub bump: u8,
+}
+
+impl UserStats {
+    pub const LEN: usize = 8 +   // discriminator
+        32 +                      // user
+        8 +                       // bought_volume
+        8 +                       // bought_count
+        8 +                       // sold_volume
+        8 +                       // sold_count
+        1;                        // bump
+
+    /// Record a purchase made by this user
+    pub fn record_purchase(&mut self, price: u64) -> Result<()> {
+        self.bought_count = self
+            .bought_count
+            .checked_add(1)
+            .ok_or(crate::error::VerityError::ArithmeticOverflow)?;
+        self.bought_volume = self
+            .bought_volume
+            .checked_add(price)
+            .ok_or(crate::error::VerityError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Record a sale made by this user
+    pub fn record_sale(&mut self, price: u64) -> Result<()> {
+        self.sold_count = self
+            .sold_count
+            .checked_add(1)
+            .ok_or(crate::error::VerityError::ArithmeticOverflow)?;
+        self.sold_volume = self
+            .sold_volume
+            .checked_add(price)
+            .ok_or(crate::error::VerityError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Marketplace fee exemption for a partner seller
+/// Seeds: [b"fee_exempt", seller]
+/// Granted and revoked by config.authority; royalties still apply
+#[account]
+pub struct FeeExemption {
+    pub seller: Pubkey,
+    pub bump: u8,
+}
+
+impl FeeExemption {
+    pub const LEN: usize = 8 +   // discriminator
+        32 +                      // seller
+        1;                        // bump
+}
+
+/// Sale proceeds held for a seller until claimed
+/// Seeds: [b"proceeds", seller]
+/// Listings created with escrow_proceeds credit this account instead of the
+/// seller's wallet; everything above its rent is claimable via claim_proceeds
+#[account]
+pub struct Proceeds {
+    pub seller: Pubkey,
+    pub bump: u8,
+}
+
+impl Proceeds {
+    pub const LEN: usize = 8 +   // discriminator
+        32 +                      // seller
+        1;                        // bump
+}
+
+/// Per-creator royalty escrow
+/// Seeds: [b"royalty_escrow", creator]
+/// While Config.escrow_royalties is set, buy_now credits creators' royalties
+/// here (creating the account at the buyer's expense if needed) instead of
+/// transferring to their wallets; everything above its rent is claimable via
+/// claim_royalty
+#[account]
+pub struct RoyaltyEscrow {
+    pub creator: Pubkey,
+    pub bump: u8,
+}
+
+impl RoyaltyEscrow {
+    pub const LEN: usize = 8 +   // discriminator
+        32 +                      // creator
+        1;                        // bump
+}
+
+/// Royalty enforcement for one verified collection
+/// Seeds: [b"royalty_policy", collection]
+/// Set by config.authority or a verified creator of the collection NFT;
+/// sales of the collection's listings follow `enforce` instead of the
+/// listing's enforce_royalties choice and the marketplace default
+#[account]
+pub struct RoyaltyPolicy {
+    pub collection: Pubkey,
+    pub enforce: bool,
+    pub bump: u8,
+}
+
+impl RoyaltyPolicy {
+    pub const LEN: usize = 8 +   // discriminator
+        32 +                      // collection
+        1 +                       // enforce
+        1;                        // bump
+}
+
+/// Moving average of a verified collection's sale prices
+/// Seeds: [b"collection_twap", collection]
+/// Created by the first buy_now of one of the collection's listings and
+/// updated by every later one; listings' min_floor is checked against it
+#[account]
+pub struct CollectionTwap {
+    pub collection: Pubkey,
+    pub ema_price: u64,           // Average unit price in lamports
+    pub last_update_ts: i64,      // When the last sale was folded in (0 = no sale yet)
+    pub bump: u8,
+}
+
+impl CollectionTwap {
+    pub const LEN: usize = 8 +   // discriminator
+        32 +                      // collection
+        8 +                       // ema_price
+        8 +                       // last_update_ts
+        1;                        // bump
+    
+    /// Fold a sale's unit price into the average with TWAP_WEIGHT_BPS of the
+    /// weight (the first sale sets it outright)
+    pub fn record(&mut self, unit_price: u64, now: i64) {
+        self.ema_price = if self.last_update_ts == 0 {
+            unit_price
+        } else {
+            let weighted = (self.ema_price as u128) * ((10000 - TWAP_WEIGHT_BPS) as u128)
+                + (unit_price as u128) * (TWAP_WEIGHT_BPS as u128);
+            (weighted / 10000) as u64
+        };
+        self.last_update_ts = now;
+    }
+}
+
+/// Compressed NFT (Bubblegum) listing
+/// Seeds: [b"compressed_listing", seller, merkle_tree, leaf_nonce]
+/// The seller keeps ownership of the leaf; this PDA is only set as its
+/// leaf delegate so the program can transfer it to a buyer
+#[account]
+pub struct CompressedListing {
+    pub seller: Pubkey,
+    pub asset_id: Pubkey,        // Bubblegum asset id (derived from tree + nonce)
+    pub merkle_tree: Pubkey,
+    pub leaf_nonce: u64,
+    pub price_config: PriceConfig,
+    pub conditions: ListingConditions,
+    pub state: ListingState,
+    pub bump: u8,
+    pub listed_ts: i64,          // When list_compressed last listed the leaf (for Config.relist_cooldown)
+    pub deposit: u64,            // Config.listing_deposit held in lamports, refunded when the account closes
+}
+
+impl CompressedListing {
+    pub const LEN: usize = 8 +   // discriminator
+        32 +                      // seller
+        32 +                      // asset_id
+        32 +                      // merkle_tree
+        8 +                       // leaf_nonce
+        PriceConfig::LEN +
+        ListingConditions::LEN +
+        1 +                       // state
+        1 +                       // bump
+        8 +                       // listed_ts
+        8;                        // deposit
+    
+    /// The terms utils::quote_sale prices a purchase of this listing by
+    /// Compressed listings sell their single leaf with no floors or fee
+    /// override, follow the royalty default, and take the collection from
+    /// the leaf's metadata
+    pub fn sale_terms(&self) -> SaleTerms {
+        SaleTerms {
+            price_config: self.price_config,
+            conditions: self.conditions,
+            remaining_quantity: 1,
+            collection: None,
+            oracle_feed: None,
+            floor_source: None,
+            min_usd_value: None,
+            override_fee_bps: None,
+            enforce_royalties: None,
+            payment_mint: None,
+        }
+    }
+}
+
+/// Leaf data required by Bubblegum to verify a compressed NFT proof
+/// (proof nodes are passed as remaining accounts)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CompressedLeafArgs {
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+}
+
+/// A compressed NFT creator, as hashed into the leaf's creator_hash
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CompressedCreator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+/// A compressed NFT's collection, as in Bubblegum's MetadataArgs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CompressedCollection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+/// A compressed NFT's uses, as in Bubblegum's MetadataArgs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CompressedUses {
+    pub use_method: u8,          // Bubblegum UseMethod variant index
+    pub remaining: u64,
+    pub total: u64,
+}
+
+/// A compressed NFT's metadata, checked against its leaf
+/// Serializes exactly like Bubblegum's MetadataArgs (enums as their variant
+/// index), so the leaf's data_hash is keccak(keccak(self),
+/// seller_fee_basis_points) and its creator_hash covers the creators
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedMetadataArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub edition_nonce: Option<u8>,
+    pub token_standard: Option<u8>, // Bubblegum TokenStandard variant index
+    pub collection: Option<CompressedCollection>,
+    pub uses: Option<CompressedUses>,
+    pub token_program_version: u8, // Bubblegum TokenProgramVersion variant index
+    pub creators: Vec<CompressedCreator>,
 }
 
-// Listing state constants
-pub const STATE_ACTIVE: u8 = 0;
-pub const STATE_CANCELLED: u8 = 1;
-pub const STATE_SOLD: u8 = 2;
+/// Listing lifecycle state
+/// Borsh stores this as a single tag byte in declaration order, so accounts
+/// written with the old `u8` state (0 = Active, 1 = Cancelled, 2 = Sold)
+/// deserialize unchanged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ListingState {
+    Active,
+    Cancelled,
+    Sold,
+    Paused,                       // Temporarily not purchasable
+    Expired,                      // Past valid_until
+}
+
+/// Maps a raw state byte from a pre-enum account
+/// Unknown values map to Cancelled so they can never read as Active
+impl From<u8> for ListingState {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => ListingState::Active,
+            2 => ListingState::Sold,
+            3 => ListingState::Paused,
+            4 => ListingState::Expired,
+            _ => ListingState::Cancelled,
+        }
+    }
+}
+
+/// Highest marketplace fee the authority may configure (10%)
+pub const MAX_FEE_BPS: u16 = 1000;
+
+/// Default royalty cap protecting buyers from hostile metadata (10%)
+pub const DEFAULT_MAX_ROYALTY_BPS: u16 = 1000;
+
+/// Default grace for decay listings whose start_ts is slightly in the past
+/// (covers clock drift between the client and the cluster)
+pub const DEFAULT_START_TS_GRACE: i64 = 300;
+
+/// Default decay duration bounds (permissive until the authority tightens them)
+pub const DEFAULT_MIN_DURATION: i64 = 1;
+pub const DEFAULT_MAX_DURATION: i64 = i64::MAX;
+
+/// Default wait after valid_until before anyone can crank a listing expired
+/// (gives a buy_now sent just before the deadline time to land)
+pub const DEFAULT_EXPIRY_GRACE: i64 = 120;
+
+/// How long the previous fee_recipient is still accepted after update_config
+/// rotates it (in-flight buys were built against the old key)
+pub const FEE_RECIPIENT_GRACE: i64 = 120;
+
+/// Longest emergency window the authority can set in one go (72 hours)
+/// Emergency mode also freezes withdrawals, so it must always expire
+pub const MAX_EMERGENCY_DURATION: i64 = 72 * 60 * 60;
+
+/// How the buyer's payment is split for a sale
+pub struct PaymentBreakdown {
+    pub marketplace_fee: u64,
+    pub royalty: u64,
+    pub seller_amount: u64,
+}
+
+/// Split a sale price into marketplace fee, royalty and seller proceeds
+pub fn calculate_payment_breakdown(
+    price: u64,
+    fee_bps: u16,
+    royalty_bps: u16,
+) -> Result<PaymentBreakdown> {
+    let marketplace_fee = (price as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(crate::error::VerityError::ArithmeticOverflow)?
+        .checked_div(10000)
+        .ok_or(crate::error::VerityError::ArithmeticOverflow)? as u64;
+    
+    let royalty = (price as u128)
+        .checked_mul(royalty_bps as u128)
+        .ok_or(crate::error::VerityError::ArithmeticOverflow)?
+        .checked_div(10000)
+        .ok_or(crate::error::VerityError::ArithmeticOverflow)? as u64;
+    
+    let seller_amount = price
+        .checked_sub(marketplace_fee)
+        .ok_or(crate::error::VerityError::ArithmeticOverflow)?
+        .checked_sub(royalty)
+        .ok_or(crate::error::VerityError::ArithmeticOverflow)?;
+    
+    Ok(PaymentBreakdown {
+        marketplace_fee,
+        royalty,
+        seller_amount,
+    })
+}
 
 /// Calculate current price based on price configuration
 pub fn calculate_price(config: &PriceConfig, current_ts: i64) -> u64 {
@@ -101,7 +1017,10 @@ pub fn calculate_price(config: &PriceConfig, current_ts: i64) -> u64 {
         PriceType::Fixed => config.start_price,
         
         PriceType::LinearDecay => {
-            if current_ts <= config.start_ts {
+            // create_listing guarantees duration > 0, but a corrupted or
+            // migrated account must never divide by zero or sell at min_price
+            // because it has no decay window
+            if config.duration <= 0 || current_ts <= config.start_ts {
                 return config.start_price;
             }
             
@@ -126,11 +1045,41 @@ pub fn calculate_price(config: &PriceConfig, current_ts: i64) -> u64 {
     }
 }
 
+/// Calculate current price with the decay floor tied to a live oracle
+/// When min_price_is_floor_pct is set, the curve decays towards
+/// oracle_floor * pct / 10000 instead of min_price. The static min_price
+/// stays a hard lower bound, and the floor never exceeds start_price
+pub fn calculate_price_with_floor(
+    config: &PriceConfig,
+    current_ts: i64,
+    oracle_floor: Option<u64>,
+) -> Result<u64> {
+    let (Some(pct), Some(floor)) = (config.min_price_is_floor_pct, oracle_floor) else {
+        return Ok(calculate_price(config, current_ts));
+    };
+    
+    let dynamic_floor = (floor as u128)
+        .checked_mul(pct as u128)
+        .ok_or(crate::error::VerityError::ArithmeticOverflow)?
+        .checked_div(10000)
+        .ok_or(crate::error::VerityError::ArithmeticOverflow)?;
+    let dynamic_floor = u64::try_from(dynamic_floor).unwrap_or(u64::MAX);
+    
+    let adjusted = PriceConfig {
+        min_price: dynamic_floor.max(config.min_price).min(config.start_price),
+        ..*config
+    };
+    
+    Ok(calculate_price(&adjusted, current_ts))
+}
+
 /// Validate listing conditions (floor price, time window)
+/// `floor_price` is the current value of the listing's floor_source, when
+/// known; a min_floor with no price to check fails closed
 pub fn validate_conditions(
     conditions: &ListingConditions,
     current_ts: i64,
-    _pyth_price: Option<u64>, // Future: Pyth integration
+    floor_price: Option<u64>,
 ) -> Result<()> {
     // Time window validation
     if let Some(valid_from) = conditions.valid_from {
@@ -147,11 +1096,450 @@ pub fn validate_conditions(
         );
     }
     
-    // Floor price validation (Pyth integration placeholder)
-    if let Some(_min_floor) = conditions.min_floor {
-        // Future: validate against Pyth oracle
-        // require!(pyth_price.unwrap_or(0) >= min_floor, FloorTooLow);
+    // Don't sell while the floor source is below the floor, or while it has
+    // no price at all (e.g. a collection that has never sold)
+    if let Some(min_floor) = conditions.min_floor {
+        let price = floor_price.ok_or(crate::error::VerityError::FloorTooLow)?;
+        require!(price >= min_floor, crate::error::VerityError::FloorTooLow);
     }
     
     Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn linear_decay(duration: i64) -> PriceConfig {
+        PriceConfig {
+            price_type: PriceType::LinearDecay,
+            start_price: 1_000,
+            min_price: 100,
+            start_ts: 10,
+            duration,
+            min_price_is_floor_pct: None,
+        }
+    }
+    
+    #[test]
+    fn linear_decay_without_window_stays_at_start_price() {
+        for duration in [0, -1, i64::MIN] {
+            let config = linear_decay(duration);
+            for now in [10, 11, 1_000, i64::MAX] {
+                assert_eq!(calculate_price(&config, now), 1_000);
+            }
+        }
+    }
+    
+    #[test]
+    fn linear_decay_reaches_min_price_at_end_of_window() {
+        let config = linear_decay(100);
+        assert_eq!(calculate_price(&config, 10), 1_000);
+        assert_eq!(calculate_price(&config, 60), 550);
+        assert_eq!(calculate_price(&config, 110), 100);
+    }
+    
+    fn validate_err(config: PriceConfig) -> Error {
+        config.validate().unwrap_err()
+    }
+    
+    #[test]
+    fn price_config_validate_accepts_valid_prices() {
+        linear_decay(100).validate().unwrap();
+    
+        // Fixed prices don't need a window
+        let fixed = PriceConfig {
+            price_type: PriceType::Fixed,
+            ..linear_decay(0)
+        };
+        fixed.validate().unwrap();
+    
+        let floor_pct = PriceConfig {
+            min_price_is_floor_pct: Some(50),
+            ..linear_decay(100)
+        };
+        floor_pct.validate().unwrap();
+    }
+    
+    #[test]
+    fn price_config_validate_rejects_zero_prices() {
+        let invalid_price: Error = crate::error::VerityError::InvalidPrice.into();
+        assert_eq!(
+            validate_err(PriceConfig {
+                start_price: 0,
+                ..linear_decay(100)
+            }),
+            invalid_price
+        );
+        assert_eq!(
+            validate_err(PriceConfig {
+                min_price: 0,
+                ..linear_decay(100)
+            }),
+            invalid_price
+        );
+    }
+    
+    #[test]
+    fn price_config_validate_rejects_start_below_min() {
+        assert_eq!(
+            validate_err(PriceConfig {
+                start_price: 99,
+                ..linear_decay(100)
+            }),
+            crate::error::VerityError::InvalidPrice.into()
+        );
+    }
+    
+    #[test]
+    fn price_config_validate_rejects_decay_without_window() {
+        for duration in [0, -1, i64::MIN] {
+            assert_eq!(
+                validate_err(linear_decay(duration)),
+                crate::error::VerityError::InvalidDuration.into()
+            );
+            assert_eq!(
+                validate_err(PriceConfig {
+                    price_type: PriceType::Exponential,
+                    ..linear_decay(duration)
+                }),
+                crate::error::VerityError::InvalidDuration.into()
+            );
+        }
+    }
+    
+    #[test]
+    fn price_config_validate_rejects_floor_pct_on_fixed_price() {
+        let fixed = PriceConfig {
+            price_type: PriceType::Fixed,
+            ..linear_decay(100)
+        };
+        assert_eq!(
+            validate_err(PriceConfig {
+                min_price_is_floor_pct: Some(50),
+                ..fixed
+            }),
+            crate::error::VerityError::InvalidPrice.into()
+        );
+        assert_eq!(
+            validate_err(PriceConfig {
+                min_price_is_floor_pct: Some(0),
+                ..linear_decay(100)
+            }),
+            crate::error::VerityError::InvalidPrice.into()
+        );
+    }
+    
+    fn empty_twap() -> CollectionTwap {
+        CollectionTwap {
+            collection: Pubkey::new_unique(),
+            ema_price: 0,
+            last_update_ts: 0,
+            bump: 255,
+        }
+    }
+    
+    #[test]
+    fn collection_twap_first_sale_sets_the_average() {
+        let mut twap = empty_twap();
+        twap.record(1_000, 1);
+        assert_eq!(twap.ema_price, 1_000);
+        assert_eq!(twap.last_update_ts, 1);
+    }
+    
+    #[test]
+    fn collection_twap_moves_toward_each_sale() {
+        let mut twap = empty_twap();
+        twap.record(1_000, 1);
+    
+        let mut expected = [1_200, 1_360, 1_188, 1_050].into_iter();
+        for (ts, price) in [(2, 2_000), (3, 2_000), (4, 500), (5, 500)] {
+            let previous = twap.ema_price;
+            twap.record(price, ts);
+    
+            // Strictly between the old average and the new price
+            let (low, high) = (previous.min(price), previous.max(price));
+            assert!(twap.ema_price > low && twap.ema_price < high);
+            assert_eq!(Some(twap.ema_price), expected.next());
+            assert_eq!(twap.last_update_ts, ts);
+        }
+    }
+    
+    #[test]
+    fn collection_twap_converges_on_a_steady_price() {
+        let mut twap = empty_twap();
+        twap.record(10_000, 1);
+        for ts in 2..100 {
+            twap.record(500, ts);
+        }
+        assert_eq!(twap.ema_price, 500);
+    }
+    
+    #[test]
+    fn collection_twap_handles_extreme_prices() {
+        let mut twap = empty_twap();
+        twap.record(u64::MAX, 1);
+        twap.record(u64::MAX, 2);
+        assert_eq!(twap.ema_price, u64::MAX);
+    
+        twap.record(0, 3);
+        assert_eq!(twap.ema_price, (u64::MAX as u128 * 8 / 10) as u64);
+    }
+    
+    #[test]
+    fn validate_fee_bps_caps_the_fee_at_max_fee_bps() {
+        Config::validate_fee_bps(0).unwrap();
+        Config::validate_fee_bps(1_000).unwrap();
+        assert_eq!(
+            Config::validate_fee_bps(1_001).unwrap_err(),
+            crate::error::VerityError::InvalidFeeBps.into()
+        );
+    }
+    
+    fn config() -> Config {
+        Config {
+            authority: Pubkey::new_unique(),
+            fee_bps: 250,
+            fee_recipient: Pubkey::new_unique(),
+            fee_shares: Vec::new(),
+            max_royalty_bps: DEFAULT_MAX_ROYALTY_BPS,
+            start_ts_grace: 60,
+            min_duration: 50,
+            max_duration: 1_000,
+            dust_fee_policy: DustFeePolicy::Proportional,
+            min_fee_lamports: 0,
+            version: ACCOUNT_VERSION,
+            emergency_until: 0,
+            royalty_default: RoyaltyDefault::Enforce,
+            allow_royalty_opt_out: true,
+            max_fee_discount_bps: 0,
+            community_bps: 0,
+            community_recipient: Pubkey::default(),
+            expiry_grace: DEFAULT_EXPIRY_GRACE,
+            min_royalty_bps: 0,
+            listing_deposit: 0,
+            relist_cooldown: 0,
+            previous_fee_recipient: Pubkey::default(),
+            fee_recipient_changed_ts: 0,
+            max_active_listings: 0,
+            sol_usd_feed: Pubkey::default(),
+            redistribute_unverified_royalty: false,
+            escrow_royalties: false,
+            reject_freezable_mints: false,
+            init_ts: 0,
+            min_listing_price: 0,
+            discount_mint: Pubkey::default(),
+            discount_threshold: 0,
+            discount_bps: 0,
+            payment_mints: Vec::new(),
+        }
+    }
+    
+    fn window(valid_from: Option<i64>, valid_until: Option<i64>) -> ListingConditions {
+        ListingConditions {
+            min_floor: None,
+            valid_from,
+            valid_until,
+        }
+    }
+    
+    #[test]
+    fn validate_window_accepts_a_decay_inside_the_bounds() {
+        let config = config();
+        config
+            .validate_window(&linear_decay(100), &window(None, None), 10)
+            .unwrap();
+        config
+            .validate_window(&linear_decay(100), &window(Some(0), Some(20)), 70)
+            .unwrap();
+    
+        // Fixed prices only need an ordered window
+        let fixed = PriceConfig {
+            price_type: PriceType::Fixed,
+            ..linear_decay(0)
+        };
+        config
+            .validate_window(&fixed, &window(Some(500), Some(600)), 1_000)
+            .unwrap();
+    }
+    
+    #[test]
+    fn validate_window_rejects_an_unordered_window() {
+        let fixed = PriceConfig {
+            price_type: PriceType::Fixed,
+            ..linear_decay(0)
+        };
+        assert_eq!(
+            config()
+                .validate_window(&fixed, &window(Some(20), Some(20)), 10)
+                .unwrap_err(),
+            crate::error::VerityError::InvalidTimeWindow.into()
+        );
+    }
+    
+    #[test]
+    fn validate_window_enforces_the_duration_bounds() {
+        for duration in [49, 1_001] {
+            assert_eq!(
+                config()
+                    .validate_window(&linear_decay(duration), &window(None, None), 10)
+                    .unwrap_err(),
+                crate::error::VerityError::InvalidDuration.into()
+            );
+        }
+    }
+    
+    #[test]
+    fn validate_window_rejects_a_start_before_the_grace() {
+        // start_ts 10 with a 60s grace
+        config()
+            .validate_window(&linear_decay(100), &window(None, None), 70)
+            .unwrap();
+        assert_eq!(
+            config()
+                .validate_window(&linear_decay(100), &window(None, None), 71)
+                .unwrap_err(),
+            crate::error::VerityError::InvalidTimeWindow.into()
+        );
+    }
+    
+    #[test]
+    fn validate_window_requires_the_window_to_overlap_the_decay() {
+        // The decay runs from 10 to 110
+        for conditions in [window(None, Some(10)), window(Some(110), None)] {
+            assert_eq!(
+                config()
+                    .validate_window(&linear_decay(100), &conditions, 10)
+                    .unwrap_err(),
+                crate::error::VerityError::InvalidTimeWindow.into()
+            );
+        }
+    }
+    
+    fn listing(quantity: u64) -> Listing {
+        Listing {
+            seller: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            user_vault: Pubkey::new_unique(),
+            price_config: linear_decay(100),
+            conditions: window(None, None),
+            state: ListingState::Active,
+            bump: 255,
+            collection: None,
+            created_ts: 0,
+            updated_ts: 0,
+            memo: String::new(),
+            oracle_feed: None,
+            version: ACCOUNT_VERSION,
+            royalty_override: None,
+            enforce_royalties: None,
+            override_fee_bps: None,
+            escrow_proceeds: false,
+            quantity,
+            deposit: 0,
+            seller_counted: true,
+            min_usd_value: None,
+            boost_score: 0,
+            freezable: false,
+            split_payment: None,
+            floor_source: None,
+        }
+    }
+    
+    /// Round-trips the listing through its account encoding, i.e. what
+    /// buy_now's listing.exit writes before the payment CPIs
+    fn persisted(listing: &Listing) -> Listing {
+        let mut data = Vec::new();
+        listing.try_serialize(&mut data).unwrap();
+        Listing::try_deserialize(&mut data.as_slice()).unwrap()
+    }
+    
+    #[test]
+    fn take_units_marks_the_last_unit_sold() {
+        let mut listing = listing(1);
+        assert!(listing.take_units(1).unwrap());
+    
+        let stored = persisted(&listing);
+        assert_eq!(stored.state, ListingState::Sold);
+        assert_eq!(stored.quantity, 0);
+    }
+    
+    #[test]
+    fn take_units_keeps_a_partly_sold_listing_active() {
+        let mut listing = listing(3);
+        assert!(!listing.take_units(2).unwrap());
+    
+        let stored = persisted(&listing);
+        assert_eq!(stored.state, ListingState::Active);
+        assert_eq!(stored.quantity, 1);
+    }
+    
+    #[test]
+    fn take_units_rejects_more_than_remain() {
+        // Listings grown from an older layout have a zeroed quantity and one unit
+        let mut listing = listing(0);
+        assert_eq!(
+            listing.take_units(2).unwrap_err(),
+            crate::error::VerityError::InvalidQuantity.into()
+        );
+        assert!(listing.take_units(1).unwrap());
+    }
+    
+    #[test]
+    fn validate_conditions_checks_min_floor_against_the_floor_price() {
+        let conditions = ListingConditions {
+            min_floor: Some(1_000),
+            ..window(None, None)
+        };
+        validate_conditions(&conditions, 0, Some(1_000)).unwrap();
+        assert_eq!(
+            validate_conditions(&conditions, 0, Some(999)).unwrap_err(),
+            crate::error::VerityError::FloorTooLow.into()
+        );
+    }
+    
+    #[test]
+    fn validate_conditions_fails_a_floor_without_a_price() {
+        let conditions = ListingConditions {
+            min_floor: Some(1_000),
+            ..window(None, None)
+        };
+        assert_eq!(
+            validate_conditions(&conditions, 0, None).unwrap_err(),
+            crate::error::VerityError::FloorTooLow.into()
+        );
+    
+        // Without a floor there is nothing to check
+        validate_conditions(&window(None, None), 0, None).unwrap();
+    }    
+    #[test]
+    fn payment_mint_allowlist_adds_and_removes_mints() {
+        let mut config = config();
+        let usdc = Pubkey::new_unique();
+        assert!(!config.accepts_payment_mint(&usdc));
+    
+        config.allow_payment_mint(usdc).unwrap();
+        config.allow_payment_mint(usdc).unwrap();
+        assert!(config.accepts_payment_mint(&usdc));
+        assert_eq!(config.payment_mints.len(), 1);
+    
+        config.disallow_payment_mint(&usdc).unwrap();
+        assert!(!config.accepts_payment_mint(&usdc));
+        assert_eq!(
+            config.disallow_payment_mint(&usdc).unwrap_err(),
+            crate::error::VerityError::UnsupportedPaymentMint.into()
+        );
+    }
+    
+    #[test]
+    fn payment_mint_allowlist_is_capped() {
+        let mut config = config();
+        for _ in 0..MAX_PAYMENT_MINTS {
+            config.allow_payment_mint(Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(
+            config.allow_payment_mint(Pubkey::new_unique()).unwrap_err(),
+            crate::error::VerityError::TooManyPaymentMints.into()
+        );
+    }
 }
\ No newline at end of file
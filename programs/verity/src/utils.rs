@@ -0,0 +1,1136 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    keccak, native_token::LAMPORTS_PER_SOL, program::{invoke, invoke_signed}, system_instruction
+};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::{self, Mint, TokenAccount, TransferChecked};
+use anchor_spl::metadata::{mpl_token_metadata::types::Creator, MetadataAccount};
+use crate::state::{
+    CollectionTwap, CompressedLeafArgs, CompressedMetadataArgs, Config, Listing, ListingState, PaymentBreakdown, RoyaltyEscrow, RoyaltyPolicy,
+    FloorSource, SaleTerms, SellerState, SplitPayment,
+    calculate_payment_breakdown, calculate_price_with_floor, validate_conditions
+};
+use crate::oracle::{read_oracle_price_lamports, read_sol_usd_price_micros};
+use crate::error::VerityError;
+use crate::events::{RoyaltyAccrued, RoyaltyPayment};
+
+/// Transfer SOL from a signer to any account via the system program
+/// No-op for zero amounts
+pub fn transfer_lamports<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    
+    invoke(
+        &system_instruction::transfer(from.key, to.key, amount),
+        &[from.clone(), to.clone(), system_program.clone()],
+    )?;
+    
+    Ok(())
+}
+
+/// Transfer SPL tokens of `mint` out of an account `authority` signs for
+/// Uses transfer_checked, so the token program confirms the mint and its
+/// decimals. No-op for zero amounts
+pub fn transfer_tokens<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    decimals: u8,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    
+    token::transfer_checked(
+        CpiContext::new(
+            token_program.clone(),
+            TransferChecked {
+                from: from.clone(),
+                mint: mint.clone(),
+                to: to.clone(),
+                authority: authority.clone(),
+            },
+        ),
+        amount,
+        decimals,
+    )
+}
+
+/// Grow a program-owned account to `new_len`, topping up rent from `payer`
+/// New bytes are zeroed; accounts already at least `new_len` long are left as is
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    new_len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if account.data_len() >= new_len {
+        return Ok(());
+    }
+    
+    let rent = Rent::get()?.minimum_balance(new_len);
+    let top_up = rent.saturating_sub(account.lamports());
+    transfer_lamports(payer, account, system_program, top_up)?;
+    account.realloc(new_len, true)?;
+    
+    Ok(())
+}
+
+/// Pay the marketplace fee, splitting it across Config.fee_shares when set
+/// Split recipients are read from `split_accounts` in the same order as
+/// Config.fee_shares; otherwise the whole fee goes to `fee_recipient`
+/// Any part owed to `seller` is not transferred: it is returned so the
+/// caller can fold it into the seller's own transfer
+pub fn pay_marketplace_fee<'info>(
+    config: &Config,
+    marketplace_fee: u64,
+    buyer: &AccountInfo<'info>,
+    fee_recipient: &AccountInfo<'info>,
+    split_accounts: &[AccountInfo<'info>],
+    system_program: &AccountInfo<'info>,
+    seller: &Pubkey,
+) -> Result<u64> {
+    if config.fee_shares.is_empty() {
+        if fee_recipient.key == seller {
+            return Ok(marketplace_fee);
+        }
+        transfer_lamports(buyer, fee_recipient, system_program, marketplace_fee)?;
+        return Ok(0);
+    }
+    
+    require!(
+        split_accounts.len() >= config.fee_shares.len(),
+        VerityError::InvalidFeeRecipients
+    );
+    
+    let split = config.split_marketplace_fee(marketplace_fee)?;
+    let mut owed_to_seller: u64 = 0;
+    for ((recipient, amount), account) in split.iter().zip(split_accounts) {
+        require_keys_eq!(account.key(), *recipient, VerityError::InvalidFeeRecipients);
+        if recipient == seller {
+            owed_to_seller = owed_to_seller
+                .checked_add(*amount)
+                .ok_or(VerityError::ArithmeticOverflow)?;
+            continue;
+        }
+        transfer_lamports(buyer, account, system_program, *amount)?;
+    }
+    
+    Ok(owed_to_seller)
+}
+
+/// Pay the community treasury's cut of a sale (see Config::community_cut)
+/// `community_recipient` is only required when there is something to pay
+pub fn pay_community_cut<'info>(
+    amount: u64,
+    buyer: &AccountInfo<'info>,
+    community_recipient: Option<&AccountInfo<'info>>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let recipient = community_recipient.ok_or(VerityError::InvalidCommunityRecipient)?;
+    transfer_lamports(buyer, recipient, system_program, amount)
+}
+
+/// Split a royalty across the NFT's metadata creators by their share
+/// Returns one entry per creator, in metadata order: None for creators who
+/// aren't paid. Rounding dust goes to the last paid creator
+/// With `redistribute_unverified`, unverified creators are skipped and their
+/// shares spread proportionally over the verified ones (unless none of the
+/// verified creators has a share, in which case everyone is paid as usual)
+pub fn split_creator_royalty(
+    creators: &[Creator],
+    royalty: u64,
+    redistribute_unverified: bool,
+) -> Result<Vec<Option<u64>>> {
+    let redistribute = redistribute_unverified
+        && creators.iter().any(|c| c.verified && c.share > 0);
+    let is_paid = |c: &Creator| c.share > 0 && (!redistribute || c.verified);
+    let total_shares: u64 = if redistribute {
+        creators.iter().filter(|c| is_paid(c)).map(|c| c.share as u64).sum()
+    } else {
+        100
+    };
+    
+    let last_paid = creators.iter().rposition(is_paid);
+    let mut remaining = royalty;
+    let mut amounts = Vec::with_capacity(creators.len());
+    
+    for (i, creator) in creators.iter().enumerate() {
+        if !is_paid(creator) {
+            amounts.push(None);
+            continue;
+        }
+        
+        let amount = if Some(i) == last_paid {
+            remaining
+        } else {
+            (royalty as u128)
+                .checked_mul(creator.share as u128)
+                .ok_or(VerityError::ArithmeticOverflow)?
+                .checked_div(total_shares as u128)
+                .ok_or(VerityError::ArithmeticOverflow)? as u64
+        };
+        remaining = remaining
+            .checked_sub(amount)
+            .ok_or(VerityError::ArithmeticOverflow)?;
+        amounts.push(Some(amount));
+    }
+    
+    // Every lamport of the royalty must land with a creator - fail the sale
+    // rather than leave it with the buyer (e.g. no creator has a share)
+    require!(remaining == 0, VerityError::RoyaltyUnderpaid);
+    
+    Ok(amounts)
+}
+
+/// Pay a royalty to the NFT's metadata creators, split by their share (see
+/// split_creator_royalty)
+/// Creator accounts are read from `creator_accounts` in metadata order
+/// Returns what each paid creator received. A creator who is also `seller`
+/// is recorded but not transferred to - the caller folds that amount into
+/// the seller's own transfer
+/// With `escrow`, `creator_accounts` are the creators' RoyaltyEscrow PDAs,
+/// which are credited (and created if needed) instead of the wallets
+#[allow(clippy::too_many_arguments)]
+pub fn pay_creator_royalties<'info>(
+    mint: &Pubkey,
+    creators: &[Creator],
+    royalty: u64,
+    redistribute_unverified: bool,
+    escrow: bool,
+    buyer: &AccountInfo<'info>,
+    creator_accounts: &[AccountInfo<'info>],
+    system_program: &AccountInfo<'info>,
+    seller: &Pubkey,
+) -> Result<Vec<RoyaltyPayment>> {
+    require!(
+        creator_accounts.len() >= creators.len(),
+        VerityError::InvalidCreatorAccounts
+    );
+    
+    let amounts = split_creator_royalty(creators, royalty, redistribute_unverified)?;
+    let mut payments = Vec::with_capacity(creators.len());
+    
+    for ((creator, account), amount) in creators.iter().zip(creator_accounts).zip(amounts) {
+        let escrow_bump = if escrow {
+            let (address, bump) = royalty_escrow_address(&creator.address);
+            require_keys_eq!(account.key(), address, VerityError::InvalidCreatorAccounts);
+            Some(bump)
+        } else {
+            require_keys_eq!(account.key(), creator.address, VerityError::InvalidCreatorAccounts);
+            None
+        };
+        let Some(amount) = amount else {
+            continue;
+        };
+        
+        if creator.address != *seller {
+            if let Some(bump) = escrow_bump {
+                open_royalty_escrow(account, &creator.address, bump, buyer, system_program)?;
+            }
+            transfer_lamports(buyer, account, system_program, amount)?;
+            if escrow_bump.is_some() && amount > 0 {
+                emit!(RoyaltyAccrued {
+                    creator: creator.address,
+                    mint: *mint,
+                    amount,
+                });
+            }
+        }
+        payments.push(RoyaltyPayment {
+            creator: creator.address,
+            amount,
+        });
+    }
+    
+    Ok(payments)
+}
+
+/// Check a compressed NFT's metadata against its leaf's hashes and return
+/// what a sale reads from it, creators in Token Metadata's form
+/// The Bubblegum transfer then proves the leaf itself against the tree.
+/// Bubblegum has no way to record a primary sale, so the leaf only counts
+/// as never sold while one of its verified creators is the seller
+pub fn verify_compressed_metadata(
+    metadata: &CompressedMetadataArgs,
+    leaf: &CompressedLeafArgs,
+    seller: &Pubkey,
+) -> Result<SaleMetadata> {
+    let leaf_creators: Vec<mpl_bubblegum::types::Creator> = metadata
+        .creators
+        .iter()
+        .map(|creator| mpl_bubblegum::types::Creator {
+            address: creator.address,
+            verified: creator.verified,
+            share: creator.share,
+        })
+        .collect();
+    require!(
+        mpl_bubblegum::hash::hash_creators(&leaf_creators) == leaf.creator_hash,
+        VerityError::InvalidMetadata
+    );
+    
+    let metadata_hash = keccak::hashv(&[&metadata.try_to_vec()?]);
+    let data_hash = keccak::hashv(&[
+        &metadata_hash.to_bytes(),
+        &metadata.seller_fee_basis_points.to_le_bytes(),
+    ]);
+    require!(data_hash.to_bytes() == leaf.data_hash, VerityError::InvalidMetadata);
+    
+    let seller_is_creator = metadata
+        .creators
+        .iter()
+        .any(|creator| creator.verified && creator.address == *seller);
+    Ok(SaleMetadata {
+        seller_fee_basis_points: metadata.seller_fee_basis_points,
+        creators: Some(
+            metadata
+                .creators
+                .iter()
+                .map(|creator| Creator {
+                    address: creator.address,
+                    verified: creator.verified,
+                    share: creator.share,
+                })
+                .collect(),
+        ),
+        primary_sale_happened: metadata.primary_sale_happened || !seller_is_creator,
+        collection: metadata
+            .collection
+            .filter(|collection| collection.verified)
+            .map(|collection| collection.key),
+    })
+}
+
+/// What the token leg of a split-payment sale charged
+pub struct TokenLeg {
+    pub price: u64,
+    pub marketplace_fee: u64,
+    pub royalty: u64,
+}
+
+/// Token accounts paying and receiving the token leg of a split-payment sale
+pub struct TokenLegAccounts<'a, 'info> {
+    pub buyer: &'a AccountInfo<'info>,
+    pub payment_mint: &'a Account<'info, Mint>,
+    pub buyer_tokens: &'a Account<'info, TokenAccount>,
+    pub seller_tokens: &'a Account<'info, TokenAccount>,
+    pub fee_tokens: &'a Account<'info, TokenAccount>,
+    /// Royalty recipients' token accounts: the royalty override's, or one
+    /// per metadata creator in metadata order (none when the seller keeps
+    /// the royalty). A creator's must be their associated token account for
+    /// the payment mint, and it must already exist: the program never
+    /// creates it, so the buyer does (e.g. idempotently in the same
+    /// transaction) or the sale fails
+    pub royalty_accounts: &'a [AccountInfo<'info>],
+    pub token_program: &'a AccountInfo<'info>,
+}
+
+/// Price of a split-payment sale's token leg and its fee / royalty / seller
+/// shares, in base units of the payment mint
+/// The bps math is integer-only and independent of the mint's decimals: the
+/// fee and royalty round down and the seller gets the rest, so the shares
+/// always add up to the price exactly
+pub fn token_leg_breakdown(
+    split: &SplitPayment,
+    quantity: u64,
+    fee_bps: u16,
+    enforced_bps: u16,
+) -> Result<(u64, PaymentBreakdown)> {
+    let price = split
+        .token_amount
+        .checked_mul(quantity)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    Ok((price, calculate_payment_breakdown(price, fee_bps, enforced_bps)?))
+}
+
+/// Charge the token leg of a split-payment sale: `quantity` units of the
+/// listing's token_amount, split at the same fee and royalty rates as the
+/// SOL price. The whole fee goes to `fee_recipient` and the royalty follows
+/// the SOL royalty (override or metadata creators)
+#[allow(clippy::too_many_arguments)]
+pub fn pay_token_leg<'info>(
+    split: &SplitPayment,
+    quantity: u64,
+    fee_bps: u16,
+    enforced_bps: u16,
+    royalty_override: Option<Pubkey>,
+    creators: Option<&[Creator]>,
+    redistribute_unverified: bool,
+    seller: &Pubkey,
+    fee_recipient: &Pubkey,
+    accounts: TokenLegAccounts<'_, 'info>,
+) -> Result<TokenLeg> {
+    let mint = &split.payment_mint;
+    require_keys_eq!(accounts.payment_mint.key(), *mint, VerityError::InvalidPaymentAccounts);
+    require_token_account(accounts.buyer_tokens, mint, accounts.buyer.key)?;
+    require_token_account(accounts.seller_tokens, mint, seller)?;
+    require_token_account(accounts.fee_tokens, mint, fee_recipient)?;
+    
+    let (
+        price,
+        PaymentBreakdown {
+            marketplace_fee,
+            royalty,
+            seller_amount,
+        },
+    ) = token_leg_breakdown(split, quantity, fee_bps, enforced_bps)?;
+    require!(
+        accounts.buyer_tokens.amount >= price,
+        VerityError::InsufficientFunds
+    );
+    
+    let buyer_tokens = accounts.buyer_tokens.to_account_info();
+    let payment_mint = accounts.payment_mint.to_account_info();
+    let pay = |to: &AccountInfo<'info>, amount: u64| -> Result<()> {
+        transfer_tokens(
+            &buyer_tokens,
+            to,
+            &payment_mint,
+            split.payment_decimals,
+            accounts.buyer,
+            accounts.token_program,
+            amount,
+        )
+    };
+    
+    // Royalty: same destinations as the SOL royalty; whatever belongs to the
+    // seller is folded into their transfer
+    let mut seller_total = seller_amount;
+    let royalty_owed_to_seller = match (royalty_override, creators) {
+        (Some(recipient), _) if recipient != *seller => {
+            let account = accounts
+                .royalty_accounts
+                .first()
+                .ok_or(VerityError::InvalidPaymentAccounts)?;
+            require_payment_account(account, mint, &recipient)?;
+            pay(account, royalty)?;
+            0
+        }
+        (None, Some(creators)) => {
+            require!(
+                accounts.royalty_accounts.len() >= creators.len(),
+                VerityError::InvalidPaymentAccounts
+            );
+            let amounts = split_creator_royalty(creators, royalty, redistribute_unverified)?;
+            let mut owed_to_seller: u64 = 0;
+            for ((creator, account), amount) in creators.iter().zip(accounts.royalty_accounts).zip(amounts) {
+                let Some(amount) = amount else {
+                    continue;
+                };
+                if creator.address == *seller {
+                    owed_to_seller = owed_to_seller
+                        .checked_add(amount)
+                        .ok_or(VerityError::ArithmeticOverflow)?;
+                    continue;
+                }
+                require_keys_eq!(
+                    account.key(),
+                    get_associated_token_address(&creator.address, mint),
+                    VerityError::InvalidPaymentAccounts
+                );
+                require_payment_account(account, mint, &creator.address)?;
+                pay(account, amount)?;
+            }
+            owed_to_seller
+        }
+        // Override to the seller (without creators nothing is charged)
+        _ => royalty,
+    };
+    seller_total = seller_total
+        .checked_add(royalty_owed_to_seller)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    
+    pay(&accounts.fee_tokens.to_account_info(), marketplace_fee)?;
+    pay(&accounts.seller_tokens.to_account_info(), seller_total)?;
+    
+    Ok(TokenLeg {
+        price,
+        marketplace_fee,
+        royalty,
+    })
+}
+
+/// Require an unchecked account to be an SPL token account for `mint`
+/// owned by `authority`
+pub fn require_payment_account(account: &AccountInfo, mint: &Pubkey, authority: &Pubkey) -> Result<()> {
+    require_keys_eq!(*account.owner, token::ID, VerityError::InvalidTokenAccount);
+    let data = account.try_borrow_data()?;
+    let token_account = TokenAccount::try_deserialize(&mut &data[..])?;
+    require_token_account(&token_account, mint, authority)
+}
+
+/// RoyaltyEscrow PDA (and bump) for a creator
+pub fn royalty_escrow_address(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"royalty_escrow", creator.as_ref()], &crate::ID)
+}
+
+/// Enforcement set by a collection's RoyaltyPolicy (None when the listing
+/// has no verified collection or the collection has no policy)
+/// A sale of a collection's listing must pass the policy's PDA even while
+/// it doesn't exist, so a buyer can't skip an enforcing policy by leaving
+/// the account out
+pub fn read_royalty_policy(collection: Option<Pubkey>, account: Option<&AccountInfo>) -> Result<Option<bool>> {
+    let Some(collection) = collection else {
+        return Ok(None);
+    };
+    let account = account.ok_or(VerityError::MissingRoyaltyPolicy)?;
+    let (address, _) =
+        Pubkey::find_program_address(&[b"royalty_policy", collection.as_ref()], &crate::ID);
+    require_keys_eq!(account.key(), address, VerityError::MissingRoyaltyPolicy);
+    
+    // Only this program can create an account at the PDA
+    if account.owner != &crate::ID {
+        return Ok(None);
+    }
+    let data = account.try_borrow_data()?;
+    let policy = RoyaltyPolicy::try_deserialize(&mut &data[..])?;
+    Ok(Some(policy.enforce))
+}
+
+/// CollectionTwap PDA (and bump) for a collection
+pub fn collection_twap_address(collection: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"collection_twap", collection.as_ref()], &crate::ID)
+}
+
+/// A collection's average sale price (None when the listing has no verified
+/// collection or the collection hasn't sold yet)
+/// As with read_royalty_policy, a sale of a collection's listing must pass
+/// the PDA even while it doesn't exist, so no sale can be left out of it
+pub fn read_collection_twap(collection: Option<Pubkey>, account: Option<&AccountInfo>) -> Result<Option<u64>> {
+    let Some(collection) = collection else {
+        return Ok(None);
+    };
+    let account = account.ok_or(VerityError::MissingCollectionTwap)?;
+    require_keys_eq!(
+        account.key(),
+        collection_twap_address(&collection).0,
+        VerityError::MissingCollectionTwap
+    );
+    
+    if account.owner != &crate::ID {
+        return Ok(None);
+    }
+    let data = account.try_borrow_data()?;
+    let twap = CollectionTwap::try_deserialize(&mut &data[..])?;
+    Ok(Some(twap.ema_price))
+}
+
+/// Fold a sale's unit price into the collection's CollectionTwap, creating
+/// it on the collection's first sale with `payer` covering the rent
+/// Returns the new average
+pub fn record_collection_sale<'info>(
+    account: &AccountInfo<'info>,
+    collection: &Pubkey,
+    unit_price: u64,
+    now: i64,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<u64> {
+    let (address, bump) = collection_twap_address(collection);
+    require_keys_eq!(account.key(), address, VerityError::MissingCollectionTwap);
+    
+    let mut twap = if account.owner == &crate::ID {
+        let data = account.try_borrow_data()?;
+        CollectionTwap::try_deserialize(&mut &data[..])?
+    } else {
+        create_pda_account(
+            account,
+            CollectionTwap::LEN,
+            &[b"collection_twap", collection.as_ref(), &[bump]],
+            payer,
+            system_program,
+        )?;
+        CollectionTwap {
+            collection: *collection,
+            ema_price: 0,
+            last_update_ts: 0,
+            bump,
+        }
+    };
+    twap.record(unit_price, now);
+    twap.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    Ok(twap.ema_price)
+}
+
+/// Create a program-owned PDA of `space` bytes, `payer` covering the rent
+/// The address may already hold lamports (anyone can send SOL to it), so it
+/// is topped up, allocated and assigned rather than created
+pub fn create_pda_account<'info>(
+    account: &AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    transfer_lamports(payer, account, system_program, rent.saturating_sub(account.lamports()))?;
+    
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, &crate::ID),
+        &[account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+    Ok(())
+}
+
+/// Create a creator's RoyaltyEscrow if it doesn't exist yet, `payer` covering
+/// the rent
+fn open_royalty_escrow<'info>(
+    escrow: &AccountInfo<'info>,
+    creator: &Pubkey,
+    bump: u8,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if escrow.owner == &crate::ID {
+        return Ok(());
+    }
+    
+    create_pda_account(
+        escrow,
+        RoyaltyEscrow::LEN,
+        &[b"royalty_escrow", creator.as_ref(), &[bump]],
+        payer,
+        system_program,
+    )?;
+    
+    let state = RoyaltyEscrow {
+        creator: *creator,
+        bump,
+    };
+    state.try_serialize(&mut &mut escrow.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+/// Whether the listing PDA for a vault currently holds an active listing
+/// The account may not exist (never listed, or closed on cancel/sale)
+pub fn is_listing_active(listing: &AccountInfo) -> Result<bool> {
+    if listing.data_is_empty() {
+        return Ok(false);
+    }
+    
+    let data = listing.try_borrow_data()?;
+    let listing = Listing::try_deserialize(&mut &data[..])?;
+    Ok(listing.state == ListingState::Active)
+}
+
+/// Re-verify a token account that may have come from init_if_needed
+/// An account that already exists is adopted as is, so make sure it holds
+/// `mint` and belongs to `authority` before sending anything to it
+pub fn require_token_account(account: &TokenAccount, mint: &Pubkey, authority: &Pubkey) -> Result<()> {
+    require_keys_eq!(account.mint, *mint, VerityError::InvalidTokenAccount);
+    require_keys_eq!(account.owner, *authority, VerityError::InvalidTokenAccount);
+    Ok(())
+}
+
+/// Require `mint` to be a standard SPL NFT before it is vaulted
+/// decimals/supply alone don't make an NFT: a mint with a live authority
+/// could inflate supply after the sale, leaving the buyer holding one of
+/// many. Only accept 0-decimal, supply-1 mints whose mint authority has
+/// been disabled.
+pub fn require_is_nft(mint: &Mint) -> Result<()> {
+    require!(mint.decimals == 0, VerityError::UnsupportedMint);
+    require!(mint.supply == 1, VerityError::UnsupportedMint);
+    require!(mint.mint_authority.is_none(), VerityError::UnsupportedMint);
+    Ok(())
+}
+
+/// Require `mint` to be a fixed-supply semi-fungible (e.g. an edition drop)
+/// Same rules as require_is_nft except that more than one unit exists
+pub fn require_is_semi_fungible(mint: &Mint) -> Result<()> {
+    require!(mint.decimals == 0, VerityError::UnsupportedMint);
+    require!(mint.supply > 1, VerityError::UnsupportedMint);
+    require!(mint.mint_authority.is_none(), VerityError::UnsupportedMint);
+    Ok(())
+}
+
+/// The parts of an NFT's metadata a sale reads: a Token Metadata account's,
+/// or a compressed leaf's once verify_compressed_metadata has checked it
+pub struct SaleMetadata {
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub primary_sale_happened: bool,
+    pub collection: Option<Pubkey>, // Verified collection only
+}
+
+impl From<&MetadataAccount> for SaleMetadata {
+    fn from(metadata: &MetadataAccount) -> Self {
+        SaleMetadata {
+            seller_fee_basis_points: metadata.seller_fee_basis_points,
+            creators: metadata.creators.clone(),
+            primary_sale_happened: metadata.primary_sale_happened,
+            collection: metadata
+                .collection
+                .as_ref()
+                .filter(|collection| collection.verified)
+                .map(|collection| collection.key),
+        }
+    }
+}
+
+/// What a purchase of a listing costs and where the lamports go
+pub struct SaleQuote {
+    pub unit_price: u64,
+    pub price: u64,
+    pub marketplace_fee: u64,
+    pub royalty: u64,
+    pub community_amount: u64,
+    pub seller_amount: u64,
+    /// Marketplace fee rate charged (0 for exempt sellers)
+    pub fee_bps: u16,
+    /// Royalty rate actually charged, after the marketplace bounds
+    pub enforced_bps: u16,
+    /// Metadata creators the royalty is split across (None when no
+    /// metadata was supplied, or it lists no creators)
+    pub creators: Option<Vec<Creator>>,
+    /// The metadata says the NFT was never sold before - no royalty is due
+    pub primary_sale: bool,
+    pub collection: Option<Pubkey>, // Verified collection the sale counts towards
+    pub fee_discounted: bool,       // Buyer's governance-token discount applied
+}
+
+/// Price `quantity` units of a listing the way buy_now charges them:
+/// listing conditions, oracle-tracked floor, USD floor, fee override /
+/// exemption, royalty policy, dust fee policy and the community share
+/// Shared by buy_now, buy_compressed and dry_run_buy so compressed sales
+/// and previews can't drift from a normal sale
+#[allow(clippy::too_many_arguments)]
+pub fn quote_sale(
+    config: &Config,
+    terms: &SaleTerms,
+    quantity: u64,
+    now: i64,
+    oracle: Option<&AccountInfo>,
+    sol_usd_oracle: Option<&AccountInfo>,
+    fee_exempt: bool,
+    fee_discounted: bool,
+    metadata: Option<&SaleMetadata>,
+    royalty_policy: Option<&AccountInfo>,
+    collection_twap: Option<&AccountInfo>,
+) -> Result<SaleQuote> {
+    require!(
+        quantity > 0 && quantity <= terms.remaining_quantity,
+        VerityError::InvalidQuantity
+    );
+    
+    // A split-payment listing is only buyable while its mint is still on
+    // the allowlist, so removing a mint also stops sales already listed in it
+    if let Some(payment_mint) = terms.payment_mint {
+        require!(
+            config.accepts_payment_mint(&payment_mint),
+            VerityError::UnsupportedPaymentMint
+        );
+    }
+    
+    // The verified collection recorded on the listing, or else the one in
+    // the metadata supplied now
+    let collection = terms
+        .collection
+        .or_else(|| metadata.and_then(|metadata| metadata.collection));
+    
+    // Read the oracle floor when the decay floor or min_floor tracks it
+    let oracle_floor = if terms.price_config.min_price_is_floor_pct.is_some()
+        || terms.floor_source == Some(FloorSource::Oracle)
+    {
+        let oracle = oracle.ok_or(VerityError::MissingOracle)?;
+        require!(
+            terms.oracle_feed == Some(oracle.key()),
+            VerityError::InvalidOracleAccount
+        );
+        Some(read_oracle_price_lamports(oracle, now)?)
+    } else {
+        None
+    };
+    
+    // Validate listing conditions (time window, min_floor against the
+    // source the seller chose)
+    let collection_price = read_collection_twap(collection, collection_twap)?;
+    let floor_price = match terms.floor_source {
+        Some(FloorSource::CollectionTwap) => collection_price,
+        Some(FloorSource::Oracle) => oracle_floor,
+        None => None,
+    };
+    validate_conditions(&terms.conditions, now, floor_price)?;
+    
+    // Current price (per unit) and the total for this purchase
+    let unit_price = calculate_price_with_floor(&terms.price_config, now, oracle_floor)?;
+    let price = unit_price
+        .checked_mul(quantity)
+        .ok_or(VerityError::ArithmeticOverflow)?;
+    
+    // Don't sell into a SOL crash: the sale must be worth min_usd_value
+    if let Some(min_usd_value) = terms.min_usd_value {
+        let sol_usd_oracle = sol_usd_oracle.ok_or(VerityError::MissingOracle)?;
+        require_keys_eq!(
+            sol_usd_oracle.key(),
+            config.sol_usd_feed,
+            VerityError::InvalidOracleAccount
+        );
+        let usd_per_sol = read_sol_usd_price_micros(sol_usd_oracle, now)?;
+        let usd_value = (price as u128) * (usd_per_sol as u128) / (LAMPORTS_PER_SOL as u128);
+        require!(usd_value >= min_usd_value as u128, VerityError::FloorTooLow);
+    }
+    
+    // Partner sellers with an exemption pay no marketplace fee; otherwise
+    // the listing's fee override applies within the marketplace's bounds,
+    // less the governance-token discount for buyers who qualify
+    let fee_discounted = fee_discounted && !fee_exempt;
+    let fee_bps = if fee_exempt {
+        0
+    } else if fee_discounted {
+        config
+            .listing_fee_bps(terms.override_fee_bps)
+            .saturating_sub(config.discount_bps)
+    } else {
+        config.listing_fee_bps(terms.override_fee_bps)
+    };
+    
+    // Royalty: from metadata, paid to its creators - none at all when the
+    // collection / listing / marketplace royalty policy doesn't enforce
+    // them, or on a primary sale (the creator's own first sale of the NFT)
+    // An enforced royalty needs the metadata; otherwise whoever builds the
+    // transaction could leave it out and skip the creators
+    let creators = metadata
+        .and_then(|metadata| metadata.creators.clone())
+        .filter(|creators| !creators.is_empty());
+    let primary_sale = metadata.is_some_and(|metadata| !metadata.primary_sale_happened);
+    let enforce_royalties = match read_royalty_policy(collection, royalty_policy)? {
+        Some(enforce) => enforce,
+        None => config.royalties_enforced(terms.enforce_royalties),
+    };
+    let royalty_bps = if primary_sale || !enforce_royalties {
+        0
+    } else {
+        let metadata = metadata.ok_or(VerityError::MissingMetadata)?;
+        match &creators {
+            Some(_) => config.floored_royalty_bps(metadata.seller_fee_basis_points),
+            None => 0,
+        }
+    };
+    let enforced_bps = config.effective_royalty_bps(royalty_bps);
+    
+    // Calculate fees (exempt sellers skip the dust fee policy)
+    let breakdown = calculate_payment_breakdown(price, fee_bps, enforced_bps)?;
+    let PaymentBreakdown {
+        marketplace_fee,
+        royalty,
+        seller_amount,
+    } = if fee_exempt {
+        breakdown
+    } else {
+        config.apply_dust_fee_policy(breakdown)?
+    };
+    
+    // The community treasury's share comes out of the seller's proceeds
+    let community_amount = config.community_cut(price, seller_amount)?;
+    
+    Ok(SaleQuote {
+        unit_price,
+        price,
+        marketplace_fee,
+        royalty,
+        community_amount,
+        seller_amount: seller_amount - community_amount,
+        fee_bps,
+        enforced_bps,
+        creators,
+        primary_sale,
+        collection,
+        fee_discounted,
+    })
+}
+
+/// Gate a new listing the same way on every listing path, normal or
+/// compressed: take one of the seller's Config.max_active_listings slots
+/// and move Config.listing_deposit from the seller into the listing account
+/// (less `held`, a deposit the account still holds from an earlier listing)
+/// Returns the deposit the listing now holds
+pub fn open_listing<'info>(
+    config: &Config,
+    seller_state: &mut SellerState,
+    seller: &AccountInfo<'info>,
+    listing: &AccountInfo<'info>,
+    held: u64,
+    system_program: &AccountInfo<'info>,
+) -> Result<u64> {
+    seller_state.claim_slot(config.max_active_listings)?;
+    
+    // Anti-spam deposit: returned with the account's rent when it closes
+    transfer_lamports(
+        seller,
+        listing,
+        system_program,
+        config.listing_deposit.saturating_sub(held),
+    )?;
+    Ok(config.listing_deposit.max(held))
+}
+
+/// Give back the seller's active-listing slot when a listing stops being
+/// active (sold out, cancelled, deactivated, expired, reconciled)
+/// Listings created before SellerState existed never took a slot, so they
+/// don't need the account
+pub fn release_seller_slot(listing: &mut Listing, seller_state: Option<&mut SellerState>) -> Result<()> {
+    if !listing.seller_counted {
+        return Ok(());
+    }
+    
+    seller_state.ok_or(VerityError::MissingSellerState)?.release_slot();
+    listing.seller_counted = false;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{CompressedCollection, CompressedCreator, CompressedUses};
+    
+    fn creator(share: u8, verified: bool) -> Creator {
+        Creator {
+            address: Pubkey::new_unique(),
+            verified,
+            share,
+        }
+    }
+    
+    fn paid(amounts: &[Option<u64>]) -> u64 {
+        amounts.iter().flatten().sum()
+    }
+    
+    #[test]
+    fn split_creator_royalty_pays_the_whole_royalty() {
+        let creators = [creator(33, true), creator(33, true), creator(34, true)];
+        for royalty in [1, 2, 99, 100, 1_001, 999_999_999] {
+            let amounts = split_creator_royalty(&creators, royalty, false).unwrap();
+            assert_eq!(amounts.len(), 3);
+            assert_eq!(paid(&amounts), royalty);
+        }
+    }
+    
+    #[test]
+    fn split_creator_royalty_gives_the_dust_to_the_last_paid_creator() {
+        let creators = [
+            creator(25, true),
+            creator(25, true),
+            creator(25, true),
+            creator(25, true),
+            creator(0, true),
+        ];
+        let amounts = split_creator_royalty(&creators, 1_003, false).unwrap();
+        assert_eq!(amounts, vec![Some(250), Some(250), Some(250), Some(253), None]);
+    }
+    
+    #[test]
+    fn split_creator_royalty_redistributes_unverified_shares() {
+        let creators = [creator(20, true), creator(30, false), creator(50, true)];
+        let amounts = split_creator_royalty(&creators, 1_001, true).unwrap();
+        assert_eq!(amounts, vec![Some(286), None, Some(715)]);
+        assert_eq!(paid(&amounts), 1_001);
+        
+        // Without redistribution the unverified creator keeps their share
+        let amounts = split_creator_royalty(&creators, 1_001, false).unwrap();
+        assert_eq!(amounts, vec![Some(200), Some(300), Some(501)]);
+    }
+    
+    #[test]
+    fn split_creator_royalty_rejects_creators_without_shares() {
+        let creators = [creator(0, true), creator(0, false), creator(0, true)];
+        assert_eq!(
+            split_creator_royalty(&creators, 7, false).unwrap_err(),
+            VerityError::RoyaltyUnderpaid.into()
+        );
+        assert_eq!(split_creator_royalty(&creators, 0, false).unwrap(), vec![None, None, None]);
+    }    
+    fn split(token_amount: u64, payment_decimals: u8) -> SplitPayment {
+        SplitPayment {
+            payment_mint: Pubkey::new_unique(),
+            token_amount,
+            payment_decimals,
+        }
+    }
+    
+    fn assert_adds_up(price: u64, breakdown: &PaymentBreakdown) {
+        assert_eq!(
+            breakdown.marketplace_fee + breakdown.royalty + breakdown.seller_amount,
+            price
+        );
+    }
+    
+    #[test]
+    fn token_leg_breakdown_adds_up_at_6_decimals() {
+        // 12.345679 USDC a unit, three units, 2.5% fee and 7.77% royalty
+        let (price, breakdown) = token_leg_breakdown(&split(12_345_679, 6), 3, 250, 777).unwrap();
+        assert_eq!(price, 37_037_037);
+        assert_eq!(breakdown.marketplace_fee, 925_925);
+        assert_eq!(breakdown.royalty, 2_877_777);
+        assert_eq!(breakdown.seller_amount, 33_233_335);
+        assert_adds_up(price, &breakdown);
+        
+        // A single base unit can't pay a fee or royalty; the seller gets it
+        let (price, breakdown) = token_leg_breakdown(&split(1, 6), 1, 250, 777).unwrap();
+        assert_eq!((breakdown.marketplace_fee, breakdown.royalty), (0, 0));
+        assert_adds_up(price, &breakdown);
+    }
+    
+    #[test]
+    fn token_leg_breakdown_adds_up_at_9_decimals() {
+        // 1.000000001 tokens a unit, 2.5% fee and 7.77% royalty
+        let (price, breakdown) = token_leg_breakdown(&split(1_000_000_001, 9), 1, 250, 777).unwrap();
+        assert_eq!(breakdown.marketplace_fee, 25_000_000);
+        assert_eq!(breakdown.royalty, 77_700_000);
+        assert_eq!(breakdown.seller_amount, 897_300_001);
+        assert_adds_up(price, &breakdown);
+        
+        // Large 9-decimal amounts don't overflow the bps math
+        let (price, breakdown) =
+            token_leg_breakdown(&split(u64::MAX / 2, 9), 2, 1_000, 9_000).unwrap();
+        assert_eq!(breakdown.seller_amount, 1);
+        assert_adds_up(price, &breakdown);
+        assert_eq!(
+            token_leg_breakdown(&split(u64::MAX, 9), 2, 250, 0).err().unwrap(),
+            VerityError::ArithmeticOverflow.into()
+        );
+    }
+    
+    #[test]
+    fn usdc_token_leg_pays_two_creators_their_royalty_shares() {
+        // 25.000001 USDC, 2.5% fee and a 7.77% royalty split 60/40
+        let (price, breakdown) = token_leg_breakdown(&split(25_000_001, 6), 1, 250, 777).unwrap();
+        assert_eq!(breakdown.marketplace_fee, 625_000);
+        assert_eq!(breakdown.royalty, 1_942_500);
+        assert_eq!(breakdown.seller_amount, 22_432_501);
+        assert_adds_up(price, &breakdown);
+        
+        let creators = [creator(60, true), creator(40, false)];
+        let amounts = split_creator_royalty(&creators, breakdown.royalty, false).unwrap();
+        assert_eq!(amounts, vec![Some(1_165_500), Some(777_000)]);
+        assert_eq!(paid(&amounts), breakdown.royalty);
+    }
+    
+    fn compressed_metadata(creator: Pubkey) -> CompressedMetadataArgs {
+        CompressedMetadataArgs {
+            name: "Leaf".to_string(),
+            symbol: "LEAF".to_string(),
+            uri: "https://example.com/leaf.json".to_string(),
+            seller_fee_basis_points: 500,
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: Some(7),
+            token_standard: Some(0),
+            collection: Some(CompressedCollection {
+                verified: true,
+                key: Pubkey::new_unique(),
+            }),
+            uses: Some(CompressedUses {
+                use_method: 2,
+                remaining: 3,
+                total: 5,
+            }),
+            token_program_version: 0,
+            creators: vec![CompressedCreator {
+                address: creator,
+                verified: true,
+                share: 100,
+            }],
+        }
+    }
+    
+    fn leaf_of(metadata: &CompressedMetadataArgs) -> CompressedLeafArgs {
+        use mpl_bubblegum::types::{
+            Collection, Creator, MetadataArgs, TokenProgramVersion, TokenStandard, UseMethod, Uses
+        };
+        let creators: Vec<Creator> = metadata
+            .creators
+            .iter()
+            .map(|creator| Creator {
+                address: creator.address,
+                verified: creator.verified,
+                share: creator.share,
+            })
+            .collect();
+        let collection = metadata.collection.unwrap();
+        let bubblegum = MetadataArgs {
+            name: metadata.name.clone(),
+            symbol: metadata.symbol.clone(),
+            uri: metadata.uri.clone(),
+            seller_fee_basis_points: metadata.seller_fee_basis_points,
+            primary_sale_happened: metadata.primary_sale_happened,
+            is_mutable: metadata.is_mutable,
+            edition_nonce: metadata.edition_nonce,
+            token_standard: Some(TokenStandard::NonFungible),
+            collection: Some(Collection {
+                verified: collection.verified,
+                key: collection.key,
+            }),
+            uses: Some(Uses {
+                use_method: UseMethod::Single,
+                remaining: 3,
+                total: 5,
+            }),
+            token_program_version: TokenProgramVersion::Original,
+            creators: creators.clone(),
+        };
+        CompressedLeafArgs {
+            root: [0; 32],
+            data_hash: mpl_bubblegum::hash::hash_metadata(&bubblegum).unwrap(),
+            creator_hash: mpl_bubblegum::hash::hash_creators(&creators),
+            nonce: 0,
+            index: 0,
+        }
+    }
+    
+    #[test]
+    fn verify_compressed_metadata_matches_bubblegum_hashes() {
+        let creator = Pubkey::new_unique();
+        let metadata = compressed_metadata(creator);
+        let leaf = leaf_of(&metadata);
+        let sale = verify_compressed_metadata(&metadata, &leaf, &Pubkey::new_unique()).unwrap();
+        assert_eq!(sale.seller_fee_basis_points, 500);
+        assert_eq!(sale.collection, metadata.collection.map(|collection| collection.key));
+        assert_eq!(sale.creators.unwrap()[0].address, creator);
+        
+        // Any field that differs from the leaf fails the check
+        let mut tampered = metadata.clone();
+        tampered.seller_fee_basis_points = 0;
+        assert_eq!(
+            verify_compressed_metadata(&tampered, &leaf, &creator).err().unwrap(),
+            VerityError::InvalidMetadata.into()
+        );
+        let mut tampered = metadata;
+        tampered.primary_sale_happened = true;
+        assert_eq!(
+            verify_compressed_metadata(&tampered, &leaf, &creator).err().unwrap(),
+            VerityError::InvalidMetadata.into()
+        );
+    }
+    
+    #[test]
+    fn verify_compressed_metadata_only_lets_a_creator_make_the_primary_sale() {
+        let creator = Pubkey::new_unique();
+        let metadata = compressed_metadata(creator);
+        let leaf = leaf_of(&metadata);
+        assert!(!verify_compressed_metadata(&metadata, &leaf, &creator)
+            .unwrap()
+            .primary_sale_happened);
+        assert!(verify_compressed_metadata(&metadata, &leaf, &Pubkey::new_unique())
+            .unwrap()
+            .primary_sale_happened);
+    }
+}
\ No newline at end of file
@@ -9,8 +9,19 @@ import {
   TOKEN_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   getAssociatedTokenAddress,
+  setAuthority,
+  AuthorityType,
+  approve,
+  createAssociatedTokenAccount,
 } from "@solana/spl-token";
-import { PublicKey, Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import {
+  PublicKey,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
 import { assert, expect } from "chai";
 
 describe("Verity Escrowless NFT Marketplace", () => {
@@ -26,19 +37,174 @@ describe("Verity Escrowless NFT Marketplace", () => {
   const MIN_PRICE = new BN(1 * LAMPORTS_PER_SOL);
   const DURATION = new BN(3600); // 1 hour
 
+  // Marketplace authority (set by the config initialization suite)
+  const configAuthority = Keypair.generate();
+
   // Helper to airdrop and confirm
   async function airdrop(pubkey: PublicKey, amount = 10 * LAMPORTS_PER_SOL) {
     const sig = await provider.connection.requestAirdrop(pubkey, amount);
     await provider.connection.confirmTransaction(sig, "confirmed");
   }
 
-  // Helper to create NFT mint
-  async function createNFT(owner: Keypair): Promise<{ mint: PublicKey; tokenAccount: PublicKey }> {
+  // Token Metadata (cloned into the local validator, see Anchor.toml)
+  const TOKEN_METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+  function getMetadataPDA(mint: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0];
+  }
+
+  function getMasterEditionPDA(mint: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("metadata"),
+        TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+        mint.toBuffer(),
+        Buffer.from("edition"),
+      ],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0];
+  }
+
+  type MetadataOpts = {
+    sellerFeeBps?: number;
+    creators?: { address: PublicKey; share: number }[]; // Unverified
+    collection?: { mint: PublicKey; authority: Keypair }; // Verified by its authority
+    primarySaleHappened?: boolean;
+  };
+
+  // Helper to send a hand-encoded Token Metadata instruction (the suite
+  // doesn't depend on the Metaplex SDK); keys are [pubkey, signer, writable]
+  async function sendMetadataIx(
+    signers: Keypair[],
+    keys: [PublicKey, boolean, boolean][],
+    data: Buffer
+  ) {
+    const ix = new TransactionInstruction({
+      programId: TOKEN_METADATA_PROGRAM_ID,
+      keys: keys.map(([pubkey, isSigner, isWritable]) => ({ pubkey, isSigner, isWritable })),
+      data,
+    });
+    await provider.sendAndConfirm(new Transaction().add(ix), signers);
+  }
+
+  function borshU32(value: number): Buffer {
+    const buf = Buffer.alloc(4);
+    buf.writeUInt32LE(value);
+    return buf;
+  }
+
+  function borshString(value: string): Buffer {
+    return Buffer.concat([borshU32(value.length), Buffer.from(value)]);
+  }
+
+  // Helper to create `mint`'s metadata (CreateMetadataAccountV3) with
+  // `authority` as mint authority, update authority and payer
+  async function createMetadata(authority: Keypair, mint: PublicKey, opts: MetadataOpts = {}) {
+    const metadata = getMetadataPDA(mint);
+    const sellerFeeBps = Buffer.alloc(2);
+    sellerFeeBps.writeUInt16LE(opts.sellerFeeBps ?? 0);
+    const creators = opts.creators
+      ? Buffer.concat([
+          Buffer.from([1]),
+          borshU32(opts.creators.length),
+          ...opts.creators.map((c) => Buffer.concat([c.address.toBuffer(), Buffer.from([0, c.share])])),
+        ])
+      : Buffer.from([0]);
+    const collection = opts.collection
+      ? Buffer.concat([Buffer.from([1, 0]), opts.collection.mint.toBuffer()])
+      : Buffer.from([0]);
+
+    await sendMetadataIx(
+      [authority],
+      [
+        [metadata, false, true],
+        [mint, false, false],
+        [authority.publicKey, true, false], // mint authority
+        [authority.publicKey, true, true], // payer
+        [authority.publicKey, true, false], // update authority
+        [SystemProgram.programId, false, false],
+      ],
+      Buffer.concat([
+        Buffer.from([33]), // CreateMetadataAccountV3
+        borshString("Verity Test"),
+        borshString("VRT"),
+        borshString(""),
+        sellerFeeBps,
+        creators,
+        collection,
+        Buffer.from([0]), // uses
+        Buffer.from([1]), // is_mutable
+        Buffer.from([0]), // collection_details
+      ])
+    );
+
+    if (opts.collection) {
+      const { mint: collectionMint, authority: collectionAuthority } = opts.collection;
+      await sendMetadataIx(
+        [collectionAuthority],
+        [
+          [metadata, false, true],
+          [collectionAuthority.publicKey, true, true], // collection authority
+          [collectionAuthority.publicKey, true, true], // payer
+          [collectionMint, false, false],
+          [getMetadataPDA(collectionMint), false, false],
+          [getMasterEditionPDA(collectionMint), false, false],
+        ],
+        Buffer.from([18]) // VerifyCollection
+      );
+    }
+
+    if (opts.primarySaleHappened) {
+      await sendMetadataIx(
+        [authority],
+        [
+          [metadata, false, true],
+          [authority.publicKey, true, false],
+        ],
+        // UpdateMetadataAccountV2: data, update_authority, primary_sale_happened, is_mutable
+        Buffer.from([15, 0, 0, 1, 1, 0])
+      );
+    }
+  }
+
+  // Helper to create a collection NFT (metadata and master edition)
+  async function createCollection(authority: Keypair): Promise<PublicKey> {
+    const mint = await createMint(provider.connection, authority, authority.publicKey, null, 0);
+    const tokenAccount = await createAccount(provider.connection, authority, mint, authority.publicKey);
+    await mintTo(provider.connection, authority, mint, tokenAccount, authority, 1);
+    await createMetadata(authority, mint);
+    await sendMetadataIx(
+      [authority],
+      [
+        [getMasterEditionPDA(mint), false, true],
+        [mint, false, true],
+        [authority.publicKey, true, false], // update authority
+        [authority.publicKey, true, false], // mint authority
+        [authority.publicKey, true, true], // payer
+        [getMetadataPDA(mint), false, true],
+        [TOKEN_PROGRAM_ID, false, false],
+        [SystemProgram.programId, false, false],
+      ],
+      Buffer.from([17, 1, 0, 0, 0, 0, 0, 0, 0, 0]) // CreateMasterEditionV3, max_supply = Some(0)
+    );
+    return mint;
+  }
+
+  // Helper to create NFT mint (with a Token Metadata account when `metadata` is given)
+  async function createNFT(
+    owner: Keypair,
+    keepMintAuthority = false,
+    freezeAuthority: PublicKey | null = null,
+    metadata: MetadataOpts | null = null
+  ): Promise<{ mint: PublicKey; tokenAccount: PublicKey }> {
     const mint = await createMint(
       provider.connection,
       owner,
       owner.publicKey,
-      null,
+      freezeAuthority,
       0 // NFT = 0 decimals
     );
     const tokenAccount = await createAccount(
@@ -55,14 +221,87 @@ describe("Verity Escrowless NFT Marketplace", () => {
       owner,
       1 // NFT = supply of 1
     );
+    if (metadata) {
+      await createMetadata(owner, mint, metadata);
+    }
+    if (!keepMintAuthority) {
+      // A real NFT can never be minted again
+      await setAuthority(
+        provider.connection,
+        owner,
+        mint,
+        owner,
+        AuthorityType.MintTokens,
+        null
+      );
+    }
     return { mint, tokenAccount };
   }
 
+  // Helper: UpdateConfigArgs that leaves every field not in `changes` unchanged
+  function configUpdate(changes: Record<string, any>): any {
+    return {
+      maxRoyaltyBps: null,
+      startTsGrace: null,
+      minDuration: null,
+      maxDuration: null,
+      dustFeePolicy: null,
+      minFeeLamports: null,
+      feeBps: null,
+      emergencyUntil: null,
+      royaltyDefault: null,
+      allowRoyaltyOptOut: null,
+      maxFeeDiscountBps: null,
+      communityBps: null,
+      communityRecipient: null,
+      expiryGrace: null,
+      minRoyaltyBps: null,
+      listingDeposit: null,
+      relistCooldown: null,
+      feeRecipient: null,
+      maxActiveListings: null,
+      solUsdFeed: null,
+      redistributeUnverifiedRoyalty: null,
+      escrowRoyalties: null,
+      rejectFreezableMints: null,
+      minListingPrice: null,
+      discountMint: null,
+      discountThreshold: null,
+      discountBps: null,
+      ...changes,
+    };
+  }
+
   // Helper to derive PDAs
   function getConfigPDA(): [PublicKey, number] {
     return PublicKey.findProgramAddressSync([Buffer.from("config")], PROGRAM_ID);
   }
 
+  function getStatsPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync([Buffer.from("stats")], PROGRAM_ID);
+  }
+
+  function getUserStatsPDA(user: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stats"), user.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  function getProceedsPDA(seller: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("proceeds"), seller.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  function getFeeExemptionPDA(seller: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("fee_exempt"), seller.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
   function getUserVaultPDA(owner: PublicKey, mint: PublicKey): [PublicKey, number] {
     return PublicKey.findProgramAddressSync(
       [Buffer.from("user_vault"), owner.toBuffer(), mint.toBuffer()],
@@ -77,12 +316,105 @@ describe("Verity Escrowless NFT Marketplace", () => {
     );
   }
 
+  // Helper to vault a fresh NFT and list it
+  async function setupListing(
+    seller: Keypair,
+    opts: {
+      priceType?: object;
+      startPrice?: BN;
+      minPrice?: BN;
+      floorPct?: number | null;
+      oracleFeed?: PublicKey | null;
+      royaltyOverride?: PublicKey | null;
+      enforceRoyalties?: boolean | null;
+      overrideFeeBps?: number | null;
+      escrowProceeds?: boolean;
+      validUntil?: BN | null;
+      minFloor?: BN | null;
+      quantity?: BN | null;
+      minUsdValue?: BN | null;
+      boostLamports?: BN | null;
+      feeRecipient?: PublicKey | null;
+      freezable?: boolean;
+      splitPayment?: { paymentMint: PublicKey; tokenAmount: BN; paymentDecimals: number } | null;
+      floorSource?: object | null;
+      metadata?: MetadataOpts;
+    } = {}
+  ): Promise<{
+    mint: PublicKey;
+    userVaultPda: PublicKey;
+    vaultAta: PublicKey;
+    listingPda: PublicKey;
+  }> {
+    const nft = await createNFT(
+      seller,
+      false,
+      opts.freezable ? seller.publicKey : null,
+      opts.metadata
+    );
+    const [userVaultPda] = getUserVaultPDA(seller.publicKey, nft.mint);
+    const [listingPda] = getListingPDA(seller.publicKey, nft.mint);
+    const vaultAta = await getAssociatedTokenAddress(nft.mint, userVaultPda, true);
+
+    await program.methods
+      .initializeUserVault()
+      .accountsPartial({
+        userVault: userVaultPda,
+        vaultPda: userVaultPda,
+        owner: seller.publicKey,
+        ownerTokenAccount: nft.tokenAccount,
+        vaultAta: vaultAta,
+        mint: nft.mint,
+      })
+      .signers([seller])
+      .rpc();
+
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .createListing(
+        (opts.priceType ?? { fixed: {} }) as any,
+        opts.startPrice ?? START_PRICE,
+        opts.minPrice ?? MIN_PRICE,
+        new BN(now),
+        DURATION,
+        opts.minFloor ?? null, // min_floor
+        null, // valid_from
+        opts.validUntil ?? null, // valid_until
+        opts.floorPct ?? null,
+        null,
+        opts.oracleFeed ?? null,
+        opts.royaltyOverride ?? null,
+        opts.enforceRoyalties ?? null,
+        opts.overrideFeeBps ?? null,
+        opts.escrowProceeds ?? false,
+        opts.quantity ?? null,
+        opts.minUsdValue ?? null,
+        opts.boostLamports ?? null,
+        opts.splitPayment ?? null,
+        opts.floorSource ?? null
+      )
+      .accountsPartial({
+        listing: listingPda,
+        userVault: userVaultPda,
+        vaultAta: vaultAta,
+        seller: seller.publicKey,
+        mint: nft.mint,
+        metadata: opts.metadata ? getMetadataPDA(nft.mint) : null,
+        feeRecipient: opts.feeRecipient ?? null,
+        paymentMint: opts.splitPayment?.paymentMint ?? null,
+      })
+      .signers([seller])
+      .rpc();
+
+    return { mint: nft.mint, userVaultPda, vaultAta, listingPda };
+  }
+
   // ============================================
   // Test Suite Setup
   // ============================================
 
   describe("Config Initialization", () => {
-    const authority = Keypair.generate();
+    const authority = configAuthority;
     const feeRecipient = Keypair.generate();
 
     before(async () => {
@@ -92,19 +424,238 @@ describe("Verity Escrowless NFT Marketplace", () => {
     it("initializes marketplace config", async () => {
       const [configPda] = getConfigPDA();
 
-      await program.methods
+      const builder = program.methods
         .initializeConfig(FEE_BPS, feeRecipient.publicKey)
         .accountsPartial({
           config: configPda,
           authority: authority.publicKey,
         })
-        .signers([authority])
-        .rpc();
+        .signers([authority]);
+      const sim = await builder.simulate();
+      const initialized = sim.events.find((e) => e.name === "configInitialized").data;
+      assert.equal(initialized.authority.toString(), authority.publicKey.toString());
+      assert.equal(initialized.feeBps, FEE_BPS);
+      assert.equal(initialized.feeRecipient.toString(), feeRecipient.publicKey.toString());
+      assert.equal(initialized.accountVersion, 3);
+      assert.isNotEmpty(initialized.programVersion);
+      await builder.rpc();
 
       const config = await program.account.config.fetch(configPda);
       assert.equal(config.authority.toString(), authority.publicKey.toString());
       assert.equal(config.feeBps, FEE_BPS);
       assert.equal(config.feeRecipient.toString(), feeRecipient.publicKey.toString());
+
+      const [statsPda] = getStatsPDA();
+      const stats = await program.account.stats.fetch(statsPda);
+      assert.equal(stats.totalVolume.toString(), "0");
+      assert.equal(stats.totalSales.toString(), "0");
+      assert.equal(stats.activeListings.toString(), "0");
+      assert.equal(config.maxRoyaltyBps, 1000);
+      assert.equal(config.startTsGrace.toNumber(), 300);
+      assert.equal(config.minDuration.toNumber(), 1);
+      assert.equal(config.version, 3);
+      assert.isAbove(config.initTs.toNumber(), 0);
+    });
+
+    it("lets the authority stop enforcing royalties by default", async () => {
+      // The local validator has no Token Metadata program, so sales can't
+      // supply the metadata an enforced royalty needs; the Creator Royalties
+      // tests switch enforcement back on
+      const [configPda] = getConfigPDA();
+      await program.methods
+        .updateConfig(configUpdate({ royaltyDefault: { skip: {} } }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const config = await program.account.config.fetch(configPda);
+      assert.deepEqual(config.royaltyDefault, { skip: {} });
+    });
+
+    it("lets the authority update the royalty cap", async () => {
+      const [configPda] = getConfigPDA();
+
+      await program.methods
+        .updateConfig(configUpdate({ maxRoyaltyBps: 300 }))
+        .accountsPartial({ config: configPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      let config = await program.account.config.fetch(configPda);
+      assert.equal(config.maxRoyaltyBps, 300);
+
+      // Restore the default cap
+      await program.methods
+        .updateConfig(configUpdate({ maxRoyaltyBps: 1000 }))
+        .accountsPartial({ config: configPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      config = await program.account.config.fetch(configPda);
+      assert.equal(config.maxRoyaltyBps, 1000);
+    });
+
+    it("allows fee_bps up to MAX_FEE_BPS (1000) and rejects 1001", async () => {
+      const [configPda] = getConfigPDA();
+
+      await program.methods
+        .updateConfig(configUpdate({ feeBps: 1000 }))
+        .accountsPartial({ config: configPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      let config = await program.account.config.fetch(configPda);
+      assert.equal(config.feeBps, 1000);
+
+      try {
+        await program.methods
+          .updateConfig(configUpdate({ feeBps: 1001 }))
+          .accountsPartial({ config: configPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFeeBps");
+      }
+
+      // Restore the suite's fee
+      await program.methods
+        .updateConfig(configUpdate({ feeBps: FEE_BPS }))
+        .accountsPartial({ config: configPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      config = await program.account.config.fetch(configPda);
+      assert.equal(config.feeBps, FEE_BPS);
+    });
+
+    it("blocks user instructions during an emergency and lifts it", async () => {
+      const [configPda] = getConfigPDA();
+      const owner = Keypair.generate();
+      await airdrop(owner.publicKey);
+      const nft = await createNFT(owner);
+      const [userVaultPda] = getUserVaultPDA(owner.publicKey, nft.mint);
+      const vaultAta = await getAssociatedTokenAddress(nft.mint, userVaultPda, true);
+      const now = Math.floor(Date.now() / 1000);
+
+      await program.methods
+        .updateConfig(configUpdate({ emergencyUntil: new BN(now + 3600) }))
+        .accountsPartial({ config: configPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+
+      try {
+        await program.methods
+          .initializeUserVault()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            owner: owner.publicKey,
+            ownerTokenAccount: nft.tokenAccount,
+            vaultAta: vaultAta,
+            mint: nft.mint,
+          })
+          .signers([owner])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("EmergencyActive");
+      }
+
+      // Any past timestamp lifts emergency mode
+      await program.methods
+        .updateConfig(configUpdate({ emergencyUntil: new BN(0) }))
+        .accountsPartial({ config: configPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      const config = await program.account.config.fetch(configPda);
+      assert.equal(config.emergencyUntil.toNumber(), 0);
+    });
+
+    it("refuses to close the config outside an emergency", async () => {
+      try {
+        await program.methods
+          .closeConfig()
+          .accountsPartial({ authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("EmergencyNotActive");
+      }
+    });
+
+    it("rejects an emergency window longer than 72 hours", async () => {
+      const [configPda] = getConfigPDA();
+      const now = Math.floor(Date.now() / 1000);
+
+      try {
+        await program.methods
+          .updateConfig(configUpdate({ emergencyUntil: new BN(now + 4 * 24 * 60 * 60) }))
+          .accountsPartial({ config: configPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("EmergencyTooLong");
+      }
+    });
+
+    it("rejects inverted listing duration bounds", async () => {
+      const [configPda] = getConfigPDA();
+
+      try {
+        await program.methods
+          .updateConfig(configUpdate({ minDuration: new BN(7200), maxDuration: new BN(3600) }))
+          .accountsPartial({ config: configPda, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidDuration");
+      }
+    });
+
+    it("migrates the config (no-op when already current)", async () => {
+      const [configPda] = getConfigPDA();
+
+      await program.methods
+        .migrateConfig()
+        .accountsPartial({ config: configPda, authority: authority.publicKey })
+        .signers([authority])
+        .rpc();
+      const config = await program.account.config.fetch(configPda);
+      assert.equal(config.version, 3);
+      assert.equal(config.feeBps, FEE_BPS);
+    });
+
+    it("rejects config migration by non-authority", async () => {
+      const [configPda] = getConfigPDA();
+      const attacker = Keypair.generate();
+      await airdrop(attacker.publicKey);
+
+      try {
+        await program.methods
+          .migrateConfig()
+          .accountsPartial({ config: configPda, authority: attacker.publicKey })
+          .signers([attacker])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedAuthority");
+      }
+    });
+
+    it("rejects config updates from non-authority", async () => {
+      const [configPda] = getConfigPDA();
+      const attacker = Keypair.generate();
+
+      try {
+        await program.methods
+          .updateConfig(configUpdate({ maxRoyaltyBps: 0 }))
+          .accountsPartial({ config: configPda, authority: attacker.publicKey })
+          .signers([attacker])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedAuthority");
+      }
     });
 
     it("fails to reinitialize config", async () => {
@@ -175,6 +726,73 @@ describe("Verity Escrowless NFT Marketplace", () => {
       assert.equal(sellerAccount.amount.toString(), "0");
     });
 
+    it("refuses to close a vault that still holds the NFT", async () => {
+      const [userVaultPda] = getUserVaultPDA(seller.publicKey, mint);
+      const vaultAta = await getAssociatedTokenAddress(mint, userVaultPda, true);
+
+      try {
+        await program.methods
+          .closeUserVault()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            listing: getListingPDA(seller.publicKey, mint)[0],
+            owner: seller.publicKey,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("VaultNotEmpty");
+      }
+    });
+
+    it("refuses to reconcile a vault that still holds the NFT", async () => {
+      const [userVaultPda] = getUserVaultPDA(seller.publicKey, mint);
+      const vaultAta = await getAssociatedTokenAddress(mint, userVaultPda, true);
+
+      try {
+        await program.methods
+          .reconcileVault()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            listing: null,
+            owner: seller.publicKey,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("VaultNotEmpty");
+      }
+    });
+
+    it("refuses to sweep a vault that still holds the NFT", async () => {
+      const [userVaultPda] = getUserVaultPDA(seller.publicKey, mint);
+      const vaultAta = await getAssociatedTokenAddress(mint, userVaultPda, true);
+
+      try {
+        await program.methods
+          .sweepEmptyVaultAta()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            listing: getListingPDA(seller.publicKey, mint)[0],
+            owner: seller.publicKey,
+            authority: configAuthority.publicKey,
+          })
+          .signers([configAuthority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("VaultNotEmpty");
+      }
+    });
+
     it("fails to create duplicate vault", async () => {
       const [userVaultPda] = getUserVaultPDA(seller.publicKey, mint);
       const vaultAta = await getAssociatedTokenAddress(mint, userVaultPda, true);
@@ -197,14 +815,278 @@ describe("Verity Escrowless NFT Marketplace", () => {
         expect(err.toString()).to.include("already in use");
       }
     });
-  });
 
-  // ============================================
-  // Listing Tests
-  // ============================================
+    it("rejects a mint whose mint authority is still live", async () => {
+      const nft = await createNFT(seller, true);
+      const [userVaultPda] = getUserVaultPDA(seller.publicKey, nft.mint);
+      const vaultAta = await getAssociatedTokenAddress(nft.mint, userVaultPda, true);
 
-  describe("Listings", () => {
-    let seller: Keypair;
+      try {
+        await program.methods
+          .initializeUserVault()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            owner: seller.publicKey,
+            ownerTokenAccount: nft.tokenAccount,
+            vaultAta: vaultAta,
+            mint: nft.mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnsupportedMint");
+      }
+    });
+
+    it("rejects a fungible mint with decimals", async () => {
+      const mint = await createMint(provider.connection, seller, seller.publicKey, null, 6);
+      const tokenAccount = await createAccount(
+        provider.connection,
+        seller,
+        mint,
+        seller.publicKey
+      );
+      await mintTo(provider.connection, seller, mint, tokenAccount, seller, 1);
+      await setAuthority(
+        provider.connection,
+        seller,
+        mint,
+        seller,
+        AuthorityType.MintTokens,
+        null
+      );
+      const [userVaultPda] = getUserVaultPDA(seller.publicKey, mint);
+      const vaultAta = await getAssociatedTokenAddress(mint, userVaultPda, true);
+
+      try {
+        await program.methods
+          .initializeUserVault()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            owner: seller.publicKey,
+            ownerTokenAccount: tokenAccount,
+            vaultAta: vaultAta,
+            mint: mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnsupportedMint");
+      }
+    });
+
+    it("rejects a source account with an active delegate", async () => {
+      const nft = await createNFT(seller);
+      const delegate = Keypair.generate();
+      await approve(
+        provider.connection,
+        seller,
+        nft.tokenAccount,
+        delegate.publicKey,
+        seller,
+        1
+      );
+      const [userVaultPda] = getUserVaultPDA(seller.publicKey, nft.mint);
+      const vaultAta = await getAssociatedTokenAddress(nft.mint, userVaultPda, true);
+
+      try {
+        await program.methods
+          .initializeUserVault()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            owner: seller.publicKey,
+            ownerTokenAccount: nft.tokenAccount,
+            vaultAta: vaultAta,
+            mint: nft.mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("DelegatedTokenAccount");
+      }
+    });
+
+    async function vaultGroup(owner: Keypair, nft: { mint: PublicKey; tokenAccount: PublicKey }) {
+      const [userVaultPda] = getUserVaultPDA(owner.publicKey, nft.mint);
+      const vaultAta = await getAssociatedTokenAddress(nft.mint, userVaultPda, true);
+      return [
+        { pubkey: nft.mint, isSigner: false, isWritable: false },
+        { pubkey: nft.tokenAccount, isSigner: false, isWritable: true },
+        { pubkey: userVaultPda, isSigner: false, isWritable: true },
+        { pubkey: vaultAta, isSigner: false, isWritable: true },
+      ];
+    }
+
+    it("vaults several NFTs in one transaction", async () => {
+      const owner = Keypair.generate();
+      await airdrop(owner.publicKey);
+      const nfts = [await createNFT(owner), await createNFT(owner)];
+
+      const remaining = [];
+      for (const nft of nfts) {
+        remaining.push(...(await vaultGroup(owner, nft)));
+      }
+      await program.methods
+        .initializeUserVaultsBatch()
+        .accountsPartial({ owner: owner.publicKey })
+        .remainingAccounts(remaining)
+        .signers([owner])
+        .rpc();
+
+      for (const nft of nfts) {
+        const [userVaultPda] = getUserVaultPDA(owner.publicKey, nft.mint);
+        const vault = await program.account.userVault.fetch(userVaultPda);
+        assert.equal(vault.owner.toString(), owner.publicKey.toString());
+        assert.equal(vault.mint.toString(), nft.mint.toString());
+        const vaultAccount = await getAccount(provider.connection, vault.vaultAta);
+        assert.equal(vaultAccount.amount.toString(), "1");
+      }
+    });
+
+    it("vaults nothing when one NFT in the batch is invalid", async () => {
+      const owner = Keypair.generate();
+      await airdrop(owner.publicKey);
+      const valid = await createNFT(owner);
+      const mintable = await createNFT(owner, true); // mint authority still live
+
+      try {
+        await program.methods
+          .initializeUserVaultsBatch()
+          .accountsPartial({ owner: owner.publicKey })
+          .remainingAccounts([
+            ...(await vaultGroup(owner, valid)),
+            ...(await vaultGroup(owner, mintable)),
+          ])
+          .signers([owner])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnsupportedMint");
+      }
+
+      const [userVaultPda] = getUserVaultPDA(owner.publicKey, valid.mint);
+      assert.isNull(await provider.connection.getAccountInfo(userVaultPda));
+    });
+
+    it("hands a vault and its NFT to a new owner", async () => {
+      const owner = Keypair.generate();
+      const newOwner = Keypair.generate();
+      await airdrop(owner.publicKey);
+      await airdrop(newOwner.publicKey);
+
+      const nft = await createNFT(owner);
+      const [userVaultPda] = getUserVaultPDA(owner.publicKey, nft.mint);
+      const vaultAta = await getAssociatedTokenAddress(nft.mint, userVaultPda, true);
+      await program.methods
+        .initializeUserVault()
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          owner: owner.publicKey,
+          ownerTokenAccount: nft.tokenAccount,
+          vaultAta: vaultAta,
+          mint: nft.mint,
+        })
+        .signers([owner])
+        .rpc();
+
+      const [newVaultPda] = getUserVaultPDA(newOwner.publicKey, nft.mint);
+      const newVaultAta = await getAssociatedTokenAddress(nft.mint, newVaultPda, true);
+      await program.methods
+        .transferVaultOwnership()
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          listing: getListingPDA(owner.publicKey, nft.mint)[0],
+          newOwner: newOwner.publicKey,
+          newUserVault: newVaultPda,
+          newVaultPda: newVaultPda,
+          newVaultAta: newVaultAta,
+          mint: nft.mint,
+          owner: owner.publicKey,
+        })
+        .signers([owner])
+        .rpc();
+
+      // The old vault and its ATA are gone
+      assert.isNull(await provider.connection.getAccountInfo(userVaultPda));
+      assert.isNull(await provider.connection.getAccountInfo(vaultAta));
+
+      const vault = await program.account.userVault.fetch(newVaultPda);
+      assert.equal(vault.owner.toString(), newOwner.publicKey.toString());
+      assert.equal(vault.vaultAta.toString(), newVaultAta.toString());
+      assert.equal((await getAccount(provider.connection, newVaultAta)).amount.toString(), "1");
+
+      // ...and the new owner can take the NFT out
+      const newOwnerTokenAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        newOwner,
+        nft.mint,
+        newOwner.publicKey
+      );
+      await program.methods
+        .withdrawFromVault()
+        .accountsPartial({
+          userVault: newVaultPda,
+          vaultPda: newVaultPda,
+          vaultAta: newVaultAta,
+          owner: newOwner.publicKey,
+          ownerTokenAccount: newOwnerTokenAccount,
+          listing: getListingPDA(newOwner.publicKey, nft.mint)[0],
+        })
+        .signers([newOwner])
+        .rpc();
+      assert.equal(
+        (await getAccount(provider.connection, newOwnerTokenAccount)).amount.toString(),
+        "1"
+      );
+    });
+
+    it("refuses to hand over a vault with an active listing", async () => {
+      const owner = Keypair.generate();
+      const newOwner = Keypair.generate();
+      await airdrop(owner.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(owner);
+      const [newVaultPda] = getUserVaultPDA(newOwner.publicKey, mint);
+
+      try {
+        await program.methods
+          .transferVaultOwnership()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            listing: listingPda,
+            newOwner: newOwner.publicKey,
+            newUserVault: newVaultPda,
+            newVaultPda: newVaultPda,
+            newVaultAta: await getAssociatedTokenAddress(mint, newVaultPda, true),
+            mint: mint,
+            owner: owner.publicKey,
+          })
+          .signers([owner])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("VaultLocked");
+      }
+    });
+  });
+
+  // ============================================
+  // Listing Tests
+  // ============================================
+
+  describe("Listings", () => {
+    let seller: Keypair;
     let mint: PublicKey;
     let sellerTokenAccount: PublicKey;
     let userVaultPda: PublicKey;
@@ -248,7 +1130,19 @@ describe("Verity Escrowless NFT Marketplace", () => {
           DURATION,
           null, // min_floor
           null, // valid_from
-          null  // valid_until
+          null,  // valid_until
+          null,
+          "ipfs://verity-listing-note", // memo
+          null,
+          null,
+          null,
+          null,
+          false,
+          null,
+          null,
+          null,
+          null,
+          null
         )
         .accountsPartial({
           listing: listingPda,
@@ -263,13 +1157,41 @@ describe("Verity Escrowless NFT Marketplace", () => {
       const listing = await program.account.listing.fetch(listingPda);
       assert.equal(listing.seller.toString(), seller.publicKey.toString());
       assert.equal(listing.mint.toString(), mint.toString());
-      assert.equal(listing.state, 0); // STATE_ACTIVE
+      assert.deepEqual(listing.state, { active: {} });
       assert.equal(listing.priceConfig.startPrice.toString(), START_PRICE.toString());
+      assert.isTrue(listing.createdTs.toNumber() > 0);
+      assert.equal(listing.updatedTs.toString(), listing.createdTs.toString());
+      assert.equal(listing.memo, "ipfs://verity-listing-note");
+    });
+
+    it("reports the vault as locked while listed", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+
+      const sim = await program.methods
+        .checkVaultStatus()
+        .accountsPartial({ userVault: userVaultPda, listing: listingPda })
+        .simulate();
+      const status = sim.events.find((e) => e.name === "vaultStatus").data;
+      assert.isTrue(status.isLocked);
+      assert.equal(status.activeListing.toString(), listingPda.toString());
     });
 
     it("cancels listing (NFT stays in vault)", async () => {
       const [listingPda] = getListingPDA(seller.publicKey, mint);
 
+      const sim = await program.methods
+        .cancelListing()
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          seller: seller.publicKey,
+        })
+        .signers([seller])
+        .simulate();
+      const cancelled = sim.events.find((e) => e.name === "listingCancelled").data;
+      assert.equal(cancelled.listing.toString(), listingPda.toString());
+      assert.deepEqual(cancelled.reason, { seller: {} });
+
       await program.methods
         .cancelListing()
         .accountsPartial({
@@ -295,146 +1217,3854 @@ describe("Verity Escrowless NFT Marketplace", () => {
       assert.equal(vaultAccount.amount.toString(), "1");
     });
 
-    it("creates decay price listing", async () => {
+    it("rejects a metadata account not owned by Token Metadata", async () => {
       const [listingPda] = getListingPDA(seller.publicKey, mint);
       const now = Math.floor(Date.now() / 1000);
 
-      await program.methods
-        .createListing(
-          { linearDecay: {} }, // PriceType::LinearDecay
-          START_PRICE,
-          MIN_PRICE,
-          new BN(now),
-          DURATION,
-          null,
-          null,
-          null
-        )
-        .accountsPartial({
-          listing: listingPda,
-          userVault: userVaultPda,
-          vaultAta: vaultAta,
-          seller: seller.publicKey,
-          mint: mint,
-        })
-        .signers([seller])
-        .rpc();
+      try {
+        await program.methods
+          .createListing(
+            { fixed: {} },
+            START_PRICE,
+            MIN_PRICE,
+            new BN(now),
+            DURATION,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            false,
+            null,
+            null,
+            null,
+            null,
+            null
+          )
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            mint: mint,
+            metadata: mint, // Owned by the token program, not Token Metadata
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("AccountOwnedByWrongProgram");
+      }
+    });
+
+    it("rejects a TWAP floor on a listing without a verified collection", async () => {
+      const other = Keypair.generate();
+      await airdrop(other.publicKey);
+
+      // No metadata, so there is no collection average to check the floor against
+      try {
+        await setupListing(other, {
+          minFloor: START_PRICE.muln(10),
+          floorSource: { collectionTwap: {} },
+        });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("FloorNeedsCollection");
+      }
+    });
+
+    it("requires a floor_source with a min_floor, and the reverse", async () => {
+      const other = Keypair.generate();
+      await airdrop(other.publicKey);
+
+      for (const opts of [
+        { minFloor: START_PRICE.muln(10) },
+        { floorSource: { oracle: {} }, oracleFeed: Keypair.generate().publicKey },
+      ]) {
+        try {
+          await setupListing(other, opts);
+          assert.fail("Should have failed");
+        } catch (err) {
+          expect(err.toString()).to.include("MissingFloorSource");
+        }
+      }
+    });
+
+    it("requires an oracle feed for an oracle floor", async () => {
+      const other = Keypair.generate();
+      await airdrop(other.publicKey);
+
+      try {
+        await setupListing(other, { minFloor: START_PRICE.muln(10), floorSource: { oracle: {} } });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingOracle");
+      }
+    });
+
+    it("requires the oracle account when buying an oracle floor listing", async () => {
+      const other = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(other.publicKey);
+      await airdrop(buyer.publicKey);
+
+      // An oracle floor needs no collection
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(other, {
+        minFloor: START_PRICE.muln(10),
+        floorSource: { oracle: {} },
+        oracleFeed: Keypair.generate().publicKey,
+      });
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.deepEqual(listing.floorSource, { oracle: {} });
+
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: other.publicKey,
+            mint: mint,
+            config: configPda,
+            feeRecipient: config.feeRecipient,
+            oracle: null,
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingOracle");
+      }
+    });
+
+    it("rejects a zero min_floor", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      const now = Math.floor(Date.now() / 1000);
+
+      try {
+        await program.methods
+          .createListing(
+            { fixed: {} },
+            START_PRICE,
+            MIN_PRICE,
+            new BN(now),
+            DURATION,
+            new BN(0), // min_floor
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            false,
+            null,
+            null,
+            null,
+            null,
+            null
+          )
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            mint: mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFloor");
+      }
+    });
+
+    it("rejects a memo longer than 64 bytes", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      const now = Math.floor(Date.now() / 1000);
+
+      try {
+        await program.methods
+          .createListing(
+            { fixed: {} },
+            START_PRICE,
+            MIN_PRICE,
+            new BN(now),
+            DURATION,
+            null,
+            null,
+            null,
+            null,
+            "x".repeat(65), // memo
+            null,
+            null,
+            null,
+            null,
+            false,
+            null,
+            null,
+            null,
+            null,
+            null
+          )
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            mint: mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MemoTooLong");
+      }
+    });
+
+    it("rejects a decay listing whose window ends before decay starts", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      const now = Math.floor(Date.now() / 1000);
+
+      try {
+        await program.methods
+          .createListing(
+            { linearDecay: {} },
+            START_PRICE,
+            MIN_PRICE,
+            new BN(now + 600), // decay starts in 10 minutes
+            DURATION,
+            null,
+            new BN(now),
+            new BN(now + 300), // ...but the listing expires in 5
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            false,
+            null,
+            null,
+            null,
+            null,
+            null
+          )
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            mint: mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidTimeWindow");
+      }
+    });
+
+    it("rejects a decay listing that started well before now", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      const now = Math.floor(Date.now() / 1000);
+
+      try {
+        await program.methods
+          .createListing(
+            { linearDecay: {} },
+            START_PRICE,
+            MIN_PRICE,
+            new BN(now - 3600), // an hour ago, past the 5 minute grace
+            DURATION,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            false,
+            null,
+            null,
+            null,
+            null,
+            null
+          )
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            mint: mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidTimeWindow");
+      }
+    });
+
+    it("rejects decay durations outside the configured bounds", async () => {
+      const [configPda] = getConfigPDA();
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      const now = Math.floor(Date.now() / 1000);
+
+      // Require at least two hours of decay
+      await program.methods
+        .updateConfig(configUpdate({ minDuration: DURATION.muln(2) }))
+        .accountsPartial({ config: configPda, authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      try {
+        await program.methods
+          .createListing(
+            { linearDecay: {} },
+            START_PRICE,
+            MIN_PRICE,
+            new BN(now),
+            DURATION,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            false,
+            null,
+            null,
+            null,
+            null,
+            null
+          )
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            mint: mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidDuration");
+      } finally {
+        await program.methods
+          .updateConfig(configUpdate({ minDuration: new BN(1) }))
+          .accountsPartial({ config: configPda, authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+      }
+    });
+
+    it("creates decay price listing", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      const now = Math.floor(Date.now() / 1000);
+
+      await program.methods
+        .createListing(
+          { linearDecay: {} }, // PriceType::LinearDecay
+          START_PRICE,
+          MIN_PRICE,
+          new BN(now),
+          DURATION,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          false,
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultAta: vaultAta,
+          seller: seller.publicKey,
+          mint: mint,
+        })
+        .signers([seller])
+        .rpc();
+
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.deepEqual(listing.priceConfig.priceType, { linearDecay: {} });
+    });
+
+    it("previews the decay curve from start_price to min_price", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+
+      const sim = await program.methods
+        .previewSchedule(5)
+        .accountsPartial({ listing: listingPda })
+        .simulate();
+      const schedule = sim.events.find((e) => e.name === "priceSchedule").data;
+      const listing = await program.account.listing.fetch(listingPda);
+      const startTs = listing.priceConfig.startTs.toNumber();
+
+      assert.equal(schedule.points.length, 5);
+      assert.equal(schedule.points[0].timestamp.toNumber(), startTs);
+      assert.equal(schedule.points[0].price.toString(), START_PRICE.toString());
+      assert.equal(schedule.points[2].timestamp.toNumber(), startTs + DURATION.toNumber() / 2);
+      assert.equal(
+        schedule.points[2].price.toString(),
+        START_PRICE.add(MIN_PRICE).divn(2).toString()
+      );
+      assert.equal(schedule.points[4].price.toString(), MIN_PRICE.toString());
+    });
+
+    it("rejects a preview with too many samples", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+
+      try {
+        await program.methods
+          .previewSchedule(33)
+          .accountsPartial({ listing: listingPda })
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidSampleCount");
+      }
+    });
+
+    it("refuses to withdraw while the listing is active", async () => {
+      try {
+        await program.methods
+          .withdrawFromVault()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            owner: seller.publicKey,
+            ownerTokenAccount: sellerTokenAccount,
+            listing: getListingPDA(seller.publicKey, mint)[0],
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("VaultLocked");
+      }
+    });
+
+    it("deactivates and reactivates a listing without closing it", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+
+      await program.methods
+        .deactivateListing()
+        .accountsPartial({ listing: listingPda, seller: seller.publicKey })
+        .signers([seller])
+        .rpc();
+      let listing = await program.account.listing.fetch(listingPda);
+      assert.deepEqual(listing.state, { cancelled: {} });
+
+      const now = Math.floor(Date.now() / 1000);
+      const newPrice = START_PRICE.muln(2);
+      await program.methods
+        .reactivateListing({ fixed: {} }, newPrice, MIN_PRICE, new BN(now), DURATION)
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultAta: vaultAta,
+          seller: seller.publicKey,
+          mint,
+        })
+        .signers([seller])
+        .rpc();
+      listing = await program.account.listing.fetch(listingPda);
+      assert.deepEqual(listing.state, { active: {} });
+      assert.deepEqual(listing.priceConfig.priceType, { fixed: {} });
+      assert.equal(listing.priceConfig.startPrice.toString(), newPrice.toString());
+    });
+
+    it("refuses to shrink a listing", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      const info = await provider.connection.getAccountInfo(listingPda);
+
+      try {
+        await program.methods
+          .resizeListing(info.data.length - 1)
+          .accountsPartial({ listing: listingPda, seller: seller.publicKey })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidAccountSize");
+      }
+    });
+
+    it("extends a listing's time window", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      const now = Math.floor(Date.now() / 1000);
+
+      await program.methods
+        .updateListingWindow(null, new BN(now + 7200))
+        .accountsPartial({ listing: listingPda, seller: seller.publicKey })
+        .signers([seller])
+        .rpc();
+
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.equal(listing.conditions.validUntil.toNumber(), now + 7200);
+    });
+
+    it("rejects a window that would already have ended", async () => {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      const now = Math.floor(Date.now() / 1000);
+
+      try {
+        await program.methods
+          .updateListingWindow(null, new BN(now - 60))
+          .accountsPartial({ listing: listingPda, seller: seller.publicKey })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidTimeWindow");
+      }
+    });
+
+    it("reprices a listing given only the listing account", async () => {
+      const owner = Keypair.generate();
+      await airdrop(owner.publicKey);
+      const { listingPda } = await setupListing(owner);
+      const now = Math.floor(Date.now() / 1000);
+      const newPrice = START_PRICE.muln(3);
+
+      await program.methods
+        .updateListingPrice({ fixed: {} }, newPrice, MIN_PRICE, new BN(now), DURATION)
+        .accountsPartial({ listing: listingPda, seller: owner.publicKey })
+        .signers([owner])
+        .rpc();
+
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.equal(listing.priceConfig.startPrice.toString(), newPrice.toString());
+    });
+
+    it("rejects a reprice from someone other than the seller", async () => {
+      const owner = Keypair.generate();
+      const attacker = Keypair.generate();
+      await airdrop(owner.publicKey);
+      await airdrop(attacker.publicKey);
+      const { listingPda } = await setupListing(owner);
+      const now = Math.floor(Date.now() / 1000);
+
+      try {
+        await program.methods
+          .updateListingPrice({ fixed: {} }, MIN_PRICE, MIN_PRICE, new BN(now), DURATION)
+          .accountsPartial({ listing: listingPda, seller: attacker.publicKey })
+          .signers([attacker])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedSeller");
+      }
+    });
+  });
+
+  // ============================================
+  // Buy Flow Tests
+  // ============================================
+
+  describe("Buy NFT", () => {
+    let seller: Keypair;
+    let buyer: Keypair;
+    let feeRecipient: PublicKey;
+    let mint: PublicKey;
+    let sellerTokenAccount: PublicKey;
+    let configPda: PublicKey;
+    let userVaultPda: PublicKey;
+    let vaultAta: PublicKey;
+    let listingPda: PublicKey;
+
+    before(async () => {
+      seller = Keypair.generate();
+      buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const nft = await createNFT(seller);
+      mint = nft.mint;
+      sellerTokenAccount = nft.tokenAccount;
+
+      [configPda] = getConfigPDA();
+      [userVaultPda] = getUserVaultPDA(seller.publicKey, mint);
+      [listingPda] = getListingPDA(seller.publicKey, mint);
+      vaultAta = await getAssociatedTokenAddress(mint, userVaultPda, true);
+
+      // Get fee recipient from config
+      const config = await program.account.config.fetch(configPda);
+      feeRecipient = config.feeRecipient;
+
+      // Create vault
+      await program.methods
+        .initializeUserVault()
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          owner: seller.publicKey,
+          ownerTokenAccount: sellerTokenAccount,
+          vaultAta: vaultAta,
+          mint: mint,
+        })
+        .signers([seller])
+        .rpc();
+
+      // Create listing
+      const now = Math.floor(Date.now() / 1000);
+      await program.methods
+        .createListing(
+          { fixed: {} },
+          START_PRICE,
+          MIN_PRICE,
+          new BN(now),
+          DURATION,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          false,
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultAta: vaultAta,
+          seller: seller.publicKey,
+          mint: mint,
+        })
+        .signers([seller])
+        .rpc();
+    });
+
+    it("rejects the seller buying their own listing", async () => {
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: seller.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, seller.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            config: configPda,
+            buyerStats: getUserStatsPDA(seller.publicKey)[0],
+            sellerStats: getUserStatsPDA(seller.publicKey)[0],
+            feeRecipient: feeRecipient,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("SelfPurchase");
+      }
+    });
+
+    it("buys NFT and transfers to buyer", async () => {
+      const buyerAta = await getAssociatedTokenAddress(mint, buyer.publicKey);
+      const sellerBalanceBefore = await provider.connection.getBalance(seller.publicKey);
+      const [statsPda] = getStatsPDA();
+      const statsBefore = await program.account.stats.fetch(statsPda);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: buyerAta,
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          buyerStats: getUserStatsPDA(buyer.publicKey)[0],
+          sellerStats: getUserStatsPDA(seller.publicKey)[0],
+          feeRecipient: feeRecipient,
+        })
+        .signers([buyer])
+        .rpc();
+
+      // Verify buyer received NFT
+      const buyerAccount = await getAccount(provider.connection, buyerAta);
+      assert.equal(buyerAccount.amount.toString(), "1");
+
+      // Verify seller received payment
+      const sellerBalanceAfter = await provider.connection.getBalance(seller.publicKey);
+      assert.isTrue(sellerBalanceAfter > sellerBalanceBefore);
+
+      // Verify marketplace stats recorded the sale
+      const statsAfter = await program.account.stats.fetch(statsPda);
+      assert.equal(
+        statsAfter.totalSales.toString(),
+        statsBefore.totalSales.addn(1).toString()
+      );
+      assert.equal(
+        statsAfter.totalVolume.toString(),
+        statsBefore.totalVolume.add(START_PRICE).toString()
+      );
+      assert.equal(
+        statsAfter.activeListings.toString(),
+        statsBefore.activeListings.subn(1).toString()
+      );
+
+      // Verify per-user stats were created for both sides
+      const buyerStats = await program.account.userStats.fetch(
+        getUserStatsPDA(buyer.publicKey)[0]
+      );
+      assert.equal(buyerStats.user.toString(), buyer.publicKey.toString());
+      assert.equal(buyerStats.boughtCount.toString(), "1");
+      assert.equal(buyerStats.boughtVolume.toString(), START_PRICE.toString());
+      assert.equal(buyerStats.soldCount.toString(), "0");
+
+      const sellerStats = await program.account.userStats.fetch(
+        getUserStatsPDA(seller.publicKey)[0]
+      );
+      assert.equal(sellerStats.user.toString(), seller.publicKey.toString());
+      assert.equal(sellerStats.soldCount.toString(), "1");
+      assert.equal(sellerStats.soldVolume.toString(), START_PRICE.toString());
+      assert.equal(sellerStats.boughtCount.toString(), "0");
+
+      // Verify listing closed
+      try {
+        await program.account.listing.fetch(listingPda);
+        assert.fail("Listing should be closed");
+      } catch (err) {
+        expect(err.toString().toLowerCase()).to.satisfy(
+          (s: string) => s.includes("account does not exist") || s.includes("could not find")
+        );
+      }
+    });
+
+    it("closes the emptied vault after the sale", async () => {
+      const balanceBefore = await provider.connection.getBalance(seller.publicKey);
+
+      await program.methods
+        .closeUserVault()
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          listing: listingPda,
+          owner: seller.publicKey,
+        })
+        .signers([seller])
+        .rpc();
+
+      const balanceAfter = await provider.connection.getBalance(seller.publicKey);
+      assert.isTrue(balanceAfter > balanceBefore);
+      assert.isNull(await provider.connection.getAccountInfo(userVaultPda));
+      assert.isNull(await provider.connection.getAccountInfo(vaultAta));
+    });
+
+    it("rejects a buyer who can't cover the price", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      // Enough for rent and fees, well short of the 1 SOL price
+      await airdrop(buyer.publicKey, 0.1 * LAMPORTS_PER_SOL);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+        startPrice: new BN(LAMPORTS_PER_SOL),
+        minPrice: new BN(LAMPORTS_PER_SOL),
+      });
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            config: configPda,
+            feeRecipient: config.feeRecipient,
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InsufficientFunds");
+      }
+    });
+
+    it("pays a seller whose wallet was drained to zero", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+
+      // Empty the seller's wallet entirely (the provider pays the tx fee)
+      const balance = await provider.connection.getBalance(seller.publicKey);
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: seller.publicKey,
+            toPubkey: provider.wallet.publicKey,
+            lamports: balance,
+          })
+        ),
+        [seller]
+      );
+      assert.isNull(await provider.connection.getAccountInfo(seller.publicKey));
+
+      const listingRent = (await provider.connection.getAccountInfo(listingPda)).lamports;
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+        })
+        .signers([buyer])
+        .rpc();
+
+      // Proceeds + legacy royalty, plus the listing's rent
+      const fee = START_PRICE.muln(FEE_BPS).divn(10000);
+      const sellerBalance = await provider.connection.getBalance(seller.publicKey);
+      assert.equal(sellerBalance.toString(), START_PRICE.sub(fee).addn(listingRent).toString());
+    });
+
+    it("reuses a buyer ATA that already exists", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const buyerAta = await createAssociatedTokenAccount(
+        provider.connection,
+        buyer,
+        mint,
+        buyer.publicKey
+      );
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: buyerAta,
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+        })
+        .signers([buyer])
+        .rpc();
+
+      const buyerAccount = await getAccount(provider.connection, buyerAta);
+      assert.equal(buyerAccount.amount.toString(), "1");
+    });
+
+    it("rejects a pre-existing token account that isn't the buyer's ATA", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      // Same mint, but owned by someone else and not at the buyer's ATA address
+      const other = Keypair.generate();
+      const otherAccount = await createAccount(
+        provider.connection,
+        buyer,
+        mint,
+        other.publicKey,
+        Keypair.generate()
+      );
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: otherAccount,
+            seller: seller.publicKey,
+            mint: mint,
+            config: configPda,
+            feeRecipient: config.feeRecipient,
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        // Anchor's ATA constraints or require_token_account, whichever hits first
+        expect(err.toString()).to.match(/ConstraintToken|AccountNotAssociatedTokenAccount|InvalidTokenAccount/);
+      }
+
+      const vaultAccount = await getAccount(provider.connection, vaultAta);
+      assert.equal(vaultAccount.amount.toString(), "1");
+    });
+
+    it("delivers the NFT to a named recipient while the buyer pays", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      const friend = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      const friendAta = await getAssociatedTokenAddress(mint, friend.publicKey);
+
+      const builder = program.methods.buyNow(new BN(1)).accountsPartial({
+        listing: listingPda,
+        userVault: userVaultPda,
+        vaultPda: userVaultPda,
+        vaultAta: vaultAta,
+        buyer: buyer.publicKey,
+        buyerAta: friendAta,
+        seller: seller.publicKey,
+        mint: mint,
+        config: configPda,
+        feeRecipient: config.feeRecipient,
+        recipient: friend.publicKey,
+      });
+      const sim = await builder.signers([buyer]).simulate();
+      const sale = sim.events.find((e) => e.name === "sale").data;
+      assert.equal(sale.buyer.toString(), buyer.publicKey.toString());
+      assert.equal(sale.recipient.toString(), friend.publicKey.toString());
+
+      await builder.signers([buyer]).rpc();
+      const friendAccount = await getAccount(provider.connection, friendAta);
+      assert.equal(friendAccount.amount.toString(), "1");
+    });
+  });
+
+  // ============================================
+  // Batch Cancel Tests
+  // ============================================
+
+  describe("Batch Cancel", () => {
+    it("cancels several listings in one transaction", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const first = await setupListing(seller);
+      const second = await setupListing(seller);
+
+      const [statsPda] = getStatsPDA();
+      const statsBefore = await program.account.stats.fetch(statsPda);
+
+      await program.methods
+        .cancelListingsBatch()
+        .accountsPartial({ seller: seller.publicKey })
+        .remainingAccounts([
+          { pubkey: first.listingPda, isWritable: true, isSigner: false },
+          { pubkey: second.listingPda, isWritable: true, isSigner: false },
+        ])
+        .signers([seller])
+        .rpc();
+
+      assert.isNull(await provider.connection.getAccountInfo(first.listingPda));
+      assert.isNull(await provider.connection.getAccountInfo(second.listingPda));
+      const statsAfter = await program.account.stats.fetch(statsPda);
+      assert.equal(
+        statsBefore.activeListings.sub(statsAfter.activeListings).toNumber(),
+        2
+      );
+
+      // NFTs stay in their vaults
+      const vaultAccount = await getAccount(provider.connection, first.vaultAta);
+      assert.equal(vaultAccount.amount.toString(), "1");
+    });
+
+    it("rejects a batch containing someone else's listing", async () => {
+      const seller = Keypair.generate();
+      const other = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(other.publicKey);
+      const own = await setupListing(seller);
+      const foreign = await setupListing(other);
+
+      try {
+        await program.methods
+          .cancelListingsBatch()
+          .accountsPartial({ seller: seller.publicKey })
+          .remainingAccounts([
+            { pubkey: own.listingPda, isWritable: true, isSigner: false },
+            { pubkey: foreign.listingPda, isWritable: true, isSigner: false },
+          ])
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedSeller");
+      }
+
+      // Nothing was cancelled
+      assert.isNotNull(await provider.connection.getAccountInfo(own.listingPda));
+    });
+  });
+
+  // ============================================
+  // Semi-Fungible Listing Tests
+  // ============================================
+
+  describe("Semi-Fungible Listings", () => {
+    const UNITS = 5;
+    const seller = Keypair.generate();
+    const buyer = Keypair.generate();
+    let mint: PublicKey;
+    let userVaultPda: PublicKey;
+    let vaultAta: PublicKey;
+    let listingPda: PublicKey;
+
+    before(async () => {
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      // Fixed-supply edition drop: 5 units, mint authority disabled
+      mint = await createMint(provider.connection, seller, seller.publicKey, null, 0);
+      const tokenAccount = await createAccount(
+        provider.connection,
+        seller,
+        mint,
+        seller.publicKey
+      );
+      await mintTo(provider.connection, seller, mint, tokenAccount, seller, UNITS);
+      await setAuthority(
+        provider.connection,
+        seller,
+        mint,
+        seller,
+        AuthorityType.MintTokens,
+        null
+      );
+
+      [userVaultPda] = getUserVaultPDA(seller.publicKey, mint);
+      [listingPda] = getListingPDA(seller.publicKey, mint);
+      vaultAta = await getAssociatedTokenAddress(mint, userVaultPda, true);
+      await program.methods
+        .initializeUserVault()
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          owner: seller.publicKey,
+          ownerTokenAccount: tokenAccount,
+          vaultAta: vaultAta,
+          mint: mint,
+        })
+        .signers([seller])
+        .rpc();
+
+      const now = Math.floor(Date.now() / 1000);
+      await program.methods
+        .createListing(
+          { fixed: {} },
+          START_PRICE,
+          MIN_PRICE,
+          new BN(now),
+          DURATION,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          false,
+          new BN(UNITS),
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultAta: vaultAta,
+          seller: seller.publicKey,
+          mint: mint,
+        })
+        .signers([seller])
+        .rpc();
+    });
+
+    async function buy(quantity: number) {
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      await program.methods
+        .buyNow(new BN(quantity))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+        })
+        .signers([buyer])
+        .rpc();
+    }
+
+    it("rejects buying more units than are listed", async () => {
+      try {
+        await buy(UNITS + 1);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidQuantity");
+      }
+    });
+
+    it("sells part of the listing and keeps the rest up", async () => {
+      await buy(2);
+
+      const buyerToken = await getAccount(
+        provider.connection,
+        await getAssociatedTokenAddress(mint, buyer.publicKey)
+      );
+      assert.equal(buyerToken.amount.toString(), "2");
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.equal(listing.quantity.toString(), "3");
+      assert.deepEqual(listing.state, { active: {} });
+    });
+
+    it("closes the listing once the last units sell", async () => {
+      await buy(3);
+
+      assert.isNull(await provider.connection.getAccountInfo(listingPda));
+      const vaultAccount = await getAccount(provider.connection, vaultAta);
+      assert.equal(vaultAccount.amount.toString(), "0");
+    });
+  });
+
+  // ============================================
+  // Listing Expiry Tests
+  // ============================================
+
+  describe("Listing Expiry", () => {
+    const cranker = Keypair.generate();
+
+    before(async () => {
+      await airdrop(cranker.publicKey);
+    });
+
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ expiryGrace: new BN(120) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    async function expire(listingPda: PublicKey, seller: PublicKey) {
+      const config = await program.account.config.fetch(getConfigPDA()[0]);
+      await program.methods
+        .expireListing()
+        .accountsPartial({
+          listing: listingPda,
+          seller,
+          feeRecipient: config.feeRecipient,
+          cranker: cranker.publicKey,
+        })
+        .signers([cranker])
+        .rpc();
+    }
+
+    it("never expires a listing without valid_until", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const { listingPda } = await setupListing(seller);
+
+      try {
+        await expire(listingPda, seller.publicKey);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("ListingNotExpired");
+      }
+    });
+
+    it("waits out the expiry grace after valid_until", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const now = Math.floor(Date.now() / 1000);
+      const { listingPda } = await setupListing(seller, {
+        validUntil: new BN(now - 10),
+      });
+
+      // Still inside the default grace window
+      try {
+        await expire(listingPda, seller.publicKey);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("ListingNotExpired");
+      }
+
+      await program.methods
+        .updateConfig(configUpdate({ expiryGrace: new BN(0) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+      await expire(listingPda, seller.publicKey);
+      assert.isNull(await provider.connection.getAccountInfo(listingPda));
+    });
+  });
+
+  // ============================================
+  // Listing Invalidation Tests
+  // ============================================
+
+  describe("Listing Invalidation", () => {
+    it("lets anyone close a listing whose NFT left the vault", async () => {
+      const seller = Keypair.generate();
+      const cranker = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(cranker.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+
+      // Take the NFT out behind a deactivated listing
+      await program.methods
+        .deactivateListing()
+        .accountsPartial({ listing: listingPda, seller: seller.publicKey })
+        .signers([seller])
+        .rpc();
+      const sellerTokenAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        seller,
+        mint,
+        seller.publicKey
+      );
+      await program.methods
+        .withdrawFromVault()
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          owner: seller.publicKey,
+          ownerTokenAccount: sellerTokenAccount,
+          listing: listingPda,
+        })
+        .signers([seller])
+        .rpc();
+
+      const sellerBefore = await provider.connection.getBalance(seller.publicKey);
+      await program.methods
+        .invalidateListing()
+        .accountsPartial({
+          listing: listingPda,
+          vaultAta: vaultAta,
+          seller: seller.publicKey,
+          cranker: cranker.publicKey,
+        })
+        .signers([cranker])
+        .rpc();
+
+      assert.isNull(await provider.connection.getAccountInfo(listingPda));
+      const sellerAfter = await provider.connection.getBalance(seller.publicKey);
+      assert.isAbove(sellerAfter, sellerBefore);
+    });
+
+    it("refuses to invalidate a listing whose NFT is still vaulted", async () => {
+      const seller = Keypair.generate();
+      const cranker = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(cranker.publicKey);
+      const { vaultAta, listingPda } = await setupListing(seller);
+
+      try {
+        await program.methods
+          .invalidateListing()
+          .accountsPartial({
+            listing: listingPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            cranker: cranker.publicKey,
+          })
+          .signers([cranker])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("VaultNotEmpty");
+      }
+    });
+  });
+
+  // ============================================
+  // Listing Deposit Tests
+  // ============================================
+
+  describe("Listing Deposits", () => {
+    const DEPOSIT = new BN(0.05 * LAMPORTS_PER_SOL);
+
+    before(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ listingDeposit: DEPOSIT }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ listingDeposit: new BN(0), expiryGrace: new BN(120) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("locks the deposit in the listing and refunds it on cancel", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const { userVaultPda, listingPda } = await setupListing(seller);
+
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.equal(listing.deposit.toString(), DEPOSIT.toString());
+      const held = await provider.connection.getBalance(listingPda);
+
+      const before = await provider.connection.getBalance(seller.publicKey);
+      await program.methods
+        .cancelListing()
+        .accountsPartial({ listing: listingPda, userVault: userVaultPda, seller: seller.publicKey })
+        .signers([seller])
+        .rpc();
+      const after = await provider.connection.getBalance(seller.publicKey);
+      // Rent + deposit come back, minus the transaction fee
+      assert.isTrue(after - before > held - 10_000);
+    });
+
+    it("refunds the deposit to the seller on sale", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      const held = await provider.connection.getBalance(listingPda);
+
+      const before = await provider.connection.getBalance(seller.publicKey);
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+        })
+        .signers([buyer])
+        .rpc();
+      const after = await provider.connection.getBalance(seller.publicKey);
+
+      const fee = START_PRICE.muln(FEE_BPS).divn(10000);
+      assert.equal(after - before, START_PRICE.sub(fee).toNumber() + held);
+    });
+
+    it("forfeits the deposit to the fee recipient on expiry", async () => {
+      await program.methods
+        .updateConfig(configUpdate({ expiryGrace: new BN(0) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const seller = Keypair.generate();
+      const cranker = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(cranker.publicKey);
+      const now = Math.floor(Date.now() / 1000);
+      const { listingPda } = await setupListing(seller, { validUntil: new BN(now - 10) });
+      const config = await program.account.config.fetch(getConfigPDA()[0]);
+      const held = await provider.connection.getBalance(listingPda);
+
+      const feeBefore = await provider.connection.getBalance(config.feeRecipient);
+      const sellerBefore = await provider.connection.getBalance(seller.publicKey);
+      await program.methods
+        .expireListing()
+        .accountsPartial({
+          listing: listingPda,
+          seller: seller.publicKey,
+          feeRecipient: config.feeRecipient,
+          cranker: cranker.publicKey,
+        })
+        .signers([cranker])
+        .rpc();
+
+      const feeAfter = await provider.connection.getBalance(config.feeRecipient);
+      const sellerAfter = await provider.connection.getBalance(seller.publicKey);
+      assert.equal(feeAfter - feeBefore, DEPOSIT.toNumber());
+      assert.equal(sellerAfter - sellerBefore, held - DEPOSIT.toNumber());
+    });
+  });
+
+  // ============================================
+  // Relist Cooldown Tests
+  // ============================================
+
+  describe("Relist Cooldown", () => {
+    const COOLDOWN = 2;
+
+    // List the vault again right after cancelling (the vault already exists)
+    async function relist(seller: Keypair, mint: PublicKey, userVaultPda: PublicKey, vaultAta: PublicKey) {
+      const [listingPda] = getListingPDA(seller.publicKey, mint);
+      await program.methods
+        .createListing(
+          { fixed: {} } as any,
+          START_PRICE,
+          MIN_PRICE,
+          new BN(Math.floor(Date.now() / 1000)),
+          DURATION,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          null,
+          false,
+          null,
+          null,
+          null,
+          null,
+          null
+        )
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultAta: vaultAta,
+          seller: seller.publicKey,
+          mint: mint,
+        })
+        .signers([seller])
+        .rpc();
+    }
+
+    async function cancel(seller: Keypair, listingPda: PublicKey, userVaultPda: PublicKey) {
+      await program.methods
+        .cancelListing()
+        .accountsPartial({ listing: listingPda, userVault: userVaultPda, seller: seller.publicKey })
+        .signers([seller])
+        .rpc();
+    }
+
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ relistCooldown: new BN(0) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("allows an immediate relist while the cooldown is zero", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+
+      await cancel(seller, listingPda, userVaultPda);
+      await relist(seller, mint, userVaultPda, vaultAta);
+
+      const vault = await program.account.userVault.fetch(userVaultPda);
+      assert.isTrue(vault.lastListedTs.toNumber() > 0);
+    });
+
+    it("rejects a relist within the cooldown and accepts it once it has passed", async () => {
+      await program.methods
+        .updateConfig(configUpdate({ relistCooldown: new BN(COOLDOWN) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+
+      // Cancelling is never throttled
+      await cancel(seller, listingPda, userVaultPda);
+      try {
+        await relist(seller, mint, userVaultPda, vaultAta);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("RelistCooldown");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, (COOLDOWN + 1) * 1000));
+      await relist(seller, mint, userVaultPda, vaultAta);
+
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.deepEqual(listing.state, { active: {} });
+    });
+
+    it("applies the cooldown to reactivating a listing", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+
+      await program.methods
+        .deactivateListing()
+        .accountsPartial({ listing: listingPda, seller: seller.publicKey })
+        .signers([seller])
+        .rpc();
+      try {
+        await program.methods
+          .reactivateListing({ fixed: {} }, START_PRICE, MIN_PRICE, new BN(Math.floor(Date.now() / 1000)), DURATION)
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("RelistCooldown");
+      }
+    });
+
+    it("keeps the vault open until the cooldown has passed", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      await cancel(seller, listingPda, userVaultPda);
+      const ownerTokenAccount = await getAssociatedTokenAddress(mint, seller.publicKey);
+
+      // Closing the vault would drop last_listed_ts, letting a fresh
+      // deposit skip the cooldown
+      const withdraw = () =>
+        program.methods
+          .withdrawFromVault()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            owner: seller.publicKey,
+            ownerTokenAccount,
+            listing: listingPda,
+          })
+          .signers([seller])
+          .rpc();
+      try {
+        await withdraw();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("RelistCooldown");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, (COOLDOWN + 1) * 1000));
+      await withdraw();
+      assert.isNull(await provider.connection.getAccountInfo(userVaultPda));
+    });
+
+    it("rejects a negative cooldown", async () => {
+      try {
+        await program.methods
+          .updateConfig(configUpdate({ relistCooldown: new BN(-1) }))
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidTimeWindow");
+      }
+    });
+  });
+
+  // ============================================
+  // Freezable Mint Tests
+  // ============================================
+
+  describe("Freezable Mints", () => {
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ rejectFreezableMints: false }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("flags a listing whose mint can still be frozen", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      const { listingPda } = await setupListing(seller, { freezable: true });
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.isTrue(listing.freezable);
+    });
+
+    it("doesn't flag a mint without a freeze authority", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      const { listingPda } = await setupListing(seller);
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.isFalse(listing.freezable);
+    });
+
+    it("rejects freezable mints when the authority requires it", async () => {
+      await program.methods
+        .updateConfig(configUpdate({ rejectFreezableMints: true }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { freezable: true });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnsupportedMint");
+      }
+
+      // Mints without a freeze authority are still accepted
+      await setupListing(seller);
+    });
+
+    it("rechecks the mint when a listing is reactivated", async () => {
+      const setReject = (rejectFreezableMints: boolean) =>
+        program.methods
+          .updateConfig(configUpdate({ rejectFreezableMints }))
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+
+      await setReject(false);
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, { freezable: true });
+
+      await program.methods
+        .deactivateListing()
+        .accountsPartial({ listing: listingPda, seller: seller.publicKey })
+        .signers([seller])
+        .rpc();
+      await setReject(true);
+
+      try {
+        await program.methods
+          .reactivateListing({ fixed: {} }, START_PRICE, MIN_PRICE, new BN(Math.floor(Date.now() / 1000)), DURATION)
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnsupportedMint");
+      }
+    });
+  });
+
+  // ============================================
+  // Listing Boost Tests
+  // ============================================
+
+  describe("Listing Boost", () => {
+    const BOOST = new BN(0.1 * LAMPORTS_PER_SOL);
+
+    it("pays the boost to the fee recipient and records it", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const { feeRecipient } = await program.account.config.fetch(getConfigPDA()[0]);
+
+      const before = await provider.connection.getBalance(feeRecipient);
+      const { listingPda } = await setupListing(seller, { boostLamports: BOOST, feeRecipient });
+      const after = await provider.connection.getBalance(feeRecipient);
+      assert.equal(after - before, BOOST.toNumber());
+
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.equal(listing.boostScore.toString(), BOOST.toString());
+    });
+
+    it("rejects a boost without the fee recipient", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { boostLamports: BOOST });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingFeeRecipient");
+      }
+    });
+  });
+
+  // ============================================
+  // Minimum Listing Price Tests
+  // ============================================
+
+  describe("Minimum Listing Price", () => {
+    before(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ minListingPrice: MIN_PRICE }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ minListingPrice: new BN(0) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("accepts prices exactly at the minimum", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      const { listingPda } = await setupListing(seller, {
+        startPrice: MIN_PRICE,
+        minPrice: MIN_PRICE,
+      });
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.equal(listing.priceConfig.startPrice.toString(), MIN_PRICE.toString());
+    });
+
+    it("rejects a min price one lamport below the minimum", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { minPrice: MIN_PRICE.subn(1) });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidPrice");
+      }
+    });
+
+    it("rejects a start price one lamport below the minimum", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, {
+          startPrice: MIN_PRICE.subn(1),
+          minPrice: MIN_PRICE.subn(1),
+        });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidPrice");
+      }
+    });
+  });
+
+  // ============================================
+  // Active Listing Cap Tests
+  // ============================================
+
+  describe("Active Listing Cap", () => {
+    function getSellerStatePDA(seller: PublicKey): [PublicKey, number] {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("seller_state"), seller.toBuffer()],
+        PROGRAM_ID
+      );
+    }
+
+    before(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ maxActiveListings: 1 }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ maxActiveListings: 0 }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("rejects a listing over the cap and frees the slot on cancel", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const [sellerStatePda] = getSellerStatePDA(seller.publicKey);
+
+      const { userVaultPda, listingPda } = await setupListing(seller);
+      let sellerState = await program.account.sellerState.fetch(sellerStatePda);
+      assert.equal(sellerState.activeListings, 1);
+
+      try {
+        await setupListing(seller);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("TooManyActiveListings");
+      }
+
+      await program.methods
+        .cancelListing()
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          seller: seller.publicKey,
+          sellerState: sellerStatePda,
+        })
+        .signers([seller])
+        .rpc();
+      sellerState = await program.account.sellerState.fetch(sellerStatePda);
+      assert.equal(sellerState.activeListings, 0);
+
+      await setupListing(seller);
+      sellerState = await program.account.sellerState.fetch(sellerStatePda);
+      assert.equal(sellerState.activeListings, 1);
+    });
+
+    it("frees the slot when the listing sells out", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+      const [sellerStatePda] = getSellerStatePDA(seller.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+          sellerState: sellerStatePda,
+        })
+        .signers([buyer])
+        .rpc();
+
+      const sellerState = await program.account.sellerState.fetch(sellerStatePda);
+      assert.equal(sellerState.activeListings, 0);
+    });
+  });
+
+  // ============================================
+  // Fee Splitting Tests
+  // ============================================
+
+  describe("Fee Splitting", () => {
+    const treasuryA = Keypair.generate();
+    const treasuryB = Keypair.generate();
+
+    after(async () => {
+      // Restore single-recipient fees for the remaining suites
+      await program.methods
+        .setFeeRecipients([])
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("rejects shares that don't sum to 10000 bps", async () => {
+      try {
+        await program.methods
+          .setFeeRecipients([
+            { recipient: treasuryA.publicKey, shareBps: 5000 },
+            { recipient: treasuryB.publicKey, shareBps: 4000 },
+          ])
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFeeRecipients");
+      }
+    });
+
+    it("rejects non-authority updates", async () => {
+      const attacker = Keypair.generate();
+      try {
+        await program.methods
+          .setFeeRecipients([{ recipient: attacker.publicKey, shareBps: 10000 }])
+          .accountsPartial({ authority: attacker.publicKey })
+          .signers([attacker])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedAuthority");
+      }
+    });
+
+    it("splits the marketplace fee across recipients", async () => {
+      await program.methods
+        .setFeeRecipients([
+          { recipient: treasuryA.publicKey, shareBps: 7000 },
+          { recipient: treasuryB.publicKey, shareBps: 3000 },
+        ])
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+        })
+        .remainingAccounts([
+          { pubkey: treasuryA.publicKey, isWritable: true, isSigner: false },
+          { pubkey: treasuryB.publicKey, isWritable: true, isSigner: false },
+        ])
+        .signers([buyer])
+        .rpc();
+
+      const fee = START_PRICE.muln(FEE_BPS).divn(10000);
+      const shareA = fee.muln(7000).divn(10000);
+      const balanceA = await provider.connection.getBalance(treasuryA.publicKey);
+      const balanceB = await provider.connection.getBalance(treasuryB.publicKey);
+      assert.equal(balanceA.toString(), shareA.toString());
+      assert.equal(balanceB.toString(), fee.sub(shareA).toString());
+    });
+
+    it("folds a seller's own fee share into the seller transfer", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      await program.methods
+        .setFeeRecipients([
+          { recipient: seller.publicKey, shareBps: 5000 },
+          { recipient: treasuryA.publicKey, shareBps: 5000 },
+        ])
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      const listingRent = (await provider.connection.getAccountInfo(listingPda)).lamports;
+      const sellerBefore = await provider.connection.getBalance(seller.publicKey);
+      const treasuryBefore = await provider.connection.getBalance(treasuryA.publicKey);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+        })
+        .remainingAccounts([
+          { pubkey: seller.publicKey, isWritable: true, isSigner: false },
+          { pubkey: treasuryA.publicKey, isWritable: true, isSigner: false },
+        ])
+        .signers([buyer])
+        .rpc();
+
+      // Proceeds + legacy royalty + half the fee, plus the listing's rent
+      const fee = START_PRICE.muln(FEE_BPS).divn(10000);
+      const sellerShare = fee.muln(5000).divn(10000);
+      const sellerAfter = await provider.connection.getBalance(seller.publicKey);
+      const treasuryAfter = await provider.connection.getBalance(treasuryA.publicKey);
+      assert.equal(
+        (sellerAfter - sellerBefore).toString(),
+        START_PRICE.sub(fee).add(sellerShare).addn(listingRent).toString()
+      );
+      assert.equal(
+        (treasuryAfter - treasuryBefore).toString(),
+        fee.sub(sellerShare).toString()
+      );
+    });
+  });
+
+  // ============================================
+  // Creator Royalty Tests
+  // ============================================
+
+  describe("Creator Royalties", () => {
+    before(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ royaltyDefault: { enforce: {} } }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .updateConfig(
+          configUpdate({
+            royaltyDefault: { skip: {} },
+            allowRoyaltyOptOut: false,
+            minRoyaltyBps: 0,
+            redistributeUnverifiedRoyalty: false,
+            escrowRoyalties: false,
+          })
+        )
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("lets the authority redistribute unverified creators' royalty", async () => {
+      const [configPda] = getConfigPDA();
+      await program.methods
+        .updateConfig(configUpdate({ redistributeUnverifiedRoyalty: true }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const config = await program.account.config.fetch(configPda);
+      assert.isTrue(config.redistributeUnverifiedRoyalty);
+    });
+
+    it("lets the authority escrow creator royalties", async () => {
+      const [configPda] = getConfigPDA();
+      await program.methods
+        .updateConfig(configUpdate({ escrowRoyalties: true }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const config = await program.account.config.fetch(configPda);
+      assert.isTrue(config.escrowRoyalties);
+    });
+
+    it("rejects a royalty claim without an escrow", async () => {
+      const creator = Keypair.generate();
+      await airdrop(creator.publicKey);
+      const [escrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("royalty_escrow"), creator.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .claimRoyalty()
+          .accountsPartial({ royaltyEscrow: escrowPda, creator: creator.publicKey })
+          .signers([creator])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("AccountNotInitialized");
+      }
+    });
+
+    it("rejects a royalty opt-out while the authority disallows it", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { enforceRoyalties: false });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("RoyaltyOptOutDisabled");
+      }
+    });
+
+    it("charges no royalty on a listing that opted out", async () => {
+      await program.methods
+        .updateConfig(configUpdate({ allowRoyaltyOptOut: true }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+        enforceRoyalties: false,
+      });
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      const sim = await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+        })
+        .signers([buyer])
+        .simulate();
+      const sale = sim.events.find((e) => e.name === "sale").data;
+      assert.equal(sale.royalty.toString(), "0");
+    });
+
+    it("rejects a royalty floor above the royalty cap", async () => {
+      try {
+        await program.methods
+          .updateConfig(configUpdate({ minRoyaltyBps: 1500 }))
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidPrice");
+      }
+    });
+
+    it("requires the metadata when royalties are enforced", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      // Without metadata the royalty can't reach the creators, so there is
+      // no sale (no flat royalty folded into the seller's payment)
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            config: configPda,
+            feeRecipient: config.feeRecipient,
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingMetadata");
+      }
+    });
+
+    it("rejects another NFT's metadata", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const other = await createNFT(seller, false, null, {});
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      // The metadata decides the royalty, so it must be the listed mint's
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            config: configPda,
+            feeRecipient: config.feeRecipient,
+            metadata: getMetadataPDA(other.mint),
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidMetadata");
+      }
+    });
+
+    it("rejects a royalty override from a seller who isn't a verified creator", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { royaltyOverride: seller.publicKey });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedRoyaltyOverride");
+      }
+    });
+
+    it("lets the authority enforce royalties on a collection", async () => {
+      const collection = Keypair.generate().publicKey;
+      const [policyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("royalty_policy"), collection.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .setRoyaltyPolicy(true)
+        .accountsPartial({ collection, authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const policy = await program.account.royaltyPolicy.fetch(policyPda);
+      assert.equal(policy.collection.toString(), collection.toString());
+      assert.isTrue(policy.enforce);
+    });
+
+    it("lets the authority switch a collection's royalties off", async () => {
+      const collection = Keypair.generate().publicKey;
+      const [policyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("royalty_policy"), collection.toBuffer()],
+        program.programId
+      );
+
+      for (const enforce of [true, false]) {
+        await program.methods
+          .setRoyaltyPolicy(enforce)
+          .accountsPartial({ collection, authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+      }
+
+      const policy = await program.account.royaltyPolicy.fetch(policyPda);
+      assert.isFalse(policy.enforce);
+    });
+
+    it("blocks royalty policy changes during an emergency", async () => {
+      const now = Math.floor(Date.now() / 1000);
+      await program.methods
+        .updateConfig(configUpdate({ emergencyUntil: new BN(now + 3600) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      try {
+        await program.methods
+          .setRoyaltyPolicy(false)
+          .accountsPartial({ collection: Keypair.generate().publicKey, authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("EmergencyActive");
+      } finally {
+        await program.methods
+          .updateConfig(configUpdate({ emergencyUntil: new BN(0) }))
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+      }
+    });
+
+    it("rejects a royalty policy from a signer who is neither authority nor creator", async () => {
+      const stranger = Keypair.generate();
+      await airdrop(stranger.publicKey);
+
+      try {
+        await program.methods
+          .setRoyaltyPolicy(false)
+          .accountsPartial({ collection: Keypair.generate().publicKey, authority: stranger.publicKey })
+          .signers([stranger])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedRoyaltyPolicy");
+      }
+    });
+
+    it("rejects a metadata account not owned by Token Metadata", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            config: configPda,
+            feeRecipient: config.feeRecipient,
+            metadata: mint, // Owned by the token program, not Token Metadata
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("AccountOwnedByWrongProgram");
+      }
+    });
+  });
+
+  // ============================================
+  // Collection Royalty Policy Tests
+  // ============================================
+
+  describe("Collection Royalty Policy", () => {
+    const collectionAuthority = Keypair.generate();
+    const creator = Keypair.generate();
+    const SELLER_FEE_BPS = 500;
+
+    before(async () => {
+      await airdrop(collectionAuthority.publicKey);
+      await airdrop(creator.publicKey);
+    });
+
+    // Vault and list a resold NFT of a fresh collection whose policy is
+    // `enforce`, and return a buy_now for it (leaving out the policy PDA or
+    // the metadata when `omit` says so)
+    async function buyFromCollection(enforce: boolean, omit: "policy" | "metadata" | null = null) {
+      const collection = await createCollection(collectionAuthority);
+      await program.methods
+        .setRoyaltyPolicy(enforce)
+        .accountsPartial({ collection, authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+        metadata: {
+          sellerFeeBps: SELLER_FEE_BPS,
+          creators: [{ address: creator.publicKey, share: 100 }],
+          collection: { mint: collection, authority: collectionAuthority },
+          primarySaleHappened: true,
+        },
+      });
+      const listing = await program.account.listing.fetch(listingPda);
+      assert.equal(listing.collection.toString(), collection.toString());
+
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      const [royaltyPolicy] = PublicKey.findProgramAddressSync(
+        [Buffer.from("royalty_policy"), collection.toBuffer()],
+        program.programId
+      );
+      const [collectionTwap] = PublicKey.findProgramAddressSync(
+        [Buffer.from("collection_twap"), collection.toBuffer()],
+        program.programId
+      );
+      return program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+          metadata: omit === "metadata" ? null : getMetadataPDA(mint),
+          royaltyPolicy: omit === "policy" ? null : royaltyPolicy,
+          collectionTwap,
+        })
+        .remainingAccounts([{ pubkey: creator.publicKey, isSigner: false, isWritable: true }])
+        .signers([buyer]);
+    }
+
+    it("pays the creators when the collection enforces royalties", async () => {
+      // The marketplace default (Skip) is overridden by the policy
+      const sim = await (await buyFromCollection(true)).simulate();
+
+      const sale = sim.events.find((e) => e.name === "sale").data;
+      const expected = START_PRICE.muln(SELLER_FEE_BPS).divn(10_000);
+      assert.isFalse(sale.primarySale);
+      assert.equal(sale.royalty.toString(), expected.toString());
+
+      const royaltyPaid = sim.events.find((e) => e.name === "royaltyPaid").data;
+      assert.equal(royaltyPaid.payments.length, 1);
+      assert.equal(royaltyPaid.payments[0].creator.toString(), creator.publicKey.toString());
+      assert.equal(royaltyPaid.payments[0].amount.toString(), expected.toString());
+    });
+
+    it("pays no royalty when the collection switches them off", async () => {
+      await program.methods
+        .updateConfig(configUpdate({ royaltyDefault: { enforce: {} } }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      try {
+        // The marketplace default (Enforce) is overridden by the policy
+        const sim = await (await buyFromCollection(false)).simulate();
+
+        const sale = sim.events.find((e) => e.name === "sale").data;
+        assert.isFalse(sale.primarySale);
+        assert.equal(sale.royalty.toString(), "0");
+      } finally {
+        await program.methods
+          .updateConfig(configUpdate({ royaltyDefault: { skip: {} } }))
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+      }
+    });
+
+    it("rejects a purchase that leaves out the collection's policy", async () => {
+      const buy = await buyFromCollection(true, "policy");
+
+      try {
+        await buy.rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingRoyaltyPolicy");
+      }
+    });
+
+    it("requires the metadata when the collection enforces royalties", async () => {
+      // The listing recorded the collection, so its policy applies even
+      // though the buyer leaves the metadata out
+      const buy = await buyFromCollection(true, "metadata");
+
+      try {
+        await buy.rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingMetadata");
+      }
+    });
+  });
+
+  // ============================================
+  // Fee Exemption Tests
+  // ============================================
+
+  describe("Fee Exemptions", () => {
+    const partner = Keypair.generate();
+
+    before(async () => {
+      await airdrop(partner.publicKey);
+    });
+
+    it("rejects grants from non-authority", async () => {
+      const attacker = Keypair.generate();
+      await airdrop(attacker.publicKey);
+      const [exemptionPda] = getFeeExemptionPDA(attacker.publicKey);
+
+      try {
+        await program.methods
+          .grantFeeExemption()
+          .accountsPartial({
+            feeExemption: exemptionPda,
+            seller: attacker.publicKey,
+            authority: attacker.publicKey,
+          })
+          .signers([attacker])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedAuthority");
+      }
+    });
+
+    it("waives the marketplace fee for an exempt seller", async () => {
+      const [exemptionPda] = getFeeExemptionPDA(partner.publicKey);
+      await program.methods
+        .grantFeeExemption()
+        .accountsPartial({
+          feeExemption: exemptionPda,
+          seller: partner.publicKey,
+          authority: configAuthority.publicKey,
+        })
+        .signers([configAuthority])
+        .rpc();
+
+      const buyer = Keypair.generate();
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(partner);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      const feeBefore = await provider.connection.getBalance(config.feeRecipient);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: partner.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+          feeExemption: exemptionPda,
+        })
+        .signers([buyer])
+        .rpc();
+
+      const feeAfter = await provider.connection.getBalance(config.feeRecipient);
+      assert.equal(feeAfter, feeBefore);
+    });
+
+    it("lets the authority revoke an exemption", async () => {
+      const [exemptionPda] = getFeeExemptionPDA(partner.publicKey);
+      await program.methods
+        .revokeFeeExemption()
+        .accountsPartial({
+          feeExemption: exemptionPda,
+          authority: configAuthority.publicKey,
+        })
+        .signers([configAuthority])
+        .rpc();
+
+      const info = await provider.connection.getAccountInfo(exemptionPda);
+      assert.isNull(info);
+    });
+  });
+
+  // ============================================
+  // Governance Fee Discount Tests
+  // ============================================
+
+  describe("Governance Fee Discount", () => {
+    const DISCOUNT_BPS = 100;
+    const THRESHOLD = 1_000;
+    let discountMint: PublicKey;
+
+    before(async () => {
+      discountMint = await createMint(
+        provider.connection,
+        configAuthority,
+        configAuthority.publicKey,
+        null,
+        0
+      );
+      await program.methods
+        .updateConfig(
+          configUpdate({
+            discountMint,
+            discountThreshold: new BN(THRESHOLD),
+            discountBps: DISCOUNT_BPS,
+          })
+        )
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .updateConfig(
+          configUpdate({ discountMint: PublicKey.default, discountThreshold: new BN(0), discountBps: 0 })
+        )
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("rejects a zero threshold while the discount is enabled", async () => {
+      try {
+        await program.methods
+          .updateConfig(configUpdate({ discountThreshold: new BN(0) }))
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidDiscountThreshold");
+      }
+    });
+
+    // Simulate a purchase by a buyer holding `balance` governance tokens
+    async function buyHolding(balance: number, tokenOwner?: Keypair) {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+      const holder = tokenOwner ?? buyer;
+      if (tokenOwner) {
+        await airdrop(tokenOwner.publicKey);
+      }
+
+      const discountTokenAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        holder,
+        discountMint,
+        holder.publicKey
+      );
+      if (balance > 0) {
+        await mintTo(
+          provider.connection,
+          configAuthority,
+          discountMint,
+          discountTokenAccount,
+          configAuthority,
+          balance
+        );
+      }
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      const sim = await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+          discountTokenAccount,
+        })
+        .signers([buyer])
+        .simulate();
+      return { sale: sim.events.find((e) => e.name === "sale").data, config };
+    }
+
+    it("discounts the fee for a buyer at the threshold", async () => {
+      const { sale, config } = await buyHolding(THRESHOLD);
+
+      assert.isTrue(sale.feeDiscounted);
+      assert.equal(sale.feeBps, config.feeBps - DISCOUNT_BPS);
+      assert.equal(
+        sale.marketplaceFee.toString(),
+        START_PRICE.muln(config.feeBps - DISCOUNT_BPS).divn(10000).toString()
+      );
+    });
+
+    it("charges the full fee below the threshold", async () => {
+      const { sale, config } = await buyHolding(THRESHOLD - 1);
+
+      assert.isFalse(sale.feeDiscounted);
+      assert.equal(sale.feeBps, config.feeBps);
+    });
+
+    it("rejects a discount token account the buyer doesn't own", async () => {
+      try {
+        await buyHolding(THRESHOLD, Keypair.generate());
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidDiscountAccount");
+      }
+    });
+  });
+
+  // ============================================
+  // Fee Recipient Rotation Tests
+  // ============================================
+
+  describe("Fee Recipient Rotation", () => {
+    let originalRecipient: PublicKey;
+
+    before(async () => {
+      const config = await program.account.config.fetch(getConfigPDA()[0]);
+      originalRecipient = config.feeRecipient;
+    });
+
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ feeRecipient: originalRecipient }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    async function buyWithRecipient(feeRecipient: PublicKey) {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: getConfigPDA()[0],
+          feeRecipient,
+        })
+        .signers([buyer])
+        .rpc();
+    }
+
+    it("accepts both the new and the previous recipient during the grace window", async () => {
+      const rotated = Keypair.generate();
+      await airdrop(rotated.publicKey);
+      await program.methods
+        .updateConfig(configUpdate({ feeRecipient: rotated.publicKey }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const config = await program.account.config.fetch(getConfigPDA()[0]);
+      assert.equal(config.feeRecipient.toString(), rotated.publicKey.toString());
+      assert.equal(config.previousFeeRecipient.toString(), originalRecipient.toString());
+
+      await buyWithRecipient(rotated.publicKey);
+      await buyWithRecipient(originalRecipient);
+    });
+
+    it("rejects a recipient that is no longer current or previous", async () => {
+      // A second rotation retires the original recipient entirely
+      const next = Keypair.generate();
+      await airdrop(next.publicKey);
+      await program.methods
+        .updateConfig(configUpdate({ feeRecipient: next.publicKey }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      try {
+        await buyWithRecipient(originalRecipient);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("ConstraintRaw");
+      }
+    });
+  });
+
+  // ============================================
+  // Listing Fee Override Tests
+  // ============================================
+
+  describe("Listing Fee Overrides", () => {
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ maxFeeDiscountBps: 0 }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("rejects a discounted fee when no discount is allowed", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { overrideFeeBps: FEE_BPS - 100 });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFeeOverride");
+      }
+    });
+
+    it("rejects a fee above the marketplace fee", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { overrideFeeBps: FEE_BPS + 100 });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidFeeOverride");
+      }
+    });
+
+    it("charges the listing's discounted fee", async () => {
+      await program.methods
+        .updateConfig(configUpdate({ maxFeeDiscountBps: 100 }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const overrideFeeBps = FEE_BPS - 100;
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+        overrideFeeBps,
+      });
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      const sim = await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+        })
+        .signers([buyer])
+        .simulate();
+      const sale = sim.events.find((e) => e.name === "sale").data;
+      assert.equal(sale.feeBps, overrideFeeBps);
+      assert.equal(
+        sale.marketplaceFee.toString(),
+        START_PRICE.muln(overrideFeeBps).divn(10000).toString()
+      );
+    });
+  });
+
+  // ============================================
+  // Community Share Tests
+  // ============================================
+
+  describe("Community Share", () => {
+    const COMMUNITY_BPS = 100; // 1%
+    const treasury = Keypair.generate();
+
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ communityBps: 0 }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    it("rejects a community share without a recipient", async () => {
+      try {
+        await program.methods
+          .updateConfig(configUpdate({ communityBps: COMMUNITY_BPS }))
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidCommunityRecipient");
+      }
+    });
+
+    it("routes the community share of a sale to the treasury", async () => {
+      await airdrop(treasury.publicKey);
+      await program.methods
+        .updateConfig(
+          configUpdate({
+            communityBps: COMMUNITY_BPS,
+            communityRecipient: treasury.publicKey,
+          })
+        )
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      const treasuryBefore = await provider.connection.getBalance(treasury.publicKey);
+
+      const builder = program.methods.buyNow(new BN(1)).accountsPartial({
+        listing: listingPda,
+        userVault: userVaultPda,
+        vaultPda: userVaultPda,
+        vaultAta: vaultAta,
+        buyer: buyer.publicKey,
+        buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+        seller: seller.publicKey,
+        mint: mint,
+        config: configPda,
+        feeRecipient: config.feeRecipient,
+        communityRecipient: treasury.publicKey,
+      });
+      const expected = START_PRICE.muln(COMMUNITY_BPS).divn(10000);
+      const sim = await builder.signers([buyer]).simulate();
+      const sale = sim.events.find((e) => e.name === "sale").data;
+      assert.equal(sale.communityAmount.toString(), expected.toString());
+
+      await builder.signers([buyer]).rpc();
+      const treasuryAfter = await provider.connection.getBalance(treasury.publicKey);
+      assert.equal((treasuryAfter - treasuryBefore).toString(), expected.toString());
+    });
+  });
+
+  // ============================================
+  // Escrowed Proceeds Tests
+  // ============================================
+
+  describe("Escrowed Proceeds", () => {
+    const seller = Keypair.generate();
+    const [proceedsPda] = getProceedsPDA(seller.publicKey);
+
+    before(async () => {
+      await airdrop(seller.publicKey);
+      await program.methods
+        .openProceeds()
+        .accountsPartial({ proceeds: proceedsPda, seller: seller.publicKey })
+        .signers([seller])
+        .rpc();
+    });
+
+    async function buyEscrowedListing(proceeds: PublicKey | null) {
+      const buyer = Keypair.generate();
+      await airdrop(buyer.publicKey);
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+        escrowProceeds: true,
+      });
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+          proceeds,
+        })
+        .signers([buyer])
+        .rpc();
+    }
+
+    it("requires the Proceeds account for an escrowed listing", async () => {
+      try {
+        await buyEscrowedListing(null);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidProceedsAccount");
+      }
+    });
+
+    it("credits proceeds to the PDA and lets the seller claim them", async () => {
+      const heldBefore = await provider.connection.getBalance(proceedsPda);
+      await buyEscrowedListing(proceedsPda);
+
+      // Proceeds + legacy royalty are held for the seller
+      const fee = START_PRICE.muln(FEE_BPS).divn(10000);
+      const held = (await provider.connection.getBalance(proceedsPda)) - heldBefore;
+      assert.equal(held.toString(), START_PRICE.sub(fee).toString());
+
+      const sellerBefore = await provider.connection.getBalance(seller.publicKey);
+      await program.methods
+        .claimProceeds()
+        .accountsPartial({ proceeds: proceedsPda, seller: seller.publicKey })
+        .signers([seller])
+        .rpc();
+      const sellerAfter = await provider.connection.getBalance(seller.publicKey);
+      assert.isTrue(sellerAfter - sellerBefore > held - 10_000); // minus tx fee
+      assert.equal(await provider.connection.getBalance(proceedsPda), heldBefore);
+    });
+  });
+
+  // ============================================
+  // Split Payment Tests
+  // ============================================
+
+  describe("Split Payments", () => {
+    const TOKEN_AMOUNT = new BN(1_000_000);
+    let paymentMint: PublicKey;
+
+    before(async () => {
+      paymentMint = await createMint(
+        provider.connection,
+        configAuthority,
+        configAuthority.publicKey,
+        null,
+        6
+      );
+      await program.methods
+        .addPaymentMint()
+        .accountsPartial({ paymentMint, authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    async function splitListing() {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+      const listing = await setupListing(seller, {
+        splitPayment: { paymentMint, tokenAmount: TOKEN_AMOUNT, paymentDecimals: 6 },
+      });
+
+      const buyerTokens = await createAssociatedTokenAccount(
+        provider.connection,
+        buyer,
+        paymentMint,
+        buyer.publicKey
+      );
+      await mintTo(
+        provider.connection,
+        configAuthority,
+        paymentMint,
+        buyerTokens,
+        configAuthority,
+        TOKEN_AMOUNT.toNumber()
+      );
+      const sellerTokens = await createAssociatedTokenAccount(
+        provider.connection,
+        seller,
+        paymentMint,
+        seller.publicKey
+      );
+      const { feeRecipient } = await program.account.config.fetch(getConfigPDA()[0]);
+      const feeTokens = await getAssociatedTokenAddress(paymentMint, feeRecipient);
+      if (!(await provider.connection.getAccountInfo(feeTokens))) {
+        await createAssociatedTokenAccount(provider.connection, buyer, paymentMint, feeRecipient);
+      }
+
+      return { seller, buyer, buyerTokens, sellerTokens, feeRecipient, feeTokens, ...listing };
+    }
+
+    it("charges both legs and splits the fee on each", async () => {
+      const {
+        seller,
+        buyer,
+        buyerTokens,
+        sellerTokens,
+        feeRecipient,
+        feeTokens,
+        mint,
+        userVaultPda,
+        vaultAta,
+        listingPda,
+      } = await splitListing();
+      const feeTokensBefore = (await getAccount(provider.connection, feeTokens)).amount;
+      const feeSolBefore = await provider.connection.getBalance(feeRecipient);
+
+      const builder = program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          feeRecipient,
+          paymentMint,
+          buyerPaymentAccount: buyerTokens,
+          sellerPaymentAccount: sellerTokens,
+          feeRecipientPaymentAccount: feeTokens,
+        })
+        .signers([buyer]);
+      const sim = await builder.simulate();
+      const sale = sim.events.find((e) => e.name === "sale").data;
+      assert.equal(sale.paymentMint.toString(), paymentMint.toString());
+      assert.equal(sale.tokenPrice.toString(), TOKEN_AMOUNT.toString());
+      await builder.rpc();
+
+      // Token leg: fee to the fee recipient, the rest (with the legacy
+      // royalty) to the seller, all of it from the buyer
+      const tokenFee = TOKEN_AMOUNT.muln(FEE_BPS).divn(10000);
+      assert.equal(sale.tokenMarketplaceFee.toString(), tokenFee.toString());
+      const feeTokensAfter = (await getAccount(provider.connection, feeTokens)).amount;
+      assert.equal((feeTokensAfter - feeTokensBefore).toString(), tokenFee.toString());
+      const sellerAccount = await getAccount(provider.connection, sellerTokens);
+      assert.equal(sellerAccount.amount.toString(), TOKEN_AMOUNT.sub(tokenFee).toString());
+      const buyerAccount = await getAccount(provider.connection, buyerTokens);
+      assert.equal(buyerAccount.amount.toString(), "0");
+
+      // SOL leg is charged as usual
+      const solFee = START_PRICE.muln(FEE_BPS).divn(10000);
+      const feeSolAfter = await provider.connection.getBalance(feeRecipient);
+      assert.equal((feeSolAfter - feeSolBefore).toString(), solFee.toString());
+    });
+
+    it("rejects a split-payment purchase without the token accounts", async () => {
+      const { seller, buyer, feeRecipient, mint, userVaultPda, vaultAta, listingPda } =
+        await splitListing();
+
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            feeRecipient,
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidPaymentAccounts");
+      }
+    });
+
+    it("pays a two-creator royalty in the payment token", async () => {
+      const SELLER_FEE_BPS = 500;
+      const creatorA = Keypair.generate();
+      const creatorB = Keypair.generate();
+      await airdrop(creatorA.publicKey);
+      await airdrop(creatorB.publicKey);
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      await program.methods
+        .updateConfig(configUpdate({ royaltyDefault: { enforce: {} } }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      try {
+        const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+          splitPayment: { paymentMint, tokenAmount: TOKEN_AMOUNT, paymentDecimals: 6 },
+          metadata: {
+            sellerFeeBps: SELLER_FEE_BPS,
+            creators: [
+              { address: creatorA.publicKey, share: 60 },
+              { address: creatorB.publicKey, share: 40 },
+            ],
+            primarySaleHappened: true,
+          },
+        });
+
+        const buyerTokens = await createAssociatedTokenAccount(
+          provider.connection,
+          buyer,
+          paymentMint,
+          buyer.publicKey
+        );
+        await mintTo(
+          provider.connection,
+          configAuthority,
+          paymentMint,
+          buyerTokens,
+          configAuthority,
+          TOKEN_AMOUNT.toNumber()
+        );
+        const sellerTokens = await createAssociatedTokenAccount(
+          provider.connection,
+          seller,
+          paymentMint,
+          seller.publicKey
+        );
+        const [configPda] = getConfigPDA();
+        const config = await program.account.config.fetch(configPda);
+        const feeTokens = await getAssociatedTokenAddress(paymentMint, config.feeRecipient);
+        if (!(await provider.connection.getAccountInfo(feeTokens))) {
+          await createAssociatedTokenAccount(provider.connection, buyer, paymentMint, config.feeRecipient);
+        }
+        // Creators' token accounts must already exist; the buyer opens them
+        const creatorTokens = [];
+        for (const creator of [creatorA, creatorB]) {
+          creatorTokens.push(
+            await createAssociatedTokenAccount(provider.connection, buyer, paymentMint, creator.publicKey)
+          );
+        }
+
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            config: configPda,
+            feeRecipient: config.feeRecipient,
+            metadata: getMetadataPDA(mint),
+            paymentMint,
+            buyerPaymentAccount: buyerTokens,
+            sellerPaymentAccount: sellerTokens,
+            feeRecipientPaymentAccount: feeTokens,
+          })
+          // SOL royalty to the creators' wallets, then the token royalty to
+          // their token accounts
+          .remainingAccounts([
+            { pubkey: creatorA.publicKey, isSigner: false, isWritable: true },
+            { pubkey: creatorB.publicKey, isSigner: false, isWritable: true },
+            ...creatorTokens.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+          ])
+          .signers([buyer])
+          .rpc();
+
+        const royalty = TOKEN_AMOUNT.muln(SELLER_FEE_BPS).divn(10_000);
+        const shareA = royalty.muln(60).divn(100);
+        const tokensA = (await getAccount(provider.connection, creatorTokens[0])).amount;
+        const tokensB = (await getAccount(provider.connection, creatorTokens[1])).amount;
+        assert.equal(tokensA.toString(), shareA.toString());
+        assert.equal(tokensB.toString(), royalty.sub(shareA).toString());
+
+        // The seller gets the rest after the fee
+        const sellerAmount = (await getAccount(provider.connection, sellerTokens)).amount;
+        assert.equal(
+          (tokensA + tokensB + sellerAmount).toString(),
+          TOKEN_AMOUNT.sub(TOKEN_AMOUNT.muln(FEE_BPS).divn(10_000)).toString()
+        );
+      } finally {
+        await program.methods
+          .updateConfig(configUpdate({ royaltyDefault: { skip: {} } }))
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+      }
+    });
+
+    it("rejects payment_decimals that don't match the payment mint", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, {
+          splitPayment: { paymentMint, tokenAmount: TOKEN_AMOUNT, paymentDecimals: 9 },
+        });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidPaymentDecimals");
+      }
+    });
+
+    it("only lists in payment mints on the allowlist", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+      const spoofMint = await createMint(provider.connection, seller, seller.publicKey, null, 6);
+
+      try {
+        await setupListing(seller, {
+          splitPayment: { paymentMint: spoofMint, tokenAmount: TOKEN_AMOUNT, paymentDecimals: 6 },
+        });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnsupportedPaymentMint");
+      }
+    });
+
+    it("stops sales in a mint once it leaves the allowlist", async () => {
+      const {
+        seller,
+        buyer,
+        buyerTokens,
+        sellerTokens,
+        feeRecipient,
+        feeTokens,
+        mint,
+        userVaultPda,
+        vaultAta,
+        listingPda,
+      } = await splitListing();
+
+      await program.methods
+        .removePaymentMint(paymentMint)
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            feeRecipient,
+            paymentMint,
+            buyerPaymentAccount: buyerTokens,
+            sellerPaymentAccount: sellerTokens,
+            feeRecipientPaymentAccount: feeTokens,
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnsupportedPaymentMint");
+      } finally {
+        await program.methods
+          .addPaymentMint()
+          .accountsPartial({ paymentMint, authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+      }
+    });
+
+    it("only lets the authority manage the allowlist", async () => {
+      const attacker = Keypair.generate();
+      await airdrop(attacker.publicKey);
+      const spoofMint = await createMint(provider.connection, attacker, attacker.publicKey, null, 6);
+
+      try {
+        await program.methods
+          .addPaymentMint()
+          .accountsPartial({ paymentMint: spoofMint, authority: attacker.publicKey })
+          .signers([attacker])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedAuthority");
+      }
+
+      try {
+        await program.methods
+          .removePaymentMint(paymentMint)
+          .accountsPartial({ authority: attacker.publicKey })
+          .signers([attacker])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("UnauthorizedAuthority");
+      }
+    });
+  });
+
+  // ============================================
+  // OTC Settlement Tests
+  // ============================================
+
+  describe("OTC Settlement", () => {
+    const OTC_PRICE = new BN(1.5 * LAMPORTS_PER_SOL);
+
+    async function depositNft(seller: Keypair) {
+      const nft = await createNFT(seller);
+      const [userVaultPda] = getUserVaultPDA(seller.publicKey, nft.mint);
+      const vaultAta = await getAssociatedTokenAddress(nft.mint, userVaultPda, true);
+      await program.methods
+        .initializeUserVault()
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          owner: seller.publicKey,
+          ownerTokenAccount: nft.tokenAccount,
+          vaultAta: vaultAta,
+          mint: nft.mint,
+        })
+        .signers([seller])
+        .rpc();
+      return { mint: nft.mint, userVaultPda, vaultAta };
+    }
+
+    async function settle(
+      seller: Keypair,
+      buyer: Keypair,
+      mint: PublicKey,
+      userVaultPda: PublicKey,
+      vaultAta: PublicKey
+    ) {
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      await program.methods
+        .settleOtc(OTC_PRICE)
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          listing: getListingPDA(seller.publicKey, mint)[0],
+          seller: seller.publicKey,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+          oracle: null,
+        })
+        .signers([seller, buyer])
+        .rpc();
+    }
+
+    it("settles an agreed price with both signatures", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+      const { mint, userVaultPda, vaultAta } = await depositNft(seller);
+
+      const [statsPda] = getStatsPDA();
+      const statsBefore = await program.account.stats.fetch(statsPda);
+
+      await settle(seller, buyer, mint, userVaultPda, vaultAta);
+
+      const buyerAta = await getAssociatedTokenAddress(mint, buyer.publicKey);
+      const buyerToken = await getAccount(provider.connection, buyerAta);
+      assert.equal(buyerToken.amount.toString(), "1");
+
+      // No listing was ever created, so the active count is untouched
+      const statsAfter = await program.account.stats.fetch(statsPda);
+      assert.equal(
+        statsAfter.totalSales.toString(),
+        statsBefore.totalSales.addn(1).toString()
+      );
+      assert.equal(
+        statsAfter.activeListings.toString(),
+        statsBefore.activeListings.toString()
+      );
+    });
+
+    it("rejects settlement while the NFT is listed", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+      const { mint, userVaultPda, vaultAta } = await setupListing(seller);
+
+      try {
+        await settle(seller, buyer, mint, userVaultPda, vaultAta);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("VaultLocked");
+      }
+    });
+  });
+
+  // ============================================
+  // Dust Fee Policy Tests
+  // ============================================
+
+  describe("Dust Fee Policy", () => {
+    const ONE_LAMPORT = new BN(1);
+
+    async function buyOneLamportListing(): Promise<number> {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+        startPrice: ONE_LAMPORT,
+        minPrice: ONE_LAMPORT,
+      });
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+      const feeBefore = await provider.connection.getBalance(config.feeRecipient);
+
+      await program.methods
+        .buyNow(new BN(1))
+        .accountsPartial({
+          listing: listingPda,
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
+          mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+        })
+        .signers([buyer])
+        .rpc();
+
+      const feeAfter = await provider.connection.getBalance(config.feeRecipient);
+      return feeAfter - feeBefore;
+    }
+
+    async function setDustPolicy(policy: object, minFee: number) {
+      await program.methods
+        .updateConfig(configUpdate({ dustFeePolicy: policy, minFeeLamports: new BN(minFee) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    }
+
+    after(async () => {
+      await setDustPolicy({ proportional: {} }, 0);
+    });
+
+    it("charges nothing on a 1 lamport sale by default", async () => {
+      assert.equal(await buyOneLamportListing(), 0);
+    });
+
+    it("charges the minimum fee on a 1 lamport sale", async () => {
+      await setDustPolicy({ minimumFee: {} }, 1);
+      assert.equal(await buyOneLamportListing(), 1);
+    });
+
+    it("waives sub-minimum fees when exempt below minimum", async () => {
+      await setDustPolicy({ exemptBelowMinimum: {} }, 1000);
+      assert.equal(await buyOneLamportListing(), 0);
+    });
+  });
+
+  // ============================================
+  // Oracle-Tracked Floor Tests
+  // ============================================
+
+  describe("Oracle-Tracked Decay Floor", () => {
+    it("rejects a floor percentage on fixed-price listings", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { floorPct: 8000 });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidPrice");
+      }
+    });
+
+    it("requires the oracle account when buying", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+        priceType: { linearDecay: {} },
+        floorPct: 8000,
+        oracleFeed: Keypair.generate().publicKey,
+      });
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            config: configPda,
+            feeRecipient: config.feeRecipient,
+            oracle: null,
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingOracle");
+      }
+    });
+
+    it("requires an oracle feed on oracle-tracked listings", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { priceType: { linearDecay: {} }, floorPct: 8000 });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingOracle");
+      }
+    });
+
+    it("rejects an oracle other than the listing's feed", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
 
-      const listing = await program.account.listing.fetch(listingPda);
-      assert.deepEqual(listing.priceConfig.priceType, { linearDecay: {} });
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+        priceType: { linearDecay: {} },
+        floorPct: 8000,
+        oracleFeed: Keypair.generate().publicKey,
+      });
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      try {
+        await program.methods
+          .buyNow(new BN(1))
+          .accountsPartial({
+            listing: listingPda,
+            userVault: userVaultPda,
+            vaultPda: userVaultPda,
+            vaultAta: vaultAta,
+            buyer: buyer.publicKey,
+            buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+            seller: seller.publicKey,
+            mint: mint,
+            config: configPda,
+            feeRecipient: config.feeRecipient,
+            oracle: Keypair.generate().publicKey, // unrelated feed
+          })
+          .signers([buyer])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidOracleAccount");
+      }
     });
   });
 
   // ============================================
-  // Buy Flow Tests
+  // USD Floor Tests
   // ============================================
 
-  describe("Buy NFT", () => {
-    let seller: Keypair;
-    let buyer: Keypair;
-    let feeRecipient: PublicKey;
-    let mint: PublicKey;
-    let sellerTokenAccount: PublicKey;
-    let configPda: PublicKey;
-    let userVaultPda: PublicKey;
-    let vaultAta: PublicKey;
-    let listingPda: PublicKey;
+  describe("USD Floor", () => {
+    const MIN_USD = new BN(50_000_000); // $50 in micro-USD
+    const solUsdFeed = Keypair.generate().publicKey;
 
-    before(async () => {
-      seller = Keypair.generate();
-      buyer = Keypair.generate();
+    after(async () => {
+      await program.methods
+        .updateConfig(configUpdate({ solUsdFeed: PublicKey.default }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+    });
+
+    async function buyWithSolUsdOracle(solUsdOracle: PublicKey | null) {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
       await airdrop(seller.publicKey);
       await airdrop(buyer.publicKey);
 
-      const nft = await createNFT(seller);
-      mint = nft.mint;
-      sellerTokenAccount = nft.tokenAccount;
-
-      [configPda] = getConfigPDA();
-      [userVaultPda] = getUserVaultPDA(seller.publicKey, mint);
-      [listingPda] = getListingPDA(seller.publicKey, mint);
-      vaultAta = await getAssociatedTokenAddress(mint, userVaultPda, true);
-
-      // Get fee recipient from config
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller, {
+        minUsdValue: MIN_USD,
+      });
+      const [configPda] = getConfigPDA();
       const config = await program.account.config.fetch(configPda);
-      feeRecipient = config.feeRecipient;
 
-      // Create vault
       await program.methods
-        .initializeUserVault()
+        .buyNow(new BN(1))
         .accountsPartial({
+          listing: listingPda,
           userVault: userVaultPda,
           vaultPda: userVaultPda,
-          owner: seller.publicKey,
-          ownerTokenAccount: sellerTokenAccount,
           vaultAta: vaultAta,
+          buyer: buyer.publicKey,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
+          seller: seller.publicKey,
           mint: mint,
+          config: configPda,
+          feeRecipient: config.feeRecipient,
+          solUsdOracle,
         })
-        .signers([seller])
+        .signers([buyer])
         .rpc();
+    }
 
-      // Create listing
-      const now = Math.floor(Date.now() / 1000);
+    it("rejects a USD floor while no SOL/USD feed is configured", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      try {
+        await setupListing(seller, { minUsdValue: MIN_USD });
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingOracle");
+      }
+    });
+
+    it("requires the SOL/USD oracle when buying", async () => {
       await program.methods
-        .createListing(
-          { fixed: {} },
-          START_PRICE,
-          MIN_PRICE,
-          new BN(now),
-          DURATION,
-          null,
-          null,
-          null
-        )
-        .accountsPartial({
-          listing: listingPda,
-          userVault: userVaultPda,
-          vaultAta: vaultAta,
-          seller: seller.publicKey,
-          mint: mint,
-        })
-        .signers([seller])
+        .updateConfig(configUpdate({ solUsdFeed }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
         .rpc();
+
+      try {
+        await buyWithSolUsdOracle(null);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("MissingOracle");
+      }
     });
 
-    it("buys NFT and transfers to buyer", async () => {
-      const buyerAta = await getAssociatedTokenAddress(mint, buyer.publicKey);
-      const sellerBalanceBefore = await provider.connection.getBalance(seller.publicKey);
+    it("rejects an oracle other than the configured SOL/USD feed", async () => {
+      try {
+        await buyWithSolUsdOracle(Keypair.generate().publicKey);
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("InvalidOracleAccount");
+      }
+    });
+  });
 
-      await program.methods
-        .buyNow()
+  // ============================================
+  // Dry Run Buy Tests
+  // ============================================
+
+  describe("Dry Run Buy", () => {
+    it("quotes exactly what buy_now charges", async () => {
+      const seller = Keypair.generate();
+      const buyer = Keypair.generate();
+      await airdrop(seller.publicKey);
+      await airdrop(buyer.publicKey);
+
+      const { mint, userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+      const [configPda] = getConfigPDA();
+      const config = await program.account.config.fetch(configPda);
+
+      const previewSim = await program.methods
+        .dryRunBuy(new BN(1))
+        .accountsPartial({ listing: listingPda, userVault: userVaultPda, vaultAta })
+        .simulate();
+      const preview = previewSim.events.find((e) => e.name === "buyPreview").data;
+
+      const saleSim = await program.methods
+        .buyNow(new BN(1))
         .accountsPartial({
           listing: listingPda,
           userVault: userVaultPda,
           vaultPda: userVaultPda,
           vaultAta: vaultAta,
           buyer: buyer.publicKey,
-          buyerAta: buyerAta,
+          buyerAta: await getAssociatedTokenAddress(mint, buyer.publicKey),
           seller: seller.publicKey,
           mint: mint,
           config: configPda,
-          feeRecipient: feeRecipient,
+          feeRecipient: config.feeRecipient,
         })
         .signers([buyer])
-        .rpc();
+        .simulate();
+      const sale = saleSim.events.find((e) => e.name === "sale").data;
 
-      // Verify buyer received NFT
-      const buyerAccount = await getAccount(provider.connection, buyerAta);
-      assert.equal(buyerAccount.amount.toString(), "1");
+      assert.isTrue(preview.buyable);
+      assert.equal(preview.price.toString(), sale.price.toString());
+      assert.equal(preview.total.toString(), sale.price.toString());
+      assert.equal(preview.marketplaceFee.toString(), sale.marketplaceFee.toString());
+      assert.equal(preview.royalty.toString(), sale.royalty.toString());
+      assert.equal(preview.communityAmount.toString(), sale.communityAmount.toString());
+    });
 
-      // Verify seller received payment
-      const sellerBalanceAfter = await provider.connection.getBalance(seller.publicKey);
-      assert.isTrue(sellerBalanceAfter > sellerBalanceBefore);
+    it("reports an unbuyable quantity instead of failing", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
 
-      // Verify listing closed
-      try {
-        await program.account.listing.fetch(listingPda);
-        assert.fail("Listing should be closed");
-      } catch (err) {
-        expect(err.toString().toLowerCase()).to.satisfy(
-          (s: string) => s.includes("account does not exist") || s.includes("could not find")
-        );
-      }
+      const { userVaultPda, vaultAta, listingPda } = await setupListing(seller);
+
+      const sim = await program.methods
+        .dryRunBuy(new BN(2))
+        .accountsPartial({ listing: listingPda, userVault: userVaultPda, vaultAta })
+        .simulate();
+      const preview = sim.events.find((e) => e.name === "buyPreview").data;
+
+      assert.isFalse(preview.buyable);
+      assert.equal(preview.price.toString(), "0");
     });
   });
 
@@ -474,6 +5104,19 @@ describe("Verity Escrowless NFT Marketplace", () => {
         .rpc();
     });
 
+    it("reports an unlisted vault as unlocked", async () => {
+      const sim = await program.methods
+        .checkVaultStatus()
+        .accountsPartial({
+          userVault: userVaultPda,
+          listing: getListingPDA(owner.publicKey, mint)[0],
+        })
+        .simulate();
+      const status = sim.events.find((e) => e.name === "vaultStatus").data;
+      assert.isFalse(status.isLocked);
+      assert.isNull(status.activeListing);
+    });
+
     it("withdraws NFT from vault", async () => {
       await program.methods
         .withdrawFromVault()
@@ -483,6 +5126,7 @@ describe("Verity Escrowless NFT Marketplace", () => {
           vaultAta: vaultAta,
           owner: owner.publicKey,
           ownerTokenAccount: ownerTokenAccount,
+          listing: getListingPDA(owner.publicKey, mint)[0],
         })
         .signers([owner])
         .rpc();
@@ -501,6 +5145,44 @@ describe("Verity Escrowless NFT Marketplace", () => {
         );
       }
     });
+
+    it("rescues a vault into the owner's ATA with emergency_withdraw", async () => {
+      const nft = await createNFT(owner);
+      const [rescueVaultPda] = getUserVaultPDA(owner.publicKey, nft.mint);
+      const rescueVaultAta = await getAssociatedTokenAddress(nft.mint, rescueVaultPda, true);
+      await program.methods
+        .initializeUserVault()
+        .accountsPartial({
+          userVault: rescueVaultPda,
+          vaultPda: rescueVaultPda,
+          owner: owner.publicKey,
+          ownerTokenAccount: nft.tokenAccount,
+          vaultAta: rescueVaultAta,
+          mint: nft.mint,
+        })
+        .signers([owner])
+        .rpc();
+
+      // The source account was a plain token account; the rescue creates the ATA
+      const destination = await getAssociatedTokenAddress(nft.mint, owner.publicKey);
+      await program.methods
+        .emergencyWithdraw()
+        .accountsPartial({
+          userVault: rescueVaultPda,
+          vaultPda: rescueVaultPda,
+          vaultAta: rescueVaultAta,
+          owner: owner.publicKey,
+          destination,
+          mint: nft.mint,
+          listing: getListingPDA(owner.publicKey, nft.mint)[0],
+        })
+        .signers([owner])
+        .rpc();
+
+      const destinationAccount = await getAccount(provider.connection, destination);
+      assert.equal(destinationAccount.amount.toString(), "1");
+      assert.isNull(await provider.connection.getAccountInfo(rescueVaultPda));
+    });
   });
 
   // ============================================
@@ -540,7 +5222,7 @@ describe("Verity Escrowless NFT Marketplace", () => {
 
       const now = Math.floor(Date.now() / 1000);
       await program.methods
-        .createListing({ fixed: {} }, START_PRICE, MIN_PRICE, new BN(now), DURATION, null, null, null)
+        .createListing({ fixed: {} }, START_PRICE, MIN_PRICE, new BN(now), DURATION, null, null, null, null, null, null, null, null, null, false, null, null, null, null, null)
         .accountsPartial({
           listing: listingPda,
           userVault: userVaultPda,
@@ -554,7 +5236,7 @@ describe("Verity Escrowless NFT Marketplace", () => {
       // Buyer 1 purchases
       const buyer1Ata = await getAssociatedTokenAddress(nft.mint, buyer1.publicKey);
       await program.methods
-        .buyNow()
+        .buyNow(new BN(1))
         .accountsPartial({
           listing: listingPda,
           userVault: userVaultPda,
@@ -574,7 +5256,7 @@ describe("Verity Escrowless NFT Marketplace", () => {
       const buyer2Ata = await getAssociatedTokenAddress(nft.mint, buyer2.publicKey);
       try {
         await program.methods
-          .buyNow()
+          .buyNow(new BN(1))
           .accountsPartial({
             listing: listingPda,
             userVault: userVaultPda,
@@ -624,7 +5306,7 @@ describe("Verity Escrowless NFT Marketplace", () => {
 
       const now = Math.floor(Date.now() / 1000);
       await program.methods
-        .createListing({ fixed: {} }, START_PRICE, MIN_PRICE, new BN(now), DURATION, null, null, null)
+        .createListing({ fixed: {} }, START_PRICE, MIN_PRICE, new BN(now), DURATION, null, null, null, null, null, null, null, null, null, false, null, null, null, null, null)
         .accountsPartial({
           listing: listingPda,
           userVault: userVaultPda,
@@ -695,6 +5377,7 @@ describe("Verity Escrowless NFT Marketplace", () => {
             vaultAta: vaultAta,
             owner: attacker.publicKey,
             ownerTokenAccount: attackerTokenAccount,
+            listing: getListingPDA(attacker.publicKey, nft.mint)[0],
           })
           .signers([attacker])
           .rpc();
@@ -706,5 +5389,154 @@ describe("Verity Escrowless NFT Marketplace", () => {
         );
       }
     });
+
+    it("rejects a listing whose mint doesn't match the vault", async () => {
+      const seller = Keypair.generate();
+      await airdrop(seller.publicKey);
+
+      const nft = await createNFT(seller);
+      const other = await createNFT(seller);
+      const [userVaultPda] = getUserVaultPDA(seller.publicKey, nft.mint);
+      const vaultAta = await getAssociatedTokenAddress(nft.mint, userVaultPda, true);
+
+      await program.methods
+        .initializeUserVault()
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          owner: seller.publicKey,
+          ownerTokenAccount: nft.tokenAccount,
+          vaultAta: vaultAta,
+          mint: nft.mint,
+        })
+        .signers([seller])
+        .rpc();
+
+      const now = Math.floor(Date.now() / 1000);
+      try {
+        await program.methods
+          .createListing(
+            { fixed: {} } as any,
+            START_PRICE,
+            MIN_PRICE,
+            new BN(now),
+            DURATION,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            null,
+            false,
+            null,
+            null,
+            null,
+            null,
+            null
+          )
+          .accountsPartial({
+            listing: getListingPDA(seller.publicKey, other.mint)[0],
+            userVault: userVaultPda,
+            vaultAta: vaultAta,
+            seller: seller.publicKey,
+            mint: other.mint,
+          })
+          .signers([seller])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        // The vault seeds are checked before its stored mint
+        expect(err.toString()).to.satisfy(
+          (s: string) => s.includes("ConstraintSeeds") || s.includes("VaultMismatch")
+        );
+      }
+    });
+
+    it("rejects a vault_pda derived with a non-canonical bump", async () => {
+      const owner = Keypair.generate();
+      await airdrop(owner.publicKey);
+
+      const nft = await createNFT(owner);
+      const [userVaultPda, canonicalBump] = getUserVaultPDA(owner.publicKey, nft.mint);
+      const vaultAta = await getAssociatedTokenAddress(nft.mint, userVaultPda, true);
+
+      await program.methods
+        .initializeUserVault()
+        .accountsPartial({
+          userVault: userVaultPda,
+          vaultPda: userVaultPda,
+          owner: owner.publicKey,
+          ownerTokenAccount: nft.tokenAccount,
+          vaultAta: vaultAta,
+          mint: nft.mint,
+        })
+        .signers([owner])
+        .rpc();
+
+      // Same seeds, next valid bump below the canonical one
+      let otherPda: PublicKey | null = null;
+      for (let bump = canonicalBump - 1; bump >= 0 && !otherPda; bump--) {
+        try {
+          otherPda = PublicKey.createProgramAddressSync(
+            [
+              Buffer.from("user_vault"),
+              owner.publicKey.toBuffer(),
+              nft.mint.toBuffer(),
+              Buffer.from([bump]),
+            ],
+            PROGRAM_ID
+          );
+        } catch {
+          // On-curve for this bump, try the next one
+        }
+      }
+
+      try {
+        await program.methods
+          .withdrawFromVault()
+          .accountsPartial({
+            userVault: userVaultPda,
+            vaultPda: otherPda,
+            vaultAta: vaultAta,
+            owner: owner.publicKey,
+            ownerTokenAccount: nft.tokenAccount,
+            listing: getListingPDA(owner.publicKey, nft.mint)[0],
+          })
+          .signers([owner])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("ConstraintSeeds");
+      }
+    });
+
+    it("refuses to close the config while listings are active", async () => {
+      const now = Math.floor(Date.now() / 1000);
+      await program.methods
+        .updateConfig(configUpdate({ emergencyUntil: new BN(now + 3600) }))
+        .accountsPartial({ authority: configAuthority.publicKey })
+        .signers([configAuthority])
+        .rpc();
+
+      try {
+        await program.methods
+          .closeConfig()
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+        assert.fail("Should have failed");
+      } catch (err) {
+        expect(err.toString()).to.include("ActiveListingsRemain");
+      } finally {
+        await program.methods
+          .updateConfig(configUpdate({ emergencyUntil: new BN(0) }))
+          .accountsPartial({ authority: configAuthority.publicKey })
+          .signers([configAuthority])
+          .rpc();
+      }
+    });
   });
 });
\ No newline at end of file